@@ -1,16 +1,22 @@
 // TODO remove suppression for dead code warning
 #![allow(dead_code)]
 
-use super::contig::{Contig, ContigEnumerator, ContigNeighbourhoodEnumerator};
+use super::contig::{CompactionPolicy, Contig, ContigEnumerator, ContigNeighbourhoodEnumerator};
+use super::grid::Grid;
 use super::multi_iterator::MultiIterator;
-use super::neighbourhood::{Neighbourhood, N_SIZE};
+use super::neighbourhood::{Neighbourhood, Window};
+use super::topology::Topology;
 use num::cast::AsPrimitive;
 use num::FromPrimitive;
+use num::Integer;
 use num::One;
+use num::Zero;
 use std::cmp::PartialOrd;
 use std::fmt::Debug;
+use std::iter::Peekable;
 use std::ops::Add;
 use std::ops::AddAssign;
+use std::ops::Range;
 use std::ops::Sub;
 use std::ops::SubAssign;
 
@@ -23,12 +29,25 @@ where
     pub y: T,
 }
 
-/// nonempty 2D array of Contigs, organised in rows, or None
-#[derive(Debug)]
-pub struct CartesianContig<Idx, T>(Contig<Idx, Contig<Idx, T>>)
+/// nonempty 2D array of Contigs, organised in rows, or None, with any automatic compaction
+#[derive(Clone, Debug)]
+pub struct CartesianContig<Idx, T>(Contig<Idx, Contig<Idx, T>>, Option<AutoCompaction<Idx, T>>)
 where
     Idx: Copy + Debug;
 
+/// state for automatic compaction, which captures how to compact a row, so that updates
+/// need not require `T: Zero`
+#[derive(Clone, Debug)]
+struct AutoCompaction<Idx, T>
+where
+    Idx: Copy + Debug,
+{
+    policy: CompactionPolicy,
+    compact: fn(&mut Contig<Idx, T>, &CompactionPolicy),
+    /// updates since the last compaction
+    updates: usize,
+}
+
 impl<Idx, T> CartesianContig<Idx, T>
 where
     T: Debug,
@@ -47,18 +66,90 @@ where
 {
     /// create almost empty, with a single cell
     pub fn new(x: Idx, y: Idx, item: T) -> CartesianContig<Idx, T> {
-        CartesianContig(Contig::new(y, Contig::new(x, item)))
+        CartesianContig(Contig::new(y, Contig::new(x, item)), None)
     }
 
     pub fn get(&self, x: Idx, y: Idx) -> Option<&T> {
         self.0.get(y).and_then(|row| row.get(x))
     }
 
+    /// the row at `y`, if any
+    pub fn row(&self, y: Idx) -> Option<&Contig<Idx, T>> {
+        self.0.get(y)
+    }
+
+    /// set the item at `x`, `y`
+    ///
+    /// Where automatic compaction is due this may leave this empty, which the caller must
+    /// handle.
     pub fn set(&mut self, x: Idx, y: Idx, item: T) {
         match self.0.get_mut(y) {
             Some(row) => row.set(x, item),
             None => self.0.set(y, Contig::new(x, item)),
         }
+        self.updated();
+    }
+
+    /// remove the item at `x`, `y`, and its row if that becomes empty
+    ///
+    /// Removing the last item leaves this empty, which the caller must handle.
+    pub fn remove(&mut self, x: Idx, y: Idx) -> Option<T> {
+        let row = self.0.get_mut(y)?;
+        let item = row.remove(x);
+        if row.is_empty() {
+            self.0.remove(y);
+        }
+        self.updated();
+        item
+    }
+
+    /// compact each row as `Contig::compact`, removing any rows left empty
+    ///
+    /// Compacting away every item leaves this empty, which the caller must handle.
+    pub fn compact(&mut self, policy: &CompactionPolicy)
+    where
+        T: Zero,
+    {
+        self.compact_rows(policy, Contig::compact);
+    }
+
+    /// compact automatically after every `policy.update_threshold` updates, or never if None
+    pub fn set_auto_compaction(&mut self, policy: Option<CompactionPolicy>)
+    where
+        T: Zero,
+    {
+        self.1 = policy.map(|policy| AutoCompaction {
+            policy,
+            compact: Contig::compact,
+            updates: 0,
+        });
+    }
+
+    /// count an update, compacting if that's due
+    fn updated(&mut self) {
+        if let Some(auto) = &mut self.1 {
+            auto.updates += 1;
+            if auto.updates >= auto.policy.update_threshold {
+                auto.updates = 0;
+                let (policy, compact) = (auto.policy, auto.compact);
+                self.compact_rows(&policy, compact);
+            }
+        }
+    }
+
+    fn compact_rows(
+        &mut self,
+        policy: &CompactionPolicy,
+        compact: fn(&mut Contig<Idx, T>, &CompactionPolicy),
+    ) {
+        self.0.retain_mut(|_, row| {
+            compact(row, policy);
+            !row.is_empty()
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 
     pub fn origin(&self) -> Coordinate<Idx> {
@@ -74,52 +165,146 @@ where
         }
     }
 
-    pub fn rows_enumerator(&self) -> ContigEnumerator<Idx, Contig<Idx, T>> {
+    pub fn rows_enumerator(&self) -> ContigEnumerator<'_, Idx, Contig<Idx, T>> {
         self.0.enumerator()
     }
 
-    pub fn neighbourhood_enumerator(&self) -> CartesianContigNeighbourhoodEnumerator<Idx, T> {
+    pub fn neighbourhood_enumerator(&self) -> CartesianContigNeighbourhoodEnumerator<'_, Idx, T> {
         CartesianContigNeighbourhoodEnumerator::new(self)
     }
+
+    /// enumerate neighbourhoods of radius `R` of whole rows
+    pub fn row_neighbourhood_enumerator_with_radius<const R: usize>(
+        &self,
+    ) -> ContigNeighbourhoodEnumerator<'_, Idx, Contig<Idx, T>, R> {
+        self.0.neighbourhood_enumerator_with_radius::<R>()
+    }
+
+    /// enumerate neighbourhoods of radius `R`, that is, `2R+1` square
+    pub fn neighbourhood_enumerator_with_radius<const R: usize>(
+        &self,
+    ) -> CartesianContigNeighbourhoodEnumerator<'_, Idx, T, R> {
+        CartesianContigNeighbourhoodEnumerator::new(self)
+    }
+
+    /// enumerate neighbourhoods of cells on `topology`, where the neighbours across an edge
+    /// are those the edge is joined to, if any
+    ///
+    /// Items outside the bounds of `topology` are ignored.
+    pub fn neighbourhood_enumerator_with_topology(
+        &self,
+        topology: Topology,
+    ) -> CartesianTopologyNeighbourhoodEnumerator<'_, Idx, T>
+    where
+        Idx: Integer,
+    {
+        CartesianTopologyNeighbourhoodEnumerator::new(self, topology)
+    }
+
+    /// enumerate only the items within the rectangle of columns `xs` and rows `ys`, by row
+    pub fn window(&self, xs: Range<Idx>, ys: Range<Idx>) -> CartesianWindowEnumerator<'_, Idx, T> {
+        CartesianWindowEnumerator {
+            xs,
+            row_enumerator: self.0.range(ys),
+            row: None,
+        }
+    }
+}
+
+/// enumerator of items within a rectangle, with their coordinates
+pub struct CartesianWindowEnumerator<'a, Idx, T>
+where
+    Idx: Copy + Debug,
+{
+    xs: Range<Idx>,
+    row_enumerator: ContigEnumerator<'a, Idx, Contig<Idx, T>>,
+    /// the current row index and its enumerator
+    row: Option<(Idx, ContigEnumerator<'a, Idx, T>)>,
+}
+
+impl<'a, Idx, T> Iterator for CartesianWindowEnumerator<'a, Idx, T>
+where
+    Idx: Copy
+        + Default
+        + One
+        + FromPrimitive
+        + AsPrimitive<usize>
+        + Add<Output = Idx>
+        + Sub<Output = Idx>
+        + PartialOrd
+        + Ord
+        + AddAssign
+        + SubAssign
+        + Debug,
+{
+    type Item = (Coordinate<Idx>, &'a T);
+
+    fn next(&mut self) -> Option<(Coordinate<Idx>, &'a T)> {
+        loop {
+            if let Some((y, columns)) = &mut self.row {
+                if let Some((x, item)) = columns.next() {
+                    return Some((Coordinate { x, y: *y }, item));
+                }
+            }
+
+            let (y, row) = self.row_enumerator.next()?;
+            self.row = Some((y, row.range(self.xs.clone())));
+        }
+    }
 }
 
 #[derive(Eq, PartialEq, Debug)]
-pub struct CartesianNeighbourhood<Idx, T> {
-    i_row: Idx,
-    i_col: Idx,
-    items: [[Option<T>; N_SIZE]; N_SIZE], // first index is row
+pub struct CartesianNeighbourhood<Idx, T, const R: usize = 1> {
+    pub i_row: Idx,
+    pub i_col: Idx,
+    pub items: Window<Window<Option<T>, R>, R>, // first index is row
 }
 
-impl<Idx, T> CartesianNeighbourhood<Idx, T> {
+impl<Idx, T, const R: usize> CartesianNeighbourhood<Idx, T, R> {
     fn new(
         i_row: Idx,
         i_col: Idx,
-        nbhs: Vec<Option<Neighbourhood<Idx, T>>>,
-    ) -> CartesianNeighbourhood<Idx, T>
+        nbhs: Vec<Option<Neighbourhood<Idx, T, R>>>,
+    ) -> CartesianNeighbourhood<Idx, T, R>
     where
         T: Debug,
     {
+        let mut rows = nbhs.into_iter();
         CartesianNeighbourhood {
             i_row,
             i_col,
-            items: nbhs
-                .into_iter()
-                .map(|nbho| match nbho {
-                    Some(nbh) => nbh.items,
-                    None => [None, None, None],
-                })
-                .collect::<Vec<[Option<T>; N_SIZE]>>()
-                .try_into()
-                .unwrap(),
+            items: Window::from_fn(|_| match rows.next().flatten() {
+                Some(nbh) => nbh.items,
+                None => Window::from_fn(|_| None),
+            }),
         }
     }
 }
 
+impl<Idx, T, const R: usize> CartesianNeighbourhood<Idx, T, R>
+where
+    Idx: Copy + Integer,
+{
+    /// the neighbours of the focused item on `grid`, by row then column, where the radius
+    /// of `grid` must be at most `R`
+    pub fn neighbours(&self, grid: Grid) -> Vec<&Option<T>> {
+        assert!(grid.radius() <= R, "neighbourhood too small for {:?}", grid);
+        let even = (self.i_col + self.i_row).is_even();
+        grid.offsets(even)
+            .into_iter()
+            .map(|(dx, dy)| &self.items[(R as isize + dy) as usize][(R as isize + dx) as usize])
+            .collect()
+    }
+}
+
 /// the MultiIterator instantiated for CarteisianContigNeighbourhoodEnumerator
-type CartesianContigNeighborhoodMultiIterator<'a, Idx, T> =
-    MultiIterator<Idx, ContigNeighbourhoodEnumerator<'a, Idx, T>, Neighbourhood<'a, Idx, &'a T>>;
+type CartesianContigNeighborhoodMultiIterator<'a, Idx, T, const R: usize> = MultiIterator<
+    Idx,
+    ContigNeighbourhoodEnumerator<'a, Idx, T, R>,
+    Neighbourhood<'a, Idx, &'a T, R>,
+>;
 
-pub struct CartesianContigNeighbourhoodEnumerator<'a, Idx, T>
+pub struct CartesianContigNeighbourhoodEnumerator<'a, Idx, T, const R: usize = 1>
 where
     Idx: Copy
         + Default
@@ -133,12 +318,12 @@ where
         + SubAssign
         + Debug,
 {
-    row_enumerator: ContigNeighbourhoodEnumerator<'a, Idx, Contig<Idx, T>>,
+    row_enumerator: ContigNeighbourhoodEnumerator<'a, Idx, Contig<Idx, T>, R>,
     i_row: Option<Idx>,
-    column_enumerator: CartesianContigNeighborhoodMultiIterator<'a, Idx, T>,
+    column_enumerator: CartesianContigNeighborhoodMultiIterator<'a, Idx, T, R>,
 }
 
-impl<'a, Idx, T> CartesianContigNeighbourhoodEnumerator<'a, Idx, T>
+impl<'a, Idx, T, const R: usize> CartesianContigNeighbourhoodEnumerator<'a, Idx, T, R>
 where
     T: Debug,
     Idx: Copy
@@ -153,8 +338,10 @@ where
         + SubAssign
         + Debug,
 {
-    fn new(c: &'a CartesianContig<Idx, T>) -> CartesianContigNeighbourhoodEnumerator<'a, Idx, T> {
-        let mut row_enumerator = c.0.neighbourhood_enumerator();
+    fn new(
+        c: &'a CartesianContig<Idx, T>,
+    ) -> CartesianContigNeighbourhoodEnumerator<'a, Idx, T, R> {
+        let mut row_enumerator = c.0.neighbourhood_enumerator_with_radius::<R>();
         let (i_row, column_enumerator) =
             CartesianContigNeighbourhoodEnumerator::get_next_row(&mut row_enumerator);
 
@@ -166,10 +353,10 @@ where
     }
 
     fn get_next_row(
-        row_enumerator: &mut ContigNeighbourhoodEnumerator<'a, Idx, Contig<Idx, T>>,
+        row_enumerator: &mut ContigNeighbourhoodEnumerator<'a, Idx, Contig<Idx, T>, R>,
     ) -> (
         Option<Idx>,
-        CartesianContigNeighborhoodMultiIterator<'a, Idx, T>,
+        CartesianContigNeighborhoodMultiIterator<'a, Idx, T, R>,
     ) {
         let row = row_enumerator.next();
         let i_row = row.as_ref().map(|n| n.i);
@@ -179,29 +366,30 @@ where
     }
 
     fn multi_iterator_for_row_neighbourhood(
-        row_nbh_o: Option<Neighbourhood<Idx, &'a Contig<Idx, T>>>,
-    ) -> CartesianContigNeighborhoodMultiIterator<'a, Idx, T> {
+        row_nbh_o: Option<Neighbourhood<Idx, &'a Contig<Idx, T>, R>>,
+    ) -> CartesianContigNeighborhoodMultiIterator<'a, Idx, T, R> {
         match row_nbh_o {
             Some(row_nbh) => {
-                // if the focused row is present, that drives the enumerator,
-                // otherwise whichever or both of the above/below rows
-                let drivers = match row_nbh.items {
-                    [_, Some(_), _] => vec![false, true, false],
-                    [Some(_), None, Some(_)] => vec![true, false, true],
-                    [Some(_), None, None] => vec![true, false, false],
-                    [None, None, Some(_)] => vec![false, false, true],
-                    [None, None, None] => vec![false, false, false],
-                };
+                // every row which is present drives the enumerator, since an item in the row
+                // above or below may have diagonal siblings which are absent in the focused row
+                let drivers = row_nbh
+                    .items
+                    .iter()
+                    .map(|c_o| c_o.is_some())
+                    .collect::<Vec<bool>>();
 
                 let iterators = row_nbh
                     .items
                     .iter()
-                    .map(|c_o| c_o.map(|c| c.neighbourhood_enumerator()))
-                    .collect::<Vec<Option<ContigNeighbourhoodEnumerator<Idx, T>>>>();
+                    .map(|c_o| c_o.map(|c| c.neighbourhood_enumerator_with_radius::<R>()))
+                    .collect::<Vec<Option<ContigNeighbourhoodEnumerator<Idx, T, R>>>>();
 
                 MultiIterator::new(iterators, drivers)
             }
-            None => MultiIterator::new(vec![None, None, None], vec![false, false, false]),
+            None => {
+                let width = Window::<(), R>::WIDTH;
+                MultiIterator::new((0..width).map(|_| None).collect(), vec![false; width])
+            }
         }
     }
 
@@ -212,40 +400,14 @@ where
     }
 
     /// return next column if any
-    fn next_col(&mut self) -> Option<CartesianNeighbourhood<Idx, &'a T>> {
-        match self.column_enumerator.next() {
-            Some((i_col, items)) => Some(CartesianNeighbourhood::new(
-                self.i_row.unwrap(),
-                i_col,
-                items,
-            )),
-            None => None,
-        }
-
-        //     .as_mut()
-        //     .and_then(|rows| match &mut rows.items[1] {
-        //         Some(ref mut row_1_e) => match row_1_e.next() {
-        //             Some(row_1_nbh) => {
-        //                 let i_col = row_1_nbh.i;
-        //                 let absent: [Option<&'a T>; 3] = [None, None, None];
-        //                 Some(CartesianNeighbourhood {
-        //                     i_row: rows.i,
-        //                     i_col,
-        //                     items: [
-        //                         rows.items[0].as_mut().map_or(absent, |c| c.get(i_col)),
-        //                         row_1_nbh.items,
-        //                         rows.items[2].as_mut().map_or(absent, |c| c.get(i_col)),
-        //                     ],
-        //                 })
-        //             }
-        //             None => None,
-        //         },
-        //         None => None,
-        //     })
+    fn next_col(&mut self) -> Option<CartesianNeighbourhood<Idx, &'a T, R>> {
+        self.column_enumerator
+            .next()
+            .map(|(i_col, items)| CartesianNeighbourhood::new(self.i_row.unwrap(), i_col, items))
     }
 }
 
-impl<'a, Idx, T> Iterator for CartesianContigNeighbourhoodEnumerator<'a, Idx, T>
+impl<'a, Idx, T, const R: usize> Iterator for CartesianContigNeighbourhoodEnumerator<'a, Idx, T, R>
 where
     T: Debug,
     Idx: Copy
@@ -260,9 +422,9 @@ where
         + SubAssign
         + Debug,
 {
-    type Item = CartesianNeighbourhood<Idx, &'a T>;
+    type Item = CartesianNeighbourhood<Idx, &'a T, R>;
 
-    fn next(&mut self) -> Option<CartesianNeighbourhood<Idx, &'a T>> {
+    fn next(&mut self) -> Option<CartesianNeighbourhood<Idx, &'a T, R>> {
         self.next_col().or_else(|| {
             self.advance_row();
             self.next_col()
@@ -270,4 +432,166 @@ where
     }
 }
 
+/// enumerator of neighbourhoods on a topology, which merges the plain enumeration with the
+/// cells reached only across an edge
+pub struct CartesianTopologyNeighbourhoodEnumerator<'a, Idx, T>
+where
+    T: Debug,
+    Idx: Copy
+        + Default
+        + One
+        + FromPrimitive
+        + AsPrimitive<usize>
+        + Add<Output = Idx>
+        + Sub<Output = Idx>
+        + PartialOrd
+        + AddAssign
+        + SubAssign
+        + Debug,
+{
+    cc: &'a CartesianContig<Idx, T>,
+    topology: Topology,
+    neighbourhoods: Peekable<CartesianContigNeighbourhoodEnumerator<'a, Idx, T>>,
+    /// row and column of cells with neighbours across an edge, in order
+    wrapped: Peekable<std::vec::IntoIter<(Idx, Idx)>>,
+}
+
+impl<'a, Idx, T> CartesianTopologyNeighbourhoodEnumerator<'a, Idx, T>
+where
+    T: Debug,
+    Idx: Copy
+        + Default
+        + One
+        + FromPrimitive
+        + AsPrimitive<usize>
+        + Add<Output = Idx>
+        + Sub<Output = Idx>
+        + PartialOrd
+        + Ord
+        + AddAssign
+        + SubAssign
+        + Integer
+        + Debug,
+{
+    fn new(
+        cc: &'a CartesianContig<Idx, T>,
+        topology: Topology,
+    ) -> CartesianTopologyNeighbourhoodEnumerator<'a, Idx, T> {
+        let mut wrapped = Vec::new();
+        if let Some((min, max)) = topology.bounds::<Idx>() {
+            let one = Idx::one();
+            // only items along the edges have neighbours across them
+            let edges = [
+                (min.x..max.x + one, min.y..min.y + one),
+                (min.x..max.x + one, max.y..max.y + one),
+                (min.x..min.x + one, min.y..max.y + one),
+                (max.x..max.x + one, min.y..max.y + one),
+            ];
+            for (xs, ys) in edges {
+                for (c, _) in cc.window(xs, ys) {
+                    for (x, y) in Self::neighbour_positions(c.x, c.y) {
+                        if !topology.contains(x, y) {
+                            if let Some(image) = topology.wrap(x, y) {
+                                wrapped.push((image.y, image.x));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        wrapped.sort();
+        wrapped.dedup();
+
+        CartesianTopologyNeighbourhoodEnumerator {
+            cc,
+            topology,
+            neighbourhoods: cc.neighbourhood_enumerator().peekable(),
+            wrapped: wrapped.into_iter().peekable(),
+        }
+    }
+
+    /// the positions of the 3x3 neighbourhood of `x`, `y`, by row then column
+    fn neighbour_positions(x: Idx, y: Idx) -> impl Iterator<Item = (Idx, Idx)> {
+        (0..9).map(move |k| {
+            (
+                x + Idx::from_usize(k % 3).unwrap() - Idx::one(),
+                y + Idx::from_usize(k / 3).unwrap() - Idx::one(),
+            )
+        })
+    }
+
+    /// the neighbourhood of a cell on an edge, found item by item
+    fn across_edges(&self, i_row: Idx, i_col: Idx) -> CartesianNeighbourhood<Idx, &'a T> {
+        let cc = self.cc;
+        let topology = self.topology;
+        let mut positions = Self::neighbour_positions(i_col, i_row);
+        let items = Window::from_fn(|_| {
+            Window::from_fn(|_| {
+                let (x, y) = positions.next().unwrap();
+                topology
+                    .wrap(x, y)
+                    .and_then(|image| cc.get(image.x, image.y))
+            })
+        });
+        CartesianNeighbourhood {
+            i_row,
+            i_col,
+            items,
+        }
+    }
+}
+
+impl<'a, Idx, T> Iterator for CartesianTopologyNeighbourhoodEnumerator<'a, Idx, T>
+where
+    T: Debug,
+    Idx: Copy
+        + Default
+        + One
+        + FromPrimitive
+        + AsPrimitive<usize>
+        + Add<Output = Idx>
+        + Sub<Output = Idx>
+        + PartialOrd
+        + Ord
+        + AddAssign
+        + SubAssign
+        + Integer
+        + Debug,
+{
+    type Item = CartesianNeighbourhood<Idx, &'a T>;
+
+    fn next(&mut self) -> Option<CartesianNeighbourhood<Idx, &'a T>> {
+        loop {
+            let next_plain = self.neighbourhoods.peek().map(|nbh| (nbh.i_row, nbh.i_col));
+            let next_wrapped = self.wrapped.peek().copied();
+            let (i_row, i_col) = match (next_plain, next_wrapped) {
+                (Some(plain), Some(wrapped)) => plain.min(wrapped),
+                (Some(position), None) | (None, Some(position)) => position,
+                (None, None) => return None,
+            };
+            let plain = if next_plain == Some((i_row, i_col)) {
+                self.neighbourhoods.next()
+            } else {
+                None
+            };
+            if next_wrapped == Some((i_row, i_col)) {
+                self.wrapped.next();
+            }
+
+            if !self.topology.contains(i_col, i_row) {
+                continue;
+            }
+            match plain {
+                Some(nbh) if self.topology.is_interior(i_col, i_row) => return Some(nbh),
+                _ => {
+                    let nbh = self.across_edges(i_row, i_col);
+                    if nbh.items.iter().flatten().any(Option::is_some) {
+                        return Some(nbh);
+                    }
+                }
+            }
+        }
+    }
+}
+
 mod tests;
@@ -1,5 +1,6 @@
 #![cfg(test)]
 use super::*;
+use crate::grid::Grid;
 
 #[test]
 fn test_cartesian_contig_set() {
@@ -13,6 +14,101 @@ fn test_cartesian_contig_set() {
     assert_eq!(cc.get(4, 2), Some(&42u8));
 }
 
+#[test]
+fn test_cartesian_contig_remove() {
+    let mut cc = CartesianContig::new(0, 0, 0u8);
+    cc.set(1, 1, 11u8);
+    cc.set(4, 1, 41u8);
+
+    assert_eq!(cc.remove(1, 2), None);
+    assert_eq!(cc.remove(0, 0), Some(0u8));
+    assert_eq!(cc.origin(), Coordinate { x: 1, y: 1 });
+
+    assert_eq!(cc.remove(4, 1), Some(41u8));
+    assert!(!cc.is_empty());
+    assert_eq!(cc.remove(1, 1), Some(11u8));
+    assert!(cc.is_empty());
+}
+
+#[test]
+fn test_cartesian_contig_compact() {
+    let mut cc = CartesianContig::new(0, 0, 1u8);
+    cc.set(1, 0, 0u8);
+    cc.set(3, 2, 0u8);
+    cc.set(2, 4, 5u8);
+
+    // the row of only zero items goes
+    cc.compact(&CompactionPolicy::memory());
+    assert_eq!(cc.get(1, 0), None);
+    assert!(cc.row(2).is_none());
+    assert_eq!(cc.origin(), Coordinate { x: 0, y: 0 });
+
+    cc.set(0, 0, 0u8);
+    cc.set(2, 4, 0u8);
+    cc.compact(&CompactionPolicy::memory());
+    assert!(cc.is_empty());
+}
+
+#[test]
+fn test_cartesian_contig_auto_compaction() {
+    let mut cc = CartesianContig::new(0, 0, 1u8);
+    cc.set_auto_compaction(Some(CompactionPolicy {
+        update_threshold: 3,
+        ..CompactionPolicy::memory()
+    }));
+
+    cc.set(1, 0, 0u8);
+    cc.set(0, 2, 0u8);
+    assert_eq!(cc.get(0, 2), Some(&0u8));
+
+    // the third update compacts, dropping the row of only a zero item
+    cc.set(-3, 4, 6u8);
+    assert_eq!(cc.get(1, 0), None);
+    assert!(cc.row(2).is_none());
+    assert_eq!(cc.origin(), Coordinate { x: -3, y: 0 });
+
+    // removal counts as an update, and compaction can leave nothing
+    cc.set(0, 0, 0u8);
+    cc.set(-3, 4, 0u8);
+    assert!(!cc.is_empty());
+    assert_eq!(cc.remove(0, 0), Some(0u8));
+    assert!(cc.is_empty());
+
+    let mut cc = CartesianContig::new(0, 0, 1u8);
+    cc.set_auto_compaction(Some(CompactionPolicy {
+        update_threshold: 1,
+        ..CompactionPolicy::memory()
+    }));
+    cc.set_auto_compaction(None);
+    for x in 1..5 {
+        cc.set(x, 0, 0u8);
+    }
+    assert_eq!(cc.get(4, 0), Some(&0u8));
+}
+
+#[test]
+fn test_cartesian_contig_window() {
+    let mut cc = CartesianContig::new(0, 0, 0u8);
+    for (x, y) in [(1, 0), (5, 0), (2, 2), (3, 2), (-4, 3), (2, 6), (9, 6)] {
+        cc.set(x, y, (y * 10 + x) as u8);
+    }
+    let window = |xs: Range<i32>, ys: Range<i32>| {
+        cc.window(xs, ys)
+            .map(|(c, item)| (c.x, c.y, *item))
+            .collect::<Vec<(i32, i32, u8)>>()
+    };
+
+    assert_eq!(
+        window(1..4, 0..7),
+        vec![(1, 0, 1), (2, 2, 22), (3, 2, 23), (2, 6, 62)]
+    );
+    assert_eq!(window(-10..10, 3..6), vec![(-4, 3, 26)]);
+    assert_eq!(window(-10..0, 0..3), vec![]);
+    assert_eq!(window(0..10, 4..6), vec![]);
+    assert_eq!(window(0..10, 7..8), vec![]);
+    assert_eq!(window(0..1, -1..1), vec![(0, 0, 0)]);
+}
+
 #[test]
 fn test_cartesian_contig_neighbourhood_enumerator() {
     fn enumerator_as_vec(cc: &CartesianContig<i32, u8>) -> Vec<CartesianNeighbourhood<i32, &u8>> {
@@ -36,6 +132,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [None, None, Some(&0)],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: -1,
@@ -45,6 +142,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [None, Some(&0), Some(&1)],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: -1,
@@ -54,6 +152,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [Some(&0), Some(&1), Some(&2)],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: -1,
@@ -63,6 +162,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [Some(&1), Some(&2), None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: -1,
@@ -72,6 +172,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [Some(&2), None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 0,
@@ -81,6 +182,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, Some(&0)],
                     [None, None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 0,
@@ -90,6 +192,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, Some(&0), Some(&1)],
                     [None, None, Some(&11)],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 0,
@@ -99,6 +202,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [Some(&0), Some(&1), Some(&2)],
                     [None, Some(&11), None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 0,
@@ -108,6 +212,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [Some(&1), Some(&2), None],
                     [Some(&11), None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 0,
@@ -117,6 +222,17 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [Some(&2), None, None],
                     [None, None, None],
                 ]
+                .into()
+            },
+            CartesianNeighbourhood {
+                i_row: 1,
+                i_col: -1,
+                items: [
+                    [None, None, Some(&0)],
+                    [None, None, None],
+                    [None, None, None],
+                ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 1,
@@ -126,6 +242,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, Some(&11)],
                     [None, None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 1,
@@ -135,6 +252,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, Some(&11), None],
                     [None, None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 1,
@@ -144,6 +262,17 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [Some(&11), None, None],
                     [None, None, None],
                 ]
+                .into()
+            },
+            CartesianNeighbourhood {
+                i_row: 1,
+                i_col: 3,
+                items: [
+                    [Some(&2), None, None],
+                    [None, None, None],
+                    [None, None, None],
+                ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 2,
@@ -153,6 +282,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [None, None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 2,
@@ -162,6 +292,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [None, None, Some(&22)],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 2,
@@ -171,6 +302,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [None, Some(&22), None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 2,
@@ -180,6 +312,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [Some(&22), None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 3,
@@ -189,6 +322,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, Some(&22)],
                     [None, None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 3,
@@ -198,6 +332,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, Some(&22), None],
                     [None, None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 3,
@@ -207,6 +342,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [Some(&22), None, None],
                     [None, None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 4,
@@ -216,6 +352,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [None, None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 4,
@@ -225,6 +362,7 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [None, None, None],
                 ]
+                .into()
             },
             CartesianNeighbourhood {
                 i_row: 4,
@@ -234,7 +372,162 @@ fn test_cartesian_contig_neighbourhood_enumerator() {
                     [None, None, None],
                     [None, None, None],
                 ]
+                .into()
             },
         ]
     );
 }
+
+#[test]
+fn test_cartesian_contig_neighbourhood_enumerator_with_radius() {
+    const R: usize = 2;
+    let r = R as i32;
+    let cells: [(i32, i32); 7] = [(0, 0), (1, 0), (7, 0), (3, 2), (-2, 5), (9, 9), (4, 12)];
+    let mut cc = CartesianContig::new(cells[0].0, cells[0].1, 0u8);
+    for (u, (x, y)) in cells.iter().enumerate().skip(1) {
+        cc.set(*x, *y, u as u8);
+    }
+
+    // every position within the square of radius R of a cell, by row then column
+    let mut expected = Vec::new();
+    for y in -10..20 {
+        for x in -10..20 {
+            if cells
+                .iter()
+                .any(|(cx, cy)| (cx - x).abs() <= r && (cy - y).abs() <= r)
+            {
+                expected.push((x, y));
+            }
+        }
+    }
+
+    let nbhs = cc
+        .neighbourhood_enumerator_with_radius::<R>()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        nbhs.iter().map(|n| (n.i_col, n.i_row)).collect::<Vec<_>>(),
+        expected
+    );
+    for nbh in nbhs.iter() {
+        for (k_row, row) in nbh.items.iter().enumerate() {
+            for (k_col, item) in row.iter().enumerate() {
+                let x = nbh.i_col + k_col as i32 - r;
+                let y = nbh.i_row + k_row as i32 - r;
+                assert_eq!(*item, cc.get(x, y), "({}, {}) in {:?}", x, y, nbh);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_cartesian_contig_neighbourhood_enumerator_with_topology() {
+    let cells: [(i32, i32); 8] = [
+        (-3, -2),
+        (0, -2),
+        (2, -2),
+        (-1, 0),
+        (2, 0),
+        (-3, 1),
+        (1, 2),
+        (2, 2),
+    ];
+    let mut cc = CartesianContig::new(cells[0].0, cells[0].1, 0u8);
+    for (u, (x, y)) in cells.iter().enumerate().skip(1) {
+        cc.set(*x, *y, u as u8);
+    }
+    // ignored, as outside the bounds
+    cc.set(9, 9, 99u8);
+
+    for topology in ["P6,5", "T6,5", "K6*,5", "K6,5*", "C6,5", "T1,5"] {
+        let topology = topology.parse::<Topology>().unwrap();
+        let (min, max) = topology.bounds::<i32>().unwrap();
+
+        // every cell with an item within its neighbourhood, by row then column
+        let mut expected = Vec::new();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let items: Window<Window<Option<&u8>, 1>, 1> = Window::from_fn(|k_row| {
+                    Window::from_fn(|k_col| {
+                        topology
+                            .wrap(x + k_col as i32 - 1, y + k_row as i32 - 1)
+                            .and_then(|image| cc.get(image.x, image.y))
+                    })
+                });
+                if items.iter().flatten().any(Option::is_some) {
+                    expected.push(CartesianNeighbourhood {
+                        i_row: y,
+                        i_col: x,
+                        items,
+                    });
+                }
+            }
+        }
+
+        assert_eq!(
+            cc.neighbourhood_enumerator_with_topology(topology)
+                .collect::<Vec<_>>(),
+            expected,
+            "{}",
+            topology
+        );
+    }
+
+    assert_eq!(
+        cc.neighbourhood_enumerator_with_topology(Topology::Plane)
+            .collect::<Vec<_>>(),
+        cc.neighbourhood_enumerator().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_cartesian_neighbourhood_neighbours() {
+    let mut cc = CartesianContig::new(0, 0, 0u8);
+    for (x, y) in [
+        (-2, -1),
+        (-1, -1),
+        (1, -1),
+        (2, -1),
+        (-1, 0),
+        (1, 1),
+        (2, 1),
+    ] {
+        cc.set(x, y, (10 * (y + 1) + x + 2) as u8);
+    }
+    let nbh = cc
+        .neighbourhood_enumerator_with_radius::<2>()
+        .find(|nbh| (nbh.i_col, nbh.i_row) == (0, 0))
+        .unwrap();
+    let neighbours = |grid: Grid| {
+        nbh.neighbours(grid)
+            .into_iter()
+            .map(|item| item.copied())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        neighbours(Grid::Square),
+        vec![Some(1), None, Some(3), Some(11), None, None, None, Some(23)]
+    );
+    assert_eq!(
+        neighbours(Grid::Hexagonal),
+        vec![Some(1), None, Some(11), None, None, Some(23)]
+    );
+    // pointing up, so two cells either side in the row below
+    assert_eq!(
+        neighbours(Grid::Triangular),
+        vec![
+            Some(1),
+            None,
+            Some(3),
+            None,
+            Some(11),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(23),
+            Some(24)
+        ]
+    );
+}
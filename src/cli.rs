@@ -0,0 +1,525 @@
+// TODO remove suppression for dead code warning
+#![allow(dead_code)]
+
+use super::cartesian::Coordinate;
+use super::generations::{GenerationsPlayfield, LIVE};
+use super::grid::Grid;
+use super::pattern::{life105, life106, macrocell, plaintext, rle, Pattern, PatternError};
+use super::playfield::Playfield;
+use super::rule::{Rule, RuleError};
+use super::topology::{split_rule, Topology};
+use super::triangular::TriangularRule;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub const USAGE: &str = "usage: life <command> [options] <input>
+
+commands:
+  run <input>       step the pattern and write the result
+  info <input>      print population and bounding box
+  convert <input>   write the pattern in another format
+  render <input>    draw the pattern as ASCII art, or a PBM image
+
+options:
+  -g, --generations <n>   generations to run, default 1
+  -r, --rule <rule>       rule to run, default from the pattern or B3/S23
+  -f, --format <format>   output format: rle, cells, life105, life106, mc, ascii or pbm
+  -o, --output <file>     output file, default standard output
+  -t, --threads <n>       threads to run with, default every core
+
+Input and output formats are inferred from file extensions where not given,
+and `-` is standard input.";
+
+/// the playfield used by all commands
+type CliPlayfield = Playfield<i64, u64>;
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Io(String, io::Error),
+    Pattern(PatternError),
+    Rule(RuleError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(s) => write!(f, "{}\n\n{}", s, USAGE),
+            CliError::Io(path, e) => write!(f, "{}: {}", path, e),
+            CliError::Pattern(e) => write!(f, "{}", e),
+            CliError::Rule(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<PatternError> for CliError {
+    fn from(e: PatternError) -> CliError {
+        CliError::Pattern(e)
+    }
+}
+
+impl From<RuleError> for CliError {
+    fn from(e: RuleError) -> CliError {
+        CliError::Rule(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+    Macrocell,
+    /// rows of `.` and `O`, for rendering only
+    Ascii,
+    /// plain portable bitmap, for rendering only
+    Pbm,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "rle" => Some(Format::Rle),
+            "cells" | "plaintext" => Some(Format::Plaintext),
+            "life105" | "lif" => Some(Format::Life105),
+            "life106" | "l" => Some(Format::Life106),
+            "mc" | "macrocell" => Some(Format::Macrocell),
+            "ascii" | "txt" => Some(Format::Ascii),
+            "pbm" => Some(Format::Pbm),
+            _ => None,
+        }
+    }
+
+    /// the format implied by the extension of `path`, if any
+    ///
+    /// The `.lif` extension is ambiguous, so is taken to be Life 1.05, with the content
+    /// deciding on reading.
+    fn from_path(path: &str) -> Option<Format> {
+        Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_name)
+    }
+
+    /// the format of pattern file content, falling back to RLE
+    fn detect(s: &str) -> Format {
+        let first = s.lines().map(str::trim).find(|line| !line.is_empty());
+        match first {
+            Some(line) if line.starts_with("[M2]") => Format::Macrocell,
+            Some(line) if line.starts_with("#Life 1.05") => Format::Life105,
+            Some(line) if line.starts_with("#Life 1.06") => Format::Life106,
+            Some(line) if line.starts_with('!') => Format::Plaintext,
+            Some(line) if line.chars().all(|c| matches!(c, '.' | 'O')) => Format::Plaintext,
+            _ => Format::Rle,
+        }
+    }
+
+    fn read(self, s: &str) -> Result<Pattern, CliError> {
+        match self {
+            Format::Rle => Ok(rle::read(s)?),
+            Format::Plaintext => Ok(plaintext::read(s)?),
+            Format::Life105 => Ok(life105::read(s)?),
+            Format::Life106 => Ok(life106::read(s)?),
+            Format::Macrocell => Ok(macrocell::read(s)?),
+            Format::Ascii | Format::Pbm => {
+                Err(CliError::Usage(format!("cannot read {:?} format", self)))
+            }
+        }
+    }
+
+    fn write(self, pattern: &Pattern) -> Result<String, CliError> {
+        match self {
+            Format::Rle => Ok(rle::write(pattern)),
+            Format::Plaintext => Ok(plaintext::write(pattern)?),
+            Format::Life105 => Ok(life105::write(pattern)),
+            Format::Life106 => Ok(life106::write(pattern)),
+            Format::Macrocell => Ok(macrocell::write(pattern)),
+            Format::Ascii => render_ascii(pattern),
+            Format::Pbm => render_pbm(pattern),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Subcommand {
+    Run,
+    Info,
+    Convert,
+    Render,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Command {
+    pub subcommand: Subcommand,
+    pub input: String,
+    pub output: Option<String>,
+    pub format: Option<Format>,
+    pub generations: u64,
+    pub rule: Option<String>,
+    /// threads to run with, or every core if `None`
+    pub threads: Option<usize>,
+}
+
+impl Command {
+    /// parse the arguments following the program name
+    pub fn parse(args: &[String]) -> Result<Command, CliError> {
+        let mut args = args.iter();
+        let subcommand = match args.next().map(String::as_str) {
+            Some("run") => Subcommand::Run,
+            Some("info") => Subcommand::Info,
+            Some("convert") => Subcommand::Convert,
+            Some("render") => Subcommand::Render,
+            Some(other) => return Err(CliError::Usage(format!("unknown command {}", other))),
+            None => return Err(CliError::Usage("missing command".to_string())),
+        };
+
+        let mut input = None;
+        let mut output = None;
+        let mut format = None;
+        let mut generations = None;
+        let mut rule = None;
+        let mut threads = None;
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| CliError::Usage(format!("missing value for {}", arg)))
+            };
+            match arg.as_str() {
+                "-o" | "--output" => output = Some(value()?),
+                "-f" | "--format" => {
+                    let name = value()?;
+                    format = Some(
+                        Format::from_name(&name)
+                            .ok_or_else(|| CliError::Usage(format!("unknown format {}", name)))?,
+                    );
+                }
+                "-g" | "--generations" => {
+                    let n = value()?;
+                    generations = Some(
+                        n.parse::<u64>()
+                            .map_err(|_| CliError::Usage(format!("invalid generations {}", n)))?,
+                    );
+                }
+                "-r" | "--rule" => rule = Some(value()?),
+                "-t" | "--threads" => {
+                    let n = value()?;
+                    threads = Some(
+                        n.parse::<usize>()
+                            .ok()
+                            .filter(|n| *n > 0)
+                            .ok_or_else(|| CliError::Usage(format!("invalid threads {}", n)))?,
+                    );
+                }
+                "-" => input = Some(arg.clone()),
+                option if option.starts_with('-') => {
+                    return Err(CliError::Usage(format!("unknown option {}", option)))
+                }
+                _ if input.is_some() => {
+                    return Err(CliError::Usage(format!("unexpected argument {}", arg)))
+                }
+                _ => input = Some(arg.clone()),
+            }
+        }
+
+        if generations.is_some() && subcommand != Subcommand::Run {
+            return Err(CliError::Usage("generations only apply to run".to_string()));
+        }
+        if threads.is_some() && subcommand != Subcommand::Run {
+            return Err(CliError::Usage("threads only apply to run".to_string()));
+        }
+
+        Ok(Command {
+            subcommand,
+            input: input.ok_or_else(|| CliError::Usage("missing input".to_string()))?,
+            output,
+            format,
+            generations: generations.unwrap_or(1),
+            rule,
+            threads,
+        })
+    }
+
+    /// the output format, from the option, the output file, or the input format
+    fn output_format(&self, input_format: Format) -> Format {
+        let default = match self.subcommand {
+            Subcommand::Render => Format::Ascii,
+            _ => input_format,
+        };
+        self.format
+            .or_else(|| self.output.as_deref().and_then(Format::from_path))
+            .unwrap_or(default)
+    }
+
+    /// execute on the content of the input file, returning the output
+    pub fn execute(&self, s: &str) -> Result<String, CliError> {
+        let input_format = match Format::from_path(&self.input) {
+            Some(Format::Life105) | None => Format::detect(s),
+            Some(format) => format,
+        };
+        // macrocell input is often far too large to list its cells, so is kept as nodes
+        if input_format == Format::Macrocell {
+            match self.subcommand {
+                Subcommand::Info => {
+                    let (_, hashlife) = macrocell::read_tree(s)?;
+                    return Ok(info(hashlife.population(), hashlife.bounding_box()));
+                }
+                Subcommand::Run => {
+                    if let Some(output) = self.run_hashlife(s)? {
+                        return Ok(output);
+                    }
+                }
+                _ => (),
+            }
+        }
+        let pattern = input_format.read(s)?;
+
+        match self.subcommand {
+            Subcommand::Info => {
+                let playfield = pattern.to_playfield::<i64, u64>();
+                let bounds = playfield.bounding_box().map(|(min, max)| {
+                    let wide = |c: Coordinate<i64>| Coordinate {
+                        x: c.x as i128,
+                        y: c.y as i128,
+                    };
+                    (wide(min), wide(max))
+                });
+                Ok(info(playfield.population() as u64, bounds))
+            }
+            Subcommand::Run => {
+                let rule = self.rule.as_ref().or(pattern.rule.as_ref());
+                let (rule, topology) = split_rule(rule.map_or("B3/S23", String::as_str))?;
+                // as in Golly, cells outside a bounded grid are discarded
+                let cells = pattern
+                    .cells
+                    .iter()
+                    .filter(|cell| topology.contains(cell.x, cell.y))
+                    .copied()
+                    .collect::<Vec<_>>();
+                let (rule, cells) = match Grid::split_suffix(rule) {
+                    (_, Grid::Triangular) => self.run_triangular(rule, topology, &cells)?,
+                    _ => self.run_life_like(rule, topology, &cells)?,
+                };
+                let result = Pattern {
+                    rule: Some(format!("{}{}", rule, topology)),
+                    cells,
+                    ..pattern
+                };
+                self.output_format(input_format).write(&result)
+            }
+            Subcommand::Convert | Subcommand::Render => {
+                let format = self.output_format(input_format);
+                if self.subcommand == Subcommand::Render
+                    && !matches!(format, Format::Ascii | Format::Pbm)
+                {
+                    return Err(CliError::Usage(format!(
+                        "cannot render {:?} format",
+                        format
+                    )));
+                }
+                // via the playfield, so the cells are normalised
+                let cells = sorted_cells(&pattern.to_playfield::<i64, u64>());
+                format.write(&Pattern { cells, ..pattern })
+            }
+        }
+    }
+
+    /// run macrocell input in the HashLife engine, without listing its cells, returning the
+    /// output, or `None` if the rule is one the engine cannot run
+    fn run_hashlife(&self, s: &str) -> Result<Option<String>, CliError> {
+        let (pattern, mut hashlife) = macrocell::read_tree(s)?;
+        let rule = self.rule.as_ref().or(pattern.rule.as_ref());
+        let (rule, topology) = split_rule(rule.map_or("B3/S23", String::as_str))?;
+        if topology != Topology::Plane || Grid::split_suffix(rule).1 == Grid::Triangular {
+            return Ok(None);
+        }
+
+        let rule = rule.parse::<Rule>()?;
+        hashlife.set_rule(&rule);
+        hashlife.step(self.generations);
+        let result = Pattern {
+            rule: Some(rule.to_string()),
+            ..pattern
+        };
+        match self.output_format(Format::Macrocell) {
+            Format::Macrocell => Ok(Some(macrocell::write_tree(&hashlife, &result))),
+            format => {
+                let mut cells = hashlife.cells();
+                cells.sort_by_key(|cell| (cell.y, cell.x));
+                format.write(&Pattern { cells, ..result }).map(Some)
+            }
+        }
+    }
+
+    /// run a rule on the square or hexagonal grid, returning the canonical rule and the cells
+    fn run_life_like(
+        &self,
+        rule: &str,
+        topology: Topology,
+        cells: &[Coordinate<i64>],
+    ) -> Result<(String, Vec<Coordinate<i64>>), CliError> {
+        let rule = rule.parse::<Rule>()?;
+        let mut playfield = CliPlayfield::from_cells(cells.iter().copied());
+        playfield.set_threads(self.threads);
+        for _ in 0..self.generations {
+            playfield.advance_with_topology(&rule, topology);
+        }
+        Ok((rule.to_string(), sorted_cells(&playfield)))
+    }
+
+    /// run a rule on the triangular grid, returning the canonical rule and the cells
+    fn run_triangular(
+        &self,
+        rule: &str,
+        topology: Topology,
+        cells: &[Coordinate<i64>],
+    ) -> Result<(String, Vec<Coordinate<i64>>), CliError> {
+        let rule = rule.parse::<TriangularRule>()?;
+        if topology != Topology::Plane {
+            return Err(CliError::Usage(
+                "bounded grids are not supported for triangular rules".to_string(),
+            ));
+        }
+        let mut playfield = GenerationsPlayfield::<i64>::new();
+        for cell in cells {
+            playfield.set_cell(cell.x, cell.y, LIVE);
+        }
+        for _ in 0..self.generations {
+            playfield = playfield.step_triangular(&rule);
+        }
+
+        let (rows, origin) = playfield.to_rows();
+        let mut cells = Vec::new();
+        for (y, row) in (origin.y..).zip(rows.iter()) {
+            for (x, state) in (origin.x..).zip(row.iter()) {
+                if *state == LIVE {
+                    cells.push(Coordinate { x, y });
+                }
+            }
+        }
+        Ok((rule.to_string(), cells))
+    }
+}
+
+/// live cells of `playfield` ordered by row then column
+fn sorted_cells(playfield: &CliPlayfield) -> Vec<Coordinate<i64>> {
+    let mut cells = playfield.cells();
+    cells.sort_by_key(|cell| (cell.y, cell.x));
+    cells
+}
+
+/// the population and bounding box, which is wide enough for any extent
+fn info(population: u64, bounding_box: Option<(Coordinate<i128>, Coordinate<i128>)>) -> String {
+    let bounds = match bounding_box {
+        Some((min, max)) => format!(
+            "x = {}, y = {}, width = {}, height = {}",
+            min.x,
+            min.y,
+            max.x - min.x + 1,
+            max.y - min.y + 1
+        ),
+        None => "none".to_string(),
+    };
+    format!("population: {}\nbounding box: {}\n", population, bounds)
+}
+
+/// rows of `.` and `O` within the bounding box
+fn render_ascii(pattern: &Pattern) -> Result<String, CliError> {
+    let (rows, _) = pattern.rows()?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let mut line = row
+                .iter()
+                .map(|live| if *live { 'O' } else { '.' })
+                .collect::<String>();
+            line.push('\n');
+            line
+        })
+        .collect())
+}
+
+/// plain PBM image of the bounding box, one pixel per cell, with live cells black
+fn render_pbm(pattern: &Pattern) -> Result<String, CliError> {
+    let (rows, _) = pattern.rows()?;
+    let width = rows.first().map_or(0, Vec::len);
+    let mut s = format!("P1\n{} {}\n", width, rows.len());
+    for row in rows.iter() {
+        let pixels = row
+            .iter()
+            .map(|live| if *live { "1" } else { "0" })
+            .collect::<Vec<_>>();
+        s.push_str(&pixels.join(" "));
+        s.push('\n');
+    }
+    Ok(s)
+}
+
+fn read_input(input: &str) -> Result<String, CliError> {
+    let mut s = String::new();
+    if input == "-" {
+        io::stdin()
+            .read_to_string(&mut s)
+            .map_err(|e| CliError::Io(input.to_string(), e))?;
+    } else {
+        s = fs::read_to_string(input).map_err(|e| CliError::Io(input.to_string(), e))?;
+    }
+    Ok(s)
+}
+
+/// `result` of writing to stdout, where the reader going away early, as `head` does, is
+/// not an error
+fn allow_broken_pipe(result: io::Result<()>) -> Result<(), CliError> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| CliError::Io("stdout".to_string(), e)),
+    }
+}
+
+fn write_output(output: Option<&str>, s: &str) -> Result<(), CliError> {
+    match output {
+        Some(path) if path != "-" => {
+            fs::write(path, s).map_err(|e| CliError::Io(path.to_string(), e))
+        }
+        _ => {
+            let mut stdout = io::stdout().lock();
+            allow_broken_pipe(stdout.write_all(s.as_bytes()).and_then(|()| stdout.flush()))
+        }
+    }
+}
+
+/// run the command line, returning the exit code, which is 2 for usage errors
+pub fn main(args: &[String]) -> i32 {
+    let result = if args
+        .first()
+        .is_some_and(|arg| matches!(arg.as_str(), "-h" | "--help" | "help"))
+    {
+        allow_broken_pipe(writeln!(io::stdout().lock(), "{}", USAGE))
+    } else {
+        Command::parse(args).and_then(|command| {
+            let s = read_input(&command.input)?;
+            let output = command.execute(&s)?;
+            write_output(command.output.as_deref(), &output)
+        })
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("life: {}", e);
+            match e {
+                CliError::Usage(_) => 2,
+                _ => 1,
+            }
+        }
+    }
+}
+
+mod tests;
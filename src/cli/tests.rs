@@ -0,0 +1,263 @@
+#![cfg(test)]
+use super::*;
+
+fn args(s: &str) -> Vec<String> {
+    s.split_whitespace().map(str::to_string).collect()
+}
+
+fn command(s: &str) -> Command {
+    Command::parse(&args(s)).unwrap()
+}
+
+const BLINKER_RLE: &str = "#N Blinker
+x = 3, y = 1, rule = B3/S23
+3o!
+";
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        command("run -g 10 --rule B36/S23 blinker.rle -o out.cells -t 4"),
+        Command {
+            subcommand: Subcommand::Run,
+            input: "blinker.rle".to_string(),
+            output: Some("out.cells".to_string()),
+            format: None,
+            generations: 10,
+            rule: Some("B36/S23".to_string()),
+            threads: Some(4),
+        }
+    );
+    assert_eq!(command("render -f pbm -").format, Some(Format::Pbm));
+    assert_eq!(command("info x.rle").generations, 1);
+    assert_eq!(command("run x.rle").threads, None);
+}
+
+#[test]
+fn test_parse_errors() {
+    for s in [
+        "",
+        "walk x.rle",
+        "run",
+        "run -g",
+        "run -g many x.rle",
+        "run --speed 2 x.rle",
+        "run x.rle y.rle",
+        "convert -f gif x.rle",
+        "info -g 2 x.rle",
+        "run -t 0 x.rle",
+        "convert -t 2 x.rle",
+    ] {
+        assert!(
+            matches!(Command::parse(&args(s)), Err(CliError::Usage(_))),
+            "{}",
+            s
+        );
+    }
+}
+
+#[test]
+fn test_detect_format() {
+    assert_eq!(Format::detect(BLINKER_RLE), Format::Rle);
+    assert_eq!(Format::detect("!Name: Blinker\nOOO\n"), Format::Plaintext);
+    assert_eq!(Format::detect(".O\nO.\n"), Format::Plaintext);
+    assert_eq!(Format::detect("#Life 1.05\n*\n"), Format::Life105);
+    assert_eq!(Format::detect("#Life 1.06\n0 0\n"), Format::Life106);
+    assert_eq!(Format::detect("[M2] (golly)\n*$\n"), Format::Macrocell);
+    assert_eq!(
+        Format::from_path("dir/glider.cells"),
+        Some(Format::Plaintext)
+    );
+    assert_eq!(Format::from_path("glider"), None);
+}
+
+#[test]
+fn test_run() {
+    let output = command("run blinker.rle").execute(BLINKER_RLE).unwrap();
+    assert_eq!(
+        output,
+        "#N Blinker\n#R 1 -1\nx = 1, y = 3, rule = B3/S23\no$o$o!\n"
+    );
+
+    let output = command("run -g 2 -f cells blinker.rle")
+        .execute(BLINKER_RLE)
+        .unwrap();
+    assert_eq!(output, "!Name: Blinker\nOOO\n");
+
+    // a rule given on the command line overrides that of the pattern
+    let output = command("run -r B/S -f life106 blinker.rle")
+        .execute(BLINKER_RLE)
+        .unwrap();
+    assert_eq!(output, "#Life 1.06\n");
+
+    assert!(matches!(
+        command("run -r B3/S23/X blinker.rle").execute(BLINKER_RLE),
+        Err(CliError::Rule(_))
+    ));
+}
+
+#[test]
+fn test_run_with_topology() {
+    // the blinker is cut off by the edges, and the cell outside them is discarded
+    let output = command("run -r B3/S23:P3,1 -")
+        .execute("#Life 1.06\n-1 0\n0 0\n1 0\n5 5\n")
+        .unwrap();
+    assert_eq!(output, "#Life 1.06\n0 0\n");
+
+    let output = command("run -g 0 -f rle -r b3/s23:t8,6 -")
+        .execute("#Life 1.06\n0 0\n")
+        .unwrap();
+    assert_eq!(output, "x = 1, y = 1, rule = B3/S23:T8,6\no!\n");
+
+    assert!(matches!(
+        command("run -r B3/S23:T0,6 -").execute("#Life 1.06\n"),
+        Err(CliError::Rule(RuleError::Topology(_)))
+    ));
+}
+
+#[test]
+fn test_run_on_other_grids() {
+    // on the square grid the domino simply dies
+    let output = command("run -f life106 -")
+        .execute("#Life 1.06\n0 0\n1 0\n")
+        .unwrap();
+    assert_eq!(output, "#Life 1.06\n");
+
+    // on the hexagonal grid the domino dies, giving birth above left and below right
+    let output = command("run -f rle -")
+        .execute("x = 2, y = 1, rule = B2/S34H\n2o!\n")
+        .unwrap();
+    assert_eq!(output, "#R 0 -1\nx = 2, y = 3, rule = B2/S34H\no2$bo!\n");
+
+    let output = command("run -g 0 -r s654/b4l -f rle -")
+        .execute("#Life 1.06\n0 0\n")
+        .unwrap();
+    assert_eq!(output, "x = 1, y = 1, rule = B4/S456L\no!\n");
+
+    assert!(matches!(
+        command("run -r B4/S456L:T8,8 -").execute("#Life 1.06\n"),
+        Err(CliError::Usage(_))
+    ));
+}
+
+#[test]
+fn test_info() {
+    assert_eq!(
+        command("info -")
+            .execute("#Life 1.06\n-1 4\n2 2\n2 2\n")
+            .unwrap(),
+        "population: 2\nbounding box: x = -1, y = 2, width = 4, height = 3\n"
+    );
+    assert_eq!(
+        command("info -").execute("#Life 1.06\n").unwrap(),
+        "population: 0\nbounding box: none\n"
+    );
+    assert!(matches!(
+        command("info x.rle").execute("x = 1\n"),
+        Err(CliError::Pattern(_))
+    ));
+}
+
+#[test]
+fn test_convert() {
+    let output = command("convert blinker.rle -o blinker.cells")
+        .execute(BLINKER_RLE)
+        .unwrap();
+    assert_eq!(output, "!Name: Blinker\nOOO\n");
+
+    let roundtrip = command("convert -f rle -").execute(&output).unwrap();
+    assert_eq!(roundtrip, "#N Blinker\nx = 3, y = 1\n3o!\n");
+
+    assert_eq!(
+        command("convert -f ascii -").execute(&output).unwrap(),
+        "OOO
+"
+    );
+}
+
+#[test]
+fn test_render() {
+    let glider = "x = 3, y = 3\nbo$2bo$3o!\n";
+
+    assert_eq!(
+        command("render -").execute(glider).unwrap(),
+        ".O.\n..O\nOOO\n"
+    );
+    assert_eq!(
+        command("render - -o glider.pbm").execute(glider).unwrap(),
+        "P1\n3 3\n0 1 0\n0 0 1\n1 1 1\n"
+    );
+    assert!(matches!(
+        command("render -f rle -").execute(glider),
+        Err(CliError::Usage(_))
+    ));
+}
+
+#[test]
+fn test_far_apart() {
+    // sparse formats are written as they are, and grids are refused rather than allocated
+    let far_apart = "#Life 1.06\n0 0\n4000000000 4000000000\n";
+
+    assert_eq!(
+        command("convert -f rle -").execute(far_apart).unwrap(),
+        "x = 4000000001, y = 4000000001\no4000000000$4000000000bo!\n"
+    );
+    assert_eq!(
+        command("convert -f life105 -").execute(far_apart).unwrap(),
+        "#Life 1.05\n#N\n#P 0 0\n*\n#P 4000000000 4000000000\n*\n"
+    );
+    for format in ["cells", "ascii", "pbm"] {
+        assert!(matches!(
+            command(&format!("convert -f {} -", format)).execute(far_apart),
+            Err(CliError::Pattern(PatternError::TooLarge(
+                4000000001, 4000000001
+            )))
+        ));
+    }
+    assert!(matches!(
+        command("render -").execute(far_apart),
+        Err(CliError::Pattern(PatternError::TooLarge(_, _)))
+    ));
+}
+
+#[test]
+fn test_macrocell_through_hashlife() {
+    // a cell far out, and a blinker by the edge of the plane
+    let life106 = "#Life 1.06
+4611686018427387904 0
+-9223372036854775807 0
+-9223372036854775807 1
+-9223372036854775807 2
+";
+    let mc = command("convert -f mc -").execute(life106).unwrap();
+
+    assert_eq!(
+        command("info in.mc").execute(&mc).unwrap(),
+        "population: 4\nbounding box: x = -9223372036854775807, y = 0, \
+         width = 13835058055282163712, height = 3\n"
+    );
+
+    // the cell dies and the blinker turns, in the same format unless asked otherwise
+    let output = command("run -g 3 in.mc").execute(&mc).unwrap();
+    assert!(output.starts_with("[M2] (life)\n#R B3/S23\n"));
+    assert_eq!(
+        command("info in.mc").execute(&output).unwrap(),
+        "population: 3\nbounding box: x = -9223372036854775808, y = 1, width = 3, height = 1\n"
+    );
+    assert_eq!(
+        command("run -g 3 in.mc -f life106").execute(&mc).unwrap(),
+        "#Life 1.06\n-9223372036854775808 1\n-9223372036854775807 1\n-9223372036854775806 1\n"
+    );
+}
+
+#[test]
+fn test_allow_broken_pipe() {
+    let error = |kind| Err(io::Error::new(kind, "closed"));
+
+    assert!(allow_broken_pipe(Ok(())).is_ok());
+    assert!(allow_broken_pipe(error(io::ErrorKind::BrokenPipe)).is_ok());
+    assert!(matches!(
+        allow_broken_pipe(error(io::ErrorKind::PermissionDenied)),
+        Err(CliError::Io(_, _))
+    ));
+}
@@ -2,10 +2,11 @@
 #![allow(dead_code)]
 
 use super::multi_iterator::SeekableIterator;
-use super::neighbourhood::Neighbourhood;
+use super::neighbourhood::{Neighbourhood, Window};
 use num::cast::AsPrimitive;
 use num::FromPrimitive;
 use num::One;
+use num::Zero;
 use std::cmp::Ordering;
 use std::cmp::PartialOrd;
 use std::collections::VecDeque;
@@ -14,11 +15,12 @@ use std::ops::Add;
 use std::ops::AddAssign;
 use std::ops::Index;
 use std::ops::IndexMut;
+use std::ops::Range;
 use std::ops::Sub;
 use std::ops::SubAssign;
 
 /// a span of contiguous items
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 struct Span<Idx, T>
 where
     Idx: Copy + Debug,
@@ -97,14 +99,25 @@ where
         self.items.append(&mut other.items);
     }
 
-    /// get the neighbourhood for `i`, which must be in range
-    fn get_neighbourhood(&self, i: Idx) -> Neighbourhood<Idx, &T> {
-        let u = Idx::as_(i - self.origin);
-        let left = (u > 0).then(|| &self.items[u - 1]);
-        let item = &self.items[u];
-        let right = self.items.get(u + 1);
+    /// split off the items from `i` onwards, which must be in range and not the origin
+    fn split_off(&mut self, i: Idx) -> Span<Idx, T> {
+        Span {
+            origin: i,
+            items: self.items.split_off(Idx::as_(i - self.origin)),
+        }
+    }
+
+    /// index one past the last item
+    fn end(&self) -> Idx {
+        self.origin + Idx::from_usize(self.items.len()).unwrap()
+    }
+
+    /// get the neighbourhood of radius `R` for `i`, with only the items in this span
+    fn get_neighbourhood<const R: usize>(&self, i: Idx) -> Neighbourhood<'_, Idx, &T, R> {
+        let radius = Idx::from_usize(R).unwrap();
+        let items = Window::from_fn(|k| self.get(i + Idx::from_usize(k).unwrap() - radius));
 
-        Neighbourhood::new(i, [left, Some(item), right])
+        Neighbourhood::from_window(i, items)
     }
 
     fn cmp(&self, i: &Idx) -> Ordering {
@@ -165,7 +178,7 @@ where
 }
 
 /// an ordered list of spans, ordered by `origin`, and coelesced opportunistically
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Contig<Idx, T>
 where
     Idx: Copy + Debug,
@@ -173,6 +186,49 @@ where
     spans: VecDeque<Span<Idx, T>>,
 }
 
+/// how to compact a contig, trading memory against iteration speed
+///
+/// Fewer, longer spans are faster to iterate and search, at the cost of holding zero items.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CompactionPolicy {
+    /// runs of more zero items than this within a span are removed, splitting the span
+    pub max_zero_run: usize,
+    /// spans separated by at most this many missing items are merged, filling the gap with zero items
+    pub max_gap: usize,
+    /// number of updates between automatic compactions
+    pub update_threshold: usize,
+}
+
+impl CompactionPolicy {
+    /// keep no zero items at all
+    pub fn memory() -> CompactionPolicy {
+        CompactionPolicy {
+            max_zero_run: 0,
+            max_gap: 0,
+            update_threshold: 1024,
+        }
+    }
+
+    /// prefer few spans, at the cost of holding zero items
+    pub fn iteration() -> CompactionPolicy {
+        CompactionPolicy {
+            max_zero_run: 16,
+            max_gap: 8,
+            update_threshold: 4096,
+        }
+    }
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> CompactionPolicy {
+        CompactionPolicy {
+            max_zero_run: 4,
+            max_gap: 2,
+            update_threshold: 1024,
+        }
+    }
+}
+
 enum ContigUpdate {
     Set(usize),
     PushFront(usize),
@@ -220,10 +276,32 @@ where
         }
     }
 
+    /// the index of the first item, which must exist
     pub fn origin(&self) -> Idx {
         self.spans[0].origin
     }
 
+    /// the index and item of the last item, which must exist
+    pub fn last(&self) -> (Idx, &T) {
+        let span = self.spans.back().unwrap();
+        let len = span.items.len();
+        (
+            span.origin + Idx::from_usize(len - 1).unwrap(),
+            &span.items[len - 1],
+        )
+    }
+
+    /// the index and item of the last item for which `f` returns true, searching backwards
+    pub fn rfind<F>(&self, mut f: F) -> Option<(Idx, &T)>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.spans.iter().rev().find_map(|span| {
+            let u = span.items.iter().rposition(&mut f)?;
+            Some((span.origin + Idx::from_usize(u).unwrap(), &span.items[u]))
+        })
+    }
+
     fn determine_update(&self, i: Idx) -> ContigUpdate {
         use ContigUpdate::*;
 
@@ -310,19 +388,191 @@ where
         }
     }
 
+    /// remove the indexed item, splitting its span if required, and returning the item if any
+    ///
+    /// Removing the last item leaves the contig empty, which the caller must handle.
+    pub fn remove(&mut self, i: Idx) -> Option<T> {
+        let u = self.spans.binary_search_by(|c| c.cmp(&i)).ok()?;
+        let span = &mut self.spans[u];
+
+        let item = if i == span.origin {
+            span.origin += Idx::one();
+            span.items.pop_front()
+        } else if span.adjoins_right(i + Idx::one()) {
+            span.items.pop_back()
+        } else {
+            let right = span.split_off(i + Idx::one());
+            let item = span.items.pop_back();
+            self.spans.insert(u + 1, right);
+            item
+        };
+
+        if self.spans[u].items.is_empty() {
+            self.spans.remove(u);
+        }
+        item
+    }
+
+    /// keep only the items for which `f` returns true, splitting spans as required
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Idx, &T) -> bool,
+    {
+        self.retain_mut(|i, item| f(i, item));
+    }
+
+    /// keep only the items for which `f` returns true, as `retain`, where `f` may also
+    /// update the items kept
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Idx, &mut T) -> bool,
+    {
+        let mut spans: VecDeque<Span<Idx, T>> = VecDeque::new();
+
+        for span in self.spans.drain(..) {
+            // whether the last span retained may be extended with the next item
+            let mut extending = false;
+            let mut i = span.origin;
+            for mut item in span.items {
+                if f(i, &mut item) {
+                    match spans.back_mut() {
+                        Some(last) if extending => last.push_back(item),
+                        _ => spans.push_back(Span::new(i, item)),
+                    }
+                    extending = true;
+                } else {
+                    extending = false;
+                }
+                i += Idx::one();
+            }
+        }
+
+        self.spans = spans;
+    }
+
+    /// remove and return all items in `range`, in order
+    pub fn drain_range(&mut self, range: Range<Idx>) -> Vec<(Idx, T)> {
+        let mut drained = Vec::new();
+        let mut spans: VecDeque<Span<Idx, T>> = VecDeque::new();
+
+        for mut span in self.spans.drain(..) {
+            let end = span.origin + Idx::from_usize(span.items.len()).unwrap();
+            if range.start >= range.end || end <= range.start || span.origin >= range.end {
+                spans.push_back(span);
+                continue;
+            }
+
+            if span.origin < range.start {
+                let middle = span.split_off(range.start);
+                spans.push_back(span);
+                span = middle;
+            }
+            let right = (end > range.end).then(|| span.split_off(range.end));
+
+            let mut i = span.origin;
+            for item in span.items {
+                drained.push((i, item));
+                i += Idx::one();
+            }
+
+            if let Some(right) = right {
+                spans.push_back(right);
+            }
+        }
+
+        self.spans = spans;
+        drained
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// remove zero items from span ends, split spans at long runs of zero items, and merge
+    /// spans separated by small gaps, according to `policy`
+    ///
+    /// Compacting away every item leaves the contig empty, which the caller must handle.
+    pub fn compact(&mut self, policy: &CompactionPolicy)
+    where
+        T: Zero,
+    {
+        let mut spans: VecDeque<Span<Idx, T>> = VecDeque::new();
+        // index of the last nonzero item kept
+        let mut last: Option<Idx> = None;
+
+        for span in self.spans.drain(..) {
+            // whether every index since the last nonzero item is present in this span
+            let mut contiguous = false;
+            let mut i = span.origin;
+
+            for item in span.items {
+                if !item.is_zero() {
+                    let gap = last.map(|last| Idx::as_(i - last) - 1);
+                    match (spans.back_mut(), gap) {
+                        (Some(back), Some(gap))
+                            if gap <= policy.max_gap
+                                || (contiguous && gap <= policy.max_zero_run) =>
+                        {
+                            for _ in 0..gap {
+                                back.push_back(T::zero());
+                            }
+                            back.push_back(item);
+                        }
+                        _ => spans.push_back(Span::new(i, item)),
+                    }
+                    last = Some(i);
+                    contiguous = true;
+                }
+                i += Idx::one();
+            }
+        }
+
+        self.spans = spans;
+    }
+
     fn coelesce_left(&mut self, u: usize) {
         if let Some(mut removed_c) = self.spans.remove(u) {
             self.spans[u - 1].append(&mut removed_c);
         }
     }
 
-    pub fn enumerator(&self) -> ContigEnumerator<Idx, T> {
-        let next_i = self.spans[0].origin;
+    pub fn enumerator(&self) -> ContigEnumerator<'_, Idx, T> {
+        let next_i = self
+            .spans
+            .front()
+            .map_or(Idx::default(), |span| span.origin);
 
         ContigEnumerator::new(self, 0, next_i)
     }
 
-    pub fn neighbourhood_enumerator(&self) -> ContigNeighbourhoodEnumerator<Idx, T> {
+    /// enumerate only the items in `range`, starting by binary search
+    pub fn range(&self, range: Range<Idx>) -> ContigEnumerator<'_, Idx, T> {
+        let (u_next, i_next) = match self.spans.binary_search_by(|c| c.cmp(&range.start)) {
+            Ok(u) => (u, range.start),
+            Err(u) => (u, self.spans.get(u).map_or(range.start, |span| span.origin)),
+        };
+
+        ContigEnumerator {
+            c: self,
+            u_next,
+            i_next,
+            end: Some(range.end),
+        }
+    }
+
+    /// the origin and items of each span, in order
+    pub fn spans(&self) -> impl Iterator<Item = (Idx, &VecDeque<T>)> {
+        self.spans.iter().map(|span| (span.origin, &span.items))
+    }
+
+    pub fn neighbourhood_enumerator(&self) -> ContigNeighbourhoodEnumerator<'_, Idx, T> {
+        ContigNeighbourhoodEnumerator::new(self)
+    }
+
+    /// enumerate neighbourhoods of radius `R` for all items and their siblings within `R`
+    pub fn neighbourhood_enumerator_with_radius<const R: usize>(
+        &self,
+    ) -> ContigNeighbourhoodEnumerator<'_, Idx, T, R> {
         ContigNeighbourhoodEnumerator::new(self)
     }
 
@@ -357,6 +607,8 @@ where
     c: &'a Contig<Idx, T>,
     u_next: usize,
     i_next: Idx,
+    /// index at which to stop, if any
+    end: Option<Idx>,
 }
 
 impl<'a, Idx, T> ContigEnumerator<'a, Idx, T>
@@ -374,7 +626,12 @@ where
         + Debug,
 {
     fn new(c: &'a Contig<Idx, T>, u_next: usize, i_next: Idx) -> ContigEnumerator<'a, Idx, T> {
-        ContigEnumerator { c, u_next, i_next }
+        ContigEnumerator {
+            c,
+            u_next,
+            i_next,
+            end: None,
+        }
     }
 
     /// advance the enumerator
@@ -406,7 +663,7 @@ where
     type Item = (Idx, &'a T);
 
     fn next(&mut self) -> Option<(Idx, &'a T)> {
-        if self.u_next < self.c.spans.len() {
+        if self.u_next < self.c.spans.len() && self.end.is_none_or(|end| self.i_next < end) {
             let i = self.i_next;
             let item = &self.c.spans[self.u_next][i];
             self.advance();
@@ -417,17 +674,19 @@ where
     }
 }
 
-/// an iterator which returns neighbourhoods for all items and their adjacent siblings, with indices
-pub struct ContigNeighbourhoodEnumerator<'a, Idx, T>
+/// an iterator which returns neighbourhoods of radius `R` for all items and their siblings
+/// within `R`, with indices
+pub struct ContigNeighbourhoodEnumerator<'a, Idx, T, const R: usize = 1>
 where
     Idx: Copy + Debug,
 {
     c: &'a Contig<Idx, T>,
+    /// the first span whose neighbourhood reaches `i_next`
     u_next: usize,
     i_next: Idx,
 }
 
-impl<'a, Idx, T> ContigNeighbourhoodEnumerator<'a, Idx, T>
+impl<'a, Idx, T, const R: usize> ContigNeighbourhoodEnumerator<'a, Idx, T, R>
 where
     Idx: Copy
         + Default
@@ -441,60 +700,74 @@ where
         + SubAssign
         + Debug,
 {
-    fn new(c: &'a Contig<Idx, T>) -> ContigNeighbourhoodEnumerator<'a, Idx, T> {
+    fn new(c: &'a Contig<Idx, T>) -> ContigNeighbourhoodEnumerator<'a, Idx, T, R> {
         let u_next = 0;
-        let i_next = c.spans[u_next].origin - Idx::one();
+        let i_next = c
+            .spans
+            .front()
+            .map_or(Idx::default(), |span| span.origin - Self::radius());
 
         ContigNeighbourhoodEnumerator { c, u_next, i_next }
     }
 
-    /// return the current neighbourhood without advancing
-    fn get_current(&self) -> Option<Neighbourhood<'a, Idx, &'a T>> {
-        // TODO tidy this up
-        if self.u_next < self.c.spans.len() {
-            let span = &self.c.spans[self.u_next];
-
-            let nbh = if span.contains(self.i_next) {
-                self.c.spans[self.u_next].get_neighbourhood(self.i_next)
-            } else if span.adjoins_left(self.i_next) {
-                // the item before this span, which could be in the gap between spans if that's a gap of one
-                let item_left = self
-                    .c
-                    .get_left_of(self.u_next)
-                    .and_then(|span_left| span_left.get(self.i_next - Idx::one()));
-                Neighbourhood::new(self.i_next, [item_left, None, span.get(span.origin)])
-            } else {
-                assert!(span.adjoins_right(self.i_next));
+    fn radius() -> Idx {
+        Idx::from_usize(R).unwrap()
+    }
 
-                // there'll never be a gap of one situation here, since in that case we would have advanced onto the next span
-                Neighbourhood::new(
-                    self.i_next,
-                    [span.get(self.i_next - Idx::one()), None, None],
-                )
-            };
+    /// the last index whose neighbourhood includes an item of `span`
+    fn reach(span: &Span<Idx, T>) -> Idx {
+        span.end() - Idx::one() + Self::radius()
+    }
 
-            Some(nbh)
-        } else {
-            None
+    /// return the current neighbourhood without advancing
+    fn get_current(&self) -> Option<Neighbourhood<'a, Idx, &'a T, R>> {
+        let span = self.c.spans.get(self.u_next)?;
+        let mut nbh = span.get_neighbourhood::<R>(self.i_next);
+
+        // earlier spans are out of reach, but following ones may not be
+        let end = span.end();
+        let first = self.i_next - Self::radius();
+        for (k, item) in nbh.items.iter_mut().enumerate() {
+            let i = first + Idx::from_usize(k).unwrap();
+            if i >= end {
+                *item = self
+                    .c
+                    .spans
+                    .range(self.u_next + 1..)
+                    .take_while(|span| span.origin <= i)
+                    .find_map(|span| span.get(i));
+            }
         }
+
+        Some(nbh)
     }
 
     fn advance(&mut self) {
         if self.u_next < self.c.spans.len() {
             self.i_next += Idx::one();
-            if !self.c.spans[self.u_next].contains_or_adjoins(self.i_next) {
+            if self.i_next > Self::reach(&self.c.spans[self.u_next]) {
                 self.u_next += 1;
-                if self.u_next < self.c.spans.len() {
-                    self.i_next = self.c.spans[self.u_next].origin - Idx::one();
+                if let Some(span) = self.c.spans.get(self.u_next) {
+                    let start = span.origin - Self::radius();
+                    if start > self.i_next {
+                        self.i_next = start;
+                    }
                 }
             }
-
-            self.u_next = self.c.normalised(self.u_next, self.i_next);
         }
     }
+
+    /// whether `u` is the first span whose neighbourhood reaches `i`
+    fn is_first_reaching(&self, u: usize, i: Idx) -> bool {
+        self.c
+            .spans
+            .get(u)
+            .is_some_and(|span| Self::reach(span) >= i)
+            && (u == 0 || Self::reach(&self.c.spans[u - 1]) < i)
+    }
 }
 
-impl<'a, Idx, T> Iterator for ContigNeighbourhoodEnumerator<'a, Idx, T>
+impl<'a, Idx, T, const R: usize> Iterator for ContigNeighbourhoodEnumerator<'a, Idx, T, R>
 where
     Idx: Copy
         + Default
@@ -508,17 +781,17 @@ where
         + SubAssign
         + Debug,
 {
-    type Item = Neighbourhood<'a, Idx, &'a T>;
+    type Item = Neighbourhood<'a, Idx, &'a T, R>;
 
-    fn next(&mut self) -> Option<Neighbourhood<'a, Idx, &'a T>> {
+    fn next(&mut self) -> Option<Neighbourhood<'a, Idx, &'a T, R>> {
         let result = self.get_current();
         self.advance();
         result
     }
 }
 
-impl<'a, Idx, T> SeekableIterator<Idx, Neighbourhood<'a, Idx, &'a T>>
-    for ContigNeighbourhoodEnumerator<'a, Idx, T>
+impl<'a, Idx, T, const R: usize> SeekableIterator<Idx, Neighbourhood<'a, Idx, &'a T, R>>
+    for ContigNeighbourhoodEnumerator<'a, Idx, T, R>
 where
     Idx: Copy
         + Default
@@ -532,40 +805,25 @@ where
         + SubAssign
         + Debug,
 {
-    /// seek to any index including adjacent locations
-    fn seek(&mut self, i_from: Idx) -> Option<Neighbourhood<'a, Idx, &'a T>> {
-        // look in current and adjacent spans before falling back to find
-        if self.u_next < self.c.spans.len() {
-            let span = &self.c.spans[self.u_next];
-            let span_left_o = self.c.get_left_of(self.u_next);
-            let span_right_o = self.c.spans.get(self.u_next + 1);
-
-            if span.contains(i_from)
-                || span.adjoins_left(i_from)
-                || (span.adjoins_right(i_from)
-                    && !span_right_o.map_or(false, |span_right| span_right.adjoins_left(i_from)))
-            {
-                self.i_next = i_from;
-            } else if span_right_o.map_or(false, |span_right| {
-                span_right.contains(i_from) || span_right.adjoins_left(i_from)
-            }) {
-                // the adjoins right case is handled by find fallback, to avoid checking the right of right span
+    /// seek to any index within reach of an item
+    fn seek(&mut self, i_from: Idx) -> Option<Neighbourhood<'a, Idx, &'a T, R>> {
+        // look in current and next spans before falling back to binary search
+        if !self.is_first_reaching(self.u_next, i_from) {
+            if self.is_first_reaching(self.u_next + 1, i_from) {
                 self.u_next += 1;
-                self.i_next = i_from;
-            } else if span_left_o.map_or(false, |span_left| {
-                span_left.contains(i_from)
-                    || span_left.adjoins_left(i_from)
-                    || span_left.adjoins_right(i_from)
-            }) {
-                self.u_next -= 1;
-                self.i_next = i_from;
             } else {
-                (self.u_next, self.i_next) = self.c.find_with_adjacent(i_from);
+                self.u_next = self
+                    .c
+                    .spans
+                    .partition_point(|span| Self::reach(span) < i_from);
             }
-        } else {
-            (self.u_next, self.i_next) = self.c.find_with_adjacent(i_from);
         }
 
+        self.i_next = match self.c.spans.get(self.u_next) {
+            Some(span) if span.origin - Self::radius() > i_from => span.origin - Self::radius(),
+            _ => i_from,
+        };
+
         if self.i_next == i_from {
             self.next()
         } else {
@@ -573,7 +831,7 @@ where
         }
     }
 
-    fn peek(&self) -> Option<Neighbourhood<'a, Idx, &'a T>> {
+    fn peek(&self) -> Option<Neighbourhood<'a, Idx, &'a T, R>> {
         self.get_current()
     }
 }
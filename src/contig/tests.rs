@@ -166,6 +166,274 @@ fn test_contig_get() {
     assert_eq!(c.get(14), None);
 }
 
+#[test]
+fn test_contig_remove() {
+    let mut c = Contig::new(10, 10u8);
+    for i in 11..15 {
+        c.set(i, i as u8);
+    }
+
+    assert_eq!(c.remove(9), None);
+    assert_eq!(c.remove(15), None);
+
+    // from the middle, splitting the span
+    assert_eq!(c.remove(12), Some(12u8));
+    assert_eq!(
+        c,
+        Contig {
+            spans: VecDeque::from(vec![
+                Span {
+                    origin: 10,
+                    items: VecDeque::from(vec![10u8, 11u8])
+                },
+                Span {
+                    origin: 13,
+                    items: VecDeque::from(vec![13u8, 14u8])
+                }
+            ])
+        }
+    );
+    assert_eq!(c.remove(12), None);
+
+    // from either end
+    assert_eq!(c.remove(10), Some(10u8));
+    assert_eq!(c.remove(14), Some(14u8));
+    assert_eq!(
+        c,
+        Contig {
+            spans: VecDeque::from(vec![
+                Span {
+                    origin: 11,
+                    items: VecDeque::from(vec![11u8])
+                },
+                Span {
+                    origin: 13,
+                    items: VecDeque::from(vec![13u8])
+                }
+            ])
+        }
+    );
+
+    // filling the gap coelesces again
+    c.set(12, 12u8);
+    assert_eq!(
+        c,
+        Contig {
+            spans: VecDeque::from(vec![Span {
+                origin: 11,
+                items: VecDeque::from(vec![11u8, 12u8, 13u8])
+            }])
+        }
+    );
+
+    // removing everything leaves nothing
+    for i in 11..14 {
+        assert_eq!(c.remove(i), Some(i as u8));
+    }
+    assert!(c.is_empty());
+}
+
+/// the spans as origin and items, for brevity
+fn spans(c: &Contig<i32, u8>) -> Vec<(i32, Vec<u8>)> {
+    c.spans()
+        .map(|(origin, items)| (origin, items.iter().copied().collect()))
+        .collect()
+}
+
+fn contig(items: &[i32]) -> Contig<i32, u8> {
+    let mut c = Contig::new(items[0], items[0] as u8);
+    for i in items[1..].iter() {
+        c.set(*i, *i as u8);
+    }
+    c
+}
+
+#[test]
+fn test_contig_retain() {
+    let mut c = contig(&[1, 2, 3, 4, 5, 6, 10, 11, 12]);
+
+    c.retain(|i, _| i % 3 != 0);
+    assert_eq!(
+        spans(&c),
+        vec![(1, vec![1, 2]), (4, vec![4, 5]), (10, vec![10, 11])]
+    );
+
+    // by item, keeping everything
+    c.retain(|_, item| *item < 20);
+    assert_eq!(
+        spans(&c),
+        vec![(1, vec![1, 2]), (4, vec![4, 5]), (10, vec![10, 11])]
+    );
+
+    // whole spans
+    c.retain(|i, _| !(4..=5).contains(&i));
+    assert_eq!(spans(&c), vec![(1, vec![1, 2]), (10, vec![10, 11])]);
+
+    c.retain(|_, _| false);
+    assert!(c.is_empty());
+    assert_eq!(c.enumerator().next(), None);
+    assert!(c.neighbourhood_enumerator().next().is_none());
+}
+
+#[test]
+fn test_contig_retain_mut() {
+    let mut c = contig(&[1, 2, 3, 4, 5, 6, 10, 11, 12]);
+
+    c.retain_mut(|i, item| {
+        *item *= 2;
+        i % 3 != 0
+    });
+    assert_eq!(
+        spans(&c),
+        vec![(1, vec![2, 4]), (4, vec![8, 10]), (10, vec![20, 22])]
+    );
+}
+
+#[test]
+fn test_contig_drain_range() {
+    let mut c = contig(&[1, 2, 3, 4, 5, 6, 10, 11, 12]);
+
+    // within a span, splitting it
+    assert_eq!(c.drain_range(3..5), vec![(3, 3), (4, 4)]);
+    assert_eq!(
+        spans(&c),
+        vec![(1, vec![1, 2]), (5, vec![5, 6]), (10, vec![10, 11, 12])]
+    );
+
+    // empty ranges, and ranges in gaps, drain nothing
+    assert_eq!(c.drain_range(6..6), vec![]);
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = 12..10;
+    assert_eq!(c.drain_range(reversed), vec![]);
+    assert_eq!(c.drain_range(7..10), vec![]);
+    assert_eq!(
+        spans(&c),
+        vec![(1, vec![1, 2]), (5, vec![5, 6]), (10, vec![10, 11, 12])]
+    );
+
+    // across spans, trimming either end
+    assert_eq!(c.drain_range(2..11), vec![(2, 2), (5, 5), (6, 6), (10, 10)]);
+    assert_eq!(spans(&c), vec![(1, vec![1]), (11, vec![11, 12])]);
+
+    // refilling coelesces as before
+    c.set(2, 2);
+    assert_eq!(spans(&c), vec![(1, vec![1, 2]), (11, vec![11, 12])]);
+
+    assert_eq!(c.drain_range(-100..100).len(), 4);
+    assert!(c.is_empty());
+}
+
+#[test]
+fn test_contig_last() {
+    let mut c = contig(&[1, 2, 3, 6, 7, 10]);
+    assert_eq!(c.last(), (10, &10));
+    c.remove(10);
+    assert_eq!(c.last(), (7, &7));
+    c.set(-4, 4);
+    assert_eq!(c.last(), (7, &7));
+}
+
+#[test]
+fn test_contig_rfind() {
+    let c = contig(&[1, 2, 3, 6, 7, 10]);
+    assert_eq!(c.rfind(|item| *item < 100), Some((10, &10)));
+    assert_eq!(c.rfind(|item| *item < 7), Some((6, &6)));
+    assert_eq!(c.rfind(|item| *item < 6), Some((3, &3)));
+    assert_eq!(c.rfind(|item| *item > 100), None);
+}
+
+#[test]
+fn test_contig_range() {
+    let c = contig(&[1, 2, 3, 6, 7, 10]);
+    let range = |r: std::ops::Range<i32>| c.range(r).map(|(i, _)| i).collect::<Vec<i32>>();
+
+    assert_eq!(range(0..100), vec![1, 2, 3, 6, 7, 10]);
+    assert_eq!(range(2..7), vec![2, 3, 6]);
+    // starting and ending in gaps
+    assert_eq!(range(4..9), vec![6, 7]);
+    assert_eq!(range(4..6), vec![]);
+    assert_eq!(range(10..11), vec![10]);
+    assert_eq!(range(11..20), vec![]);
+    assert_eq!(range(-5..1), vec![]);
+    assert_eq!(range(3..3), vec![]);
+
+    assert_eq!(c.range(6..7).next(), Some((6, &6u8)));
+}
+
+/// a contig from items, where 0 is a zero item
+fn contig_of(origin: i32, items: &[u8]) -> Contig<i32, u8> {
+    let mut c = Contig::new(origin, items[0]);
+    for (u, item) in items.iter().enumerate().skip(1) {
+        c.set(origin + u as i32, *item);
+    }
+    c
+}
+
+#[test]
+fn test_contig_compact_memory() {
+    let mut c = contig_of(0, &[0, 0, 1, 2, 0, 3, 0, 0, 0, 4, 0]);
+    c.set(20, 0);
+    c.set(22, 5);
+
+    c.compact(&CompactionPolicy::memory());
+    assert_eq!(
+        spans(&c),
+        vec![(2, vec![1, 2]), (5, vec![3]), (9, vec![4]), (22, vec![5])]
+    );
+
+    // compacting again changes nothing
+    c.compact(&CompactionPolicy::memory());
+    assert_eq!(
+        spans(&c),
+        vec![(2, vec![1, 2]), (5, vec![3]), (9, vec![4]), (22, vec![5])]
+    );
+}
+
+#[test]
+fn test_contig_compact_zero_runs_and_gaps() {
+    let policy = CompactionPolicy {
+        max_zero_run: 2,
+        max_gap: 1,
+        update_threshold: 1,
+    };
+    let mut c = contig_of(0, &[1, 0, 0, 2, 0, 0, 0, 3]);
+    c.set(9, 4);
+    c.set(12, 5);
+
+    c.compact(&policy);
+    // the run of two zeros is kept, the run of three splits the span, the gap of one is
+    // filled, and the gap of two is not
+    assert_eq!(
+        spans(&c),
+        vec![(0, vec![1, 0, 0, 2]), (7, vec![3, 0, 4]), (12, vec![5])]
+    );
+
+    c.compact(&policy);
+    assert_eq!(
+        spans(&c),
+        vec![(0, vec![1, 0, 0, 2]), (7, vec![3, 0, 4]), (12, vec![5])]
+    );
+
+    // a larger gap merges across both zero items and missing items
+    c.compact(&CompactionPolicy {
+        max_zero_run: 0,
+        max_gap: 3,
+        update_threshold: 1,
+    });
+    assert_eq!(
+        spans(&c),
+        vec![(0, vec![1, 0, 0, 2, 0, 0, 0, 3, 0, 4, 0, 0, 5])]
+    );
+}
+
+#[test]
+fn test_contig_compact_to_empty() {
+    let mut c = contig_of(-3, &[0, 0, 0]);
+    c.compact(&CompactionPolicy::default());
+    assert!(c.is_empty());
+    assert_eq!(c.enumerator().next(), None);
+}
+
 #[test]
 fn test_contig_find_with_adjacent() {
     let c = Contig::from(vec![
@@ -193,7 +461,7 @@ fn test_contig_find_with_adjacent() {
 
 #[test]
 fn test_contig_neighbourhood_enumerator() {
-    fn enumerator_as_vec(c: &Contig<i32, u8>) -> Vec<Neighbourhood<i32, &u8>> {
+    fn enumerator_as_vec(c: &Contig<i32, u8>) -> Vec<Neighbourhood<'_, i32, &u8>> {
         c.neighbourhood_enumerator()
             //.map(|nbh| (nbh.i, nbh.left.copied(), *nbh.this, nbh.right.copied()))
             .collect()
@@ -289,3 +557,71 @@ fn test_contig_neighbourhood_enumerator_seek() {
     assert_eq!(e.seek(12).map(|n| (n.i, n.items[1])), Some((12, None)));
     assert_eq!(e.next().map(|n| (n.i, n.items[1])), Some((13, Some(&13))));
 }
+
+/// check the enumerator of radius `R` against a brute force computation
+fn check_neighbourhood_enumerator_with_radius<const R: usize>(c: &Contig<i32, u8>) {
+    let items = c.enumerator().map(|(i, _)| i).collect::<Vec<i32>>();
+    let r = R as i32;
+    let covered = |i: i32| items.iter().any(|item| (item - i).abs() <= r);
+    let expected_window = |i: i32| (i - r..=i + r).map(|j| c.get(j)).collect::<Vec<_>>();
+
+    let mut expected = (items[0] - r..=items[items.len() - 1] + r)
+        .filter(|i| covered(*i))
+        .map(|i| (i, expected_window(i)));
+    for nbh in c.neighbourhood_enumerator_with_radius::<R>() {
+        assert_eq!(
+            Some((nbh.i, nbh.items.iter().copied().collect::<Vec<_>>())),
+            expected.next()
+        );
+    }
+    assert_eq!(expected.next(), None);
+
+    for i in items[0] - r - 2..=items[items.len() - 1] + r + 2 {
+        let mut e = c.neighbourhood_enumerator_with_radius::<R>();
+        let nbh = e.seek(i);
+        assert_eq!(nbh.is_some(), covered(i), "seek {} radius {}", i, R);
+        if let Some(nbh) = nbh {
+            assert_eq!(
+                nbh.items.iter().copied().collect::<Vec<_>>(),
+                expected_window(i)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_contig_neighbourhood_enumerator_with_radius() {
+    for items in [
+        vec![0],
+        vec![0, 1, 2],
+        vec![0, 2, 4, 6],
+        vec![0, 3, 4, 9, 10, 11, 20],
+        vec![-7, -1, 5, 6, 14, 17, 18, 30],
+    ] {
+        let c = contig(&items);
+        check_neighbourhood_enumerator_with_radius::<0>(&c);
+        check_neighbourhood_enumerator_with_radius::<1>(&c);
+        check_neighbourhood_enumerator_with_radius::<2>(&c);
+        check_neighbourhood_enumerator_with_radius::<3>(&c);
+        check_neighbourhood_enumerator_with_radius::<5>(&c);
+    }
+}
+
+#[test]
+fn test_contig_neighbourhood_enumerator_seek_forward_with_radius() {
+    let c = contig(&[0, 3, 4, 9, 20]);
+    let mut e = c.neighbourhood_enumerator_with_radius::<2>();
+
+    assert_eq!(e.seek(-3), None);
+    assert_eq!(e.peek().map(|n| n.i), Some(-2));
+    assert_eq!(
+        e.seek(1)
+            .map(|n| n.items.iter().copied().collect::<Vec<_>>()),
+        Some(vec![None, Some(&0), None, None, Some(&3)])
+    );
+    assert_eq!(e.seek(7).map(|n| n.i), Some(7));
+    assert_eq!(e.seek(12).map(|n| n.i), None);
+    assert_eq!(e.next().map(|n| n.i), Some(18));
+    assert_eq!(e.seek(23).map(|n| n.i), None);
+    assert_eq!(e.next().map(|n| n.i), None);
+}
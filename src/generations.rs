@@ -0,0 +1,263 @@
+// TODO remove suppression for dead code warning
+#![allow(dead_code)]
+
+use super::cartesian::{CartesianContig, CartesianNeighbourhood, Coordinate};
+use super::rule::{NeighbourhoodKind, Rule, RuleError};
+use super::topology::Topology;
+use num::cast::AsPrimitive;
+use num::FromPrimitive;
+use num::Integer;
+use num::One;
+use num::Zero;
+use std::cmp::PartialOrd;
+use std::fmt;
+use std::fmt::Debug;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Sub;
+use std::ops::SubAssign;
+use std::str::FromStr;
+
+/// the state of a cell, where 0 is dead, 1 is live, and any higher state is dying
+pub type State = u8;
+
+pub const DEAD: State = 0;
+pub const LIVE: State = 1;
+
+/// a Generations rule, where cells which fail to survive pass through refractory states
+/// before dying, and only live cells count as neighbours
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GenerationsRule {
+    rule: Rule,
+    /// number of states, including dead and live
+    states: usize,
+}
+
+impl GenerationsRule {
+    pub fn new(rule: Rule, states: usize) -> Result<GenerationsRule, RuleError> {
+        if (2..=State::MAX as usize + 1).contains(&states) {
+            Ok(GenerationsRule { rule, states })
+        } else {
+            Err(RuleError::States(states))
+        }
+    }
+
+    /// Brian's Brain, /2/3
+    pub fn brians_brain() -> GenerationsRule {
+        GenerationsRule {
+            rule: Rule::new(&[2], &[]),
+            states: 3,
+        }
+    }
+
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    /// lookup table from state and 3x3 neighbourhood index of live cells to next state
+    ///
+    /// The index is as for `Rule::transition_table`, and for a cell which is not live
+    /// the centre bit is clear.
+    pub fn transition_table(&self) -> Vec<[State; 2]> {
+        self.rule
+            .transition_table()
+            .into_iter()
+            .map(|live| if live { [LIVE, LIVE] } else { [DEAD, LIVE + 1] })
+            .collect()
+    }
+
+    /// the next state of a cell in `state`, given the lookup from `transition_table`
+    fn next_state(&self, state: State, transition: [State; 2]) -> State {
+        next_state(self.states, state, transition)
+    }
+}
+
+/// the next state of a cell in `state` for a rule with `states` states, where `transition`
+/// gives the next state for a dead cell and a live cell
+pub(crate) fn next_state(states: usize, state: State, transition: [State; 2]) -> State {
+    // widened, as the last dying state of a rule with every state is the largest `State`
+    let next = match state {
+        DEAD => transition[0] as usize,
+        LIVE => transition[1] as usize,
+        dying => dying as usize + 1,
+    };
+    if next >= states {
+        DEAD
+    } else {
+        next as State
+    }
+}
+
+impl FromStr for GenerationsRule {
+    type Err = RuleError;
+
+    /// parse either `345/2/4` S/B/C notation or `B2/S345/C4` notation, optionally followed
+    /// by a neighbourhood suffix, as in `B2/S34/C3H`
+    fn from_str(s: &str) -> Result<GenerationsRule, RuleError> {
+        let syntax = || RuleError::Syntax(s.to_string());
+
+        let (bsc, kind) = NeighbourhoodKind::split_suffix(s);
+        let (life_like, states) = bsc.rsplit_once('/').ok_or_else(syntax)?;
+        let states = states.strip_prefix(['C', 'c']).unwrap_or(states);
+        let states = states.parse::<usize>().map_err(|_| syntax())?;
+
+        let life_like = format!("{}{}", life_like, kind.suffix());
+        GenerationsRule::new(life_like.parse::<Rule>()?, states)
+    }
+}
+
+impl fmt::Display for GenerationsRule {
+    /// canonical B/S/C notation, followed by the neighbourhood suffix if any
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/C{}{}",
+            self.rule.bs_notation(),
+            self.states,
+            self.rule.kind().suffix()
+        )
+    }
+}
+
+/// Array of multi-state cells, one per item, for Generations rules
+pub struct GenerationsPlayfield<Idx>
+where
+    Idx: Copy
+        + Default
+        + One
+        + FromPrimitive
+        + AsPrimitive<usize>
+        + Add<Output = Idx>
+        + Sub<Output = Idx>
+        + PartialOrd
+        + AddAssign
+        + SubAssign
+        + Debug,
+{
+    pub(crate) cc: Option<CartesianContig<Idx, State>>,
+}
+
+impl<Idx> GenerationsPlayfield<Idx>
+where
+    Idx: Copy
+        + Default
+        + One
+        + FromPrimitive
+        + AsPrimitive<usize>
+        + Add<Output = Idx>
+        + Sub<Output = Idx>
+        + PartialOrd
+        + AddAssign
+        + SubAssign
+        + Zero
+        + Ord
+        + Debug,
+{
+    pub(crate) fn new() -> GenerationsPlayfield<Idx> {
+        GenerationsPlayfield { cc: None }
+    }
+
+    /// set the cell at `x`, `y`, creating the underlying contig if required
+    pub(crate) fn set_cell(&mut self, x: Idx, y: Idx, state: State) {
+        match &mut self.cc {
+            Some(cc) => cc.set(x, y, state),
+            None => self.cc = Some(CartesianContig::new(x, y, state)),
+        }
+    }
+
+    pub fn from_rows(rows: &[Vec<State>], origin: Coordinate<Idx>) -> GenerationsPlayfield<Idx> {
+        let mut playfield = GenerationsPlayfield::<Idx>::new();
+
+        for (y_u, row) in rows.iter().enumerate() {
+            for (x_u, state) in row.iter().enumerate() {
+                if *state != DEAD {
+                    let x = Idx::from_usize(x_u).unwrap() + origin.x;
+                    let y = Idx::from_usize(y_u).unwrap() + origin.y;
+                    playfield.set_cell(x, y, *state);
+                }
+            }
+        }
+
+        playfield
+    }
+
+    pub fn to_rows(&self) -> (Vec<Vec<State>>, Coordinate<Idx>) {
+        let cc = match &self.cc {
+            Some(cc) => cc,
+            None => return (Vec::new(), Coordinate::default()),
+        };
+        let origin = cc.origin();
+        let mut rows: Vec<Vec<State>> = Vec::new();
+
+        for (y, row) in cc.rows_enumerator() {
+            // empty rows for any gap
+            while origin.y < y - Idx::from_usize(rows.len()).unwrap() {
+                rows.push(Vec::new());
+            }
+
+            let mut states = Vec::new();
+            for (x, state) in row.enumerator() {
+                while origin.x < x - Idx::from_usize(states.len()).unwrap() {
+                    states.push(DEAD);
+                }
+                states.push(*state);
+            }
+            rows.push(states);
+        }
+        (rows, origin)
+    }
+
+    /// compute the next generation under `rule`
+    pub fn step(&self, rule: &GenerationsRule) -> GenerationsPlayfield<Idx> {
+        match &self.cc {
+            Some(cc) => Self::step_neighbourhoods(cc.neighbourhood_enumerator(), rule),
+            None => GenerationsPlayfield::new(),
+        }
+    }
+
+    /// compute the next generation under `rule` on `topology`, ignoring any cells outside it
+    pub fn step_with_topology(
+        &self,
+        rule: &GenerationsRule,
+        topology: Topology,
+    ) -> GenerationsPlayfield<Idx>
+    where
+        Idx: Integer,
+    {
+        match &self.cc {
+            Some(cc) => {
+                Self::step_neighbourhoods(cc.neighbourhood_enumerator_with_topology(topology), rule)
+            }
+            None => GenerationsPlayfield::new(),
+        }
+    }
+
+    /// the next generation from the neighbourhoods of every cell which may not be dead
+    fn step_neighbourhoods<'a, I>(nbhs: I, rule: &GenerationsRule) -> GenerationsPlayfield<Idx>
+    where
+        I: Iterator<Item = CartesianNeighbourhood<Idx, &'a State>>,
+    {
+        let mut next = GenerationsPlayfield::<Idx>::new();
+        let table = rule.transition_table();
+
+        for nbh in nbhs {
+            let state = nbh.items[1][1].copied().unwrap_or(DEAD);
+            let state = rule.next_state(state, table[Self::live_index(&nbh)]);
+            if state != DEAD {
+                next.set_cell(nbh.i_col, nbh.i_row, state);
+            }
+        }
+
+        next
+    }
+
+    /// the 3x3 neighbourhood index of live cells, with the top left cell as the most
+    /// significant bit
+    fn live_index(nbh: &CartesianNeighbourhood<Idx, &State>) -> usize {
+        nbh.items.iter().flatten().fold(0, |index, cell| {
+            (index << 1) | (cell == &Some(&LIVE)) as usize
+        })
+    }
+}
+
+mod tests;
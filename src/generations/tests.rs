@@ -0,0 +1,129 @@
+#![cfg(test)]
+use super::*;
+
+#[test]
+fn test_parse_generations_rule() {
+    assert_eq!(
+        "/2/3".parse::<GenerationsRule>(),
+        Ok(GenerationsRule::brians_brain())
+    );
+    assert_eq!(
+        "B2/S/C3".parse::<GenerationsRule>(),
+        Ok(GenerationsRule::brians_brain())
+    );
+    assert_eq!(
+        "345/2/4".parse::<GenerationsRule>(),
+        GenerationsRule::new(Rule::new(&[2], &[3, 4, 5]), 4)
+    );
+    assert_eq!(
+        "345/2/4".parse::<GenerationsRule>().unwrap().to_string(),
+        "B2/S345/C4"
+    );
+    assert_eq!(
+        "345/2/1".parse::<GenerationsRule>(),
+        Err(RuleError::States(1))
+    );
+    assert_eq!(
+        "345/2/x".parse::<GenerationsRule>(),
+        Err(RuleError::Syntax("345/2/x".to_string()))
+    );
+    assert_eq!(
+        "B3/S23".parse::<GenerationsRule>(),
+        Err(RuleError::Syntax("B3/S23".to_string()))
+    );
+}
+
+#[test]
+fn test_parse_hexagonal_generations_rule() {
+    let rule = GenerationsRule::new(Rule::hexagonal(&[2], &[3, 4]), 3);
+    assert_eq!("B2/S34/C3H".parse::<GenerationsRule>(), rule);
+    assert_eq!("34/2/3h".parse::<GenerationsRule>(), rule);
+    assert_eq!(rule.unwrap().to_string(), "B2/S34/C3H");
+    assert_eq!(
+        "B1/S12/C4V".parse::<GenerationsRule>().unwrap().to_string(),
+        "B1/S12/C4V"
+    );
+    assert_eq!(
+        "B2/S34/C3L".parse::<GenerationsRule>(),
+        Err(RuleError::Syntax("B2/S34/C3L".to_string()))
+    );
+}
+
+#[test]
+fn test_to_from_rows_roundtrip() {
+    let rows: Vec<Vec<State>> = vec![vec![0, 1, 2], vec![], vec![3, 0, 1]];
+    let origin = Coordinate { x: -4, y: 7 };
+
+    let p = GenerationsPlayfield::<i32>::from_rows(&rows, origin);
+    assert_eq!(
+        p.to_rows(),
+        (
+            vec![vec![0, 1, 2], vec![], vec![3, 0, 1]],
+            Coordinate { x: -4, y: 7 }
+        )
+    );
+
+    // leading dead cells are not retained
+    let rows: Vec<Vec<State>> = vec![vec![], vec![0, 0, 1]];
+    let p = GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+    assert_eq!(p.to_rows(), (vec![vec![1]], Coordinate { x: 2, y: 1 }));
+}
+
+#[test]
+fn test_step_brians_brain() {
+    // a pair of live cells, dying as the cells above and below are born
+    let rule = GenerationsRule::brians_brain();
+    let rows: Vec<Vec<State>> = vec![vec![0, 0], vec![1, 1], vec![0, 0]];
+    let p0 = GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+
+    let p1 = p0.step(&rule);
+    assert_eq!(
+        p1.to_rows(),
+        (
+            vec![vec![1, 1], vec![2, 2], vec![1, 1]],
+            Coordinate { x: 0, y: 0 }
+        )
+    );
+
+    // dying cells are not counted as neighbours, and die after one generation
+    let p2 = p1.step(&rule);
+    assert_eq!(
+        p2.to_rows(),
+        (
+            vec![
+                vec![0, 1, 1],
+                vec![0, 2, 2],
+                vec![1, 0, 0, 1],
+                vec![0, 2, 2],
+                vec![0, 1, 1],
+            ],
+            Coordinate { x: -1, y: -1 }
+        )
+    );
+}
+
+#[test]
+fn test_step_star_wars_refractory() {
+    // an isolated live cell fails to survive, then decays through the refractory states
+    let rule = "345/2/4".parse::<GenerationsRule>().unwrap();
+    let p = GenerationsPlayfield::<i32>::from_rows(&[vec![1]], Coordinate { x: 3, y: 3 });
+
+    let p = p.step(&rule);
+    assert_eq!(p.to_rows(), (vec![vec![2]], Coordinate { x: 3, y: 3 }));
+    let p = p.step(&rule);
+    assert_eq!(p.to_rows(), (vec![vec![3]], Coordinate { x: 3, y: 3 }));
+    let p = p.step(&rule);
+    assert_eq!(p.to_rows(), (vec![], Coordinate::default()));
+}
+
+#[test]
+fn test_step_last_of_every_state() {
+    // with every state in use, a cell in the largest state dies rather than overflowing
+    let rule = "B2/S/C256".parse::<GenerationsRule>().unwrap();
+    let p = GenerationsPlayfield::<i32>::from_rows(&[vec![254, 255]], Coordinate { x: 0, y: 0 });
+
+    let p = p.step(&rule);
+    assert_eq!(p.to_rows(), (vec![vec![255]], Coordinate { x: 0, y: 0 }));
+    let p = p.step(&rule);
+    assert_eq!(p.to_rows(), (vec![], Coordinate::default()));
+}
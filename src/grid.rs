@@ -0,0 +1,110 @@
+// TODO remove suppression for dead code warning
+#![allow(dead_code)]
+
+/// the shape of the cells, each of which is emulated on the square grid, as in Golly
+/// and LifeViewer
+///
+/// On the hexagonal grid each row is offset half a cell from the one above, so that the
+/// neighbours are those of the Moore neighbourhood other than the top right and bottom left.
+///
+/// On the triangular grid the cell at `x`, `y` points up if `x + y` is even, and down
+/// otherwise, and its neighbours are the twelve cells which share an edge or a vertex.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Grid {
+    #[default]
+    Square,
+    Hexagonal,
+    Triangular,
+}
+
+/// offsets of the neighbours on the square grid, by row then column
+const SQUARE: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// offsets of the neighbours on the hexagonal grid, by row then column
+const HEXAGONAL: [(isize, isize); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+/// offsets of the neighbours of a triangle pointing up, by row then column, where those
+/// of a triangle pointing down are the same reflected top to bottom
+const TRIANGULAR: [(isize, isize); 12] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (2, 0),
+    (-2, 1),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (2, 1),
+];
+
+impl Grid {
+    /// the letter which follows a rulestring for this grid, if any
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Grid::Square => "",
+            Grid::Hexagonal => "H",
+            Grid::Triangular => "L",
+        }
+    }
+
+    /// the greatest distance of a neighbour in either direction
+    pub fn radius(self) -> usize {
+        match self {
+            Grid::Square | Grid::Hexagonal => 1,
+            Grid::Triangular => 2,
+        }
+    }
+
+    pub fn neighbour_count(self) -> usize {
+        self.offsets(true).len()
+    }
+
+    /// offsets of the neighbours of a cell, as column then row, where `even` is whether
+    /// the sum of its coordinates is even
+    pub fn offsets(self, even: bool) -> Vec<(isize, isize)> {
+        match self {
+            Grid::Square => SQUARE.to_vec(),
+            Grid::Hexagonal => HEXAGONAL.to_vec(),
+            Grid::Triangular if even => TRIANGULAR.to_vec(),
+            Grid::Triangular => {
+                let mut offsets = TRIANGULAR
+                    .iter()
+                    .map(|(dx, dy)| (*dx, -dy))
+                    .collect::<Vec<_>>();
+                offsets.sort_by_key(|(dx, dy)| (*dy, *dx));
+                offsets
+            }
+        }
+    }
+
+    /// split the grid suffix, in either case, from the end of a rulestring
+    pub fn split_suffix(s: &str) -> (&str, Grid) {
+        let s = s.trim();
+        for grid in [Grid::Hexagonal, Grid::Triangular] {
+            if let Some(rest) = strip_suffix_ignoring_case(s, grid.suffix()) {
+                return (rest, grid);
+            }
+        }
+        (s, Grid::Square)
+    }
+}
+
+/// `s` without the ASCII `suffix` in either case, if it ends with it
+pub(crate) fn strip_suffix_ignoring_case<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    s.strip_suffix(suffix)
+        .or_else(|| s.strip_suffix(suffix.to_ascii_lowercase().as_str()))
+}
+
+mod tests;
@@ -0,0 +1,72 @@
+#![cfg(test)]
+use super::*;
+
+#[test]
+fn test_offsets() {
+    for grid in [Grid::Square, Grid::Hexagonal, Grid::Triangular] {
+        let r = grid.radius() as isize;
+        for even in [true, false] {
+            let offsets = grid.offsets(even);
+            assert_eq!(offsets.len(), grid.neighbour_count());
+            assert!(!offsets.contains(&(0, 0)));
+            assert!(offsets
+                .iter()
+                .all(|(dx, dy)| dx.abs() <= r && dy.abs() <= r));
+            let mut sorted = offsets.clone();
+            sorted.sort_by_key(|(dx, dy)| (*dy, *dx));
+            sorted.dedup();
+            assert_eq!(sorted, offsets);
+        }
+    }
+    assert_eq!(Grid::Triangular.neighbour_count(), 12);
+    assert!(!Grid::Hexagonal.offsets(true).contains(&(1, -1)));
+    assert!(!Grid::Hexagonal.offsets(true).contains(&(-1, 1)));
+}
+
+#[test]
+fn test_neighbours_are_mutual() {
+    for grid in [Grid::Square, Grid::Hexagonal, Grid::Triangular] {
+        for (x, y) in [(0isize, 0isize), (1, 0), (0, 1), (3, 5), (-2, 7)] {
+            let even = (x + y) % 2 == 0;
+            for (dx, dy) in grid.offsets(even) {
+                let (nx, ny) = (x + dx, y + dy);
+                let n_even = (nx + ny) % 2 == 0;
+                assert!(
+                    grid.offsets(n_even).contains(&(-dx, -dy)),
+                    "{:?} ({}, {}) and ({}, {})",
+                    grid,
+                    x,
+                    y,
+                    nx,
+                    ny
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_triangles_share_edges_with_three_neighbours() {
+    // pointing up, with neighbours across the base below, and either side
+    let up = Grid::Triangular.offsets(true);
+    let down = Grid::Triangular.offsets(false);
+    for edge in [(-1, 0), (1, 0), (0, 1)] {
+        assert!(up.contains(&edge));
+    }
+    for edge in [(-1, 0), (1, 0), (0, -1)] {
+        assert!(down.contains(&edge));
+    }
+    // the cell beyond the apex shares only a vertex
+    assert!(up.contains(&(0, -1)) && !up.contains(&(0, -2)));
+}
+
+#[test]
+fn test_split_suffix() {
+    assert_eq!(Grid::split_suffix("B3/S23"), ("B3/S23", Grid::Square));
+    assert_eq!(Grid::split_suffix("B2/S34H"), ("B2/S34", Grid::Hexagonal));
+    assert_eq!(Grid::split_suffix("b2/s34h "), ("b2/s34", Grid::Hexagonal));
+    assert_eq!(
+        Grid::split_suffix("B4/S456L"),
+        ("B4/S456", Grid::Triangular)
+    );
+}
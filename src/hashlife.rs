@@ -0,0 +1,613 @@
+// TODO remove suppression for dead code warning
+#![allow(dead_code)]
+
+use super::cartesian::Coordinate;
+use super::playfield::{Block, Playfield};
+use super::rule::Rule;
+use num::cast::AsPrimitive;
+use num::FromPrimitive;
+use num::Integer;
+use num::One;
+use std::cmp::PartialOrd;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Sub;
+use std::ops::SubAssign;
+
+/// index of a node in the store
+pub type NodeId = usize;
+
+pub(crate) const DEAD: NodeId = 0;
+pub(crate) const LIVE: NodeId = 1;
+
+/// smallest level for the root, so that it always has a centre to step
+const MIN_ROOT_LEVEL: u32 = 3;
+
+/// default number of nodes beyond which the store is garbage collected after a step
+const DEFAULT_NODE_LIMIT: usize = 1 << 22;
+
+/// a square of side `2^level` cells, made of four quadrants of the level below,
+/// or a single cell at level 0
+#[derive(Debug)]
+struct Node {
+    level: u32,
+    /// quadrants in the order nw, ne, sw, se
+    children: [NodeId; 4],
+    population: u64,
+}
+
+/// canonical store of nodes, where each distinct square is represented exactly once
+#[derive(Debug)]
+struct NodeStore {
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 4], NodeId>,
+    /// memoised successors, by node and log2 of the number of generations
+    successors: HashMap<(NodeId, u32), NodeId>,
+    /// the empty node at each level
+    empty: Vec<NodeId>,
+}
+
+impl NodeStore {
+    fn new() -> NodeStore {
+        let cell = |population| Node {
+            level: 0,
+            children: [DEAD; 4],
+            population,
+        };
+        NodeStore {
+            nodes: vec![cell(0), cell(1)],
+            index: HashMap::new(),
+            successors: HashMap::new(),
+            empty: vec![DEAD],
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn level(&self, n: NodeId) -> u32 {
+        self.nodes[n].level
+    }
+
+    fn population(&self, n: NodeId) -> u64 {
+        self.nodes[n].population
+    }
+
+    fn children(&self, n: NodeId) -> [NodeId; 4] {
+        self.nodes[n].children
+    }
+
+    /// the canonical node with the given quadrants, which must all be of the same level
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(n) = self.index.get(&children) {
+            return *n;
+        }
+
+        let level = self.level(children[0]) + 1;
+        let population = children.iter().map(|c| self.population(*c)).sum();
+        let n = self.nodes.len();
+        self.nodes.push(Node {
+            level,
+            children,
+            population,
+        });
+        self.index.insert(children, n);
+        n
+    }
+
+    fn empty(&mut self, level: u32) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let next = self.join([e; 4]);
+            self.empty.push(next);
+        }
+        self.empty[level as usize]
+    }
+
+    /// the centred square of half the size
+    fn centre(&mut self, n: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(n);
+        self.join([
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        ])
+    }
+
+    /// the square of twice the size with `n` at its centre
+    fn expand(&mut self, n: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(n);
+        let e = self.empty(self.level(n) - 1);
+        let nw = self.join([e, e, e, nw]);
+        let ne = self.join([e, e, ne, e]);
+        let sw = self.join([e, sw, e, e]);
+        let se = self.join([se, e, e, e]);
+        self.join([nw, ne, sw, se])
+    }
+
+    /// whether cell `x`, `y` of node `n` is live, with the origin at the top left corner
+    fn get(&self, n: NodeId, x: u128, y: u128) -> bool {
+        let level = self.level(n);
+        if level == 0 {
+            n == LIVE
+        } else {
+            let half = 1 << (level - 1);
+            let quadrant = (y >= half) as usize * 2 + (x >= half) as usize;
+            self.get(self.children(n)[quadrant], x % half, y % half)
+        }
+    }
+
+    /// node `n` with cell `x`, `y` set as given, with the origin at the top left corner
+    fn set(&mut self, n: NodeId, x: u128, y: u128, live: bool) -> NodeId {
+        let level = self.level(n);
+        if level == 0 {
+            if live {
+                LIVE
+            } else {
+                DEAD
+            }
+        } else {
+            let half = 1 << (level - 1);
+            let quadrant = (y >= half) as usize * 2 + (x >= half) as usize;
+            let mut children = self.children(n);
+            children[quadrant] = self.set(children[quadrant], x % half, y % half, live);
+            self.join(children)
+        }
+    }
+
+    /// append the live cells of node `n` with top left corner at `x`, `y`, leaving out any
+    /// beyond the range of `i64`
+    fn cells(&self, n: NodeId, x: i128, y: i128, cells: &mut Vec<Coordinate<i64>>) {
+        if self.population(n) == 0 {
+            return;
+        }
+
+        let level = self.level(n);
+        if level == 0 {
+            if let (Ok(x), Ok(y)) = (i64::try_from(x), i64::try_from(y)) {
+                cells.push(Coordinate { x, y });
+            }
+        } else {
+            let half = 1 << (level - 1);
+            let [nw, ne, sw, se] = self.children(n);
+            self.cells(nw, x, y, cells);
+            self.cells(ne, x + half, y, cells);
+            self.cells(sw, x, y + half, cells);
+            self.cells(se, x + half, y + half, cells);
+        }
+    }
+
+    /// the least or, if `last`, the greatest offset from the corner of node `n` of any live
+    /// cell, across if `vertical` is false and otherwise down, using and filling `memo`
+    fn extent(
+        &self,
+        n: NodeId,
+        vertical: bool,
+        last: bool,
+        memo: &mut HashMap<NodeId, u128>,
+    ) -> Option<u128> {
+        if self.population(n) == 0 {
+            return None;
+        }
+        let level = self.level(n);
+        if level == 0 {
+            return Some(0);
+        }
+        if let Some(e) = memo.get(&n) {
+            return Some(*e);
+        }
+
+        // the quadrants nearer the start, and those a half further on
+        let half = 1u128 << (level - 1);
+        let [nw, ne, sw, se] = self.children(n);
+        let (near, far) = if vertical {
+            ([nw, ne], [sw, se])
+        } else {
+            ([nw, sw], [ne, se])
+        };
+        let sides = if last {
+            [(far, half), (near, 0)]
+        } else {
+            [(near, 0), (far, half)]
+        };
+
+        // from the first side with any live cell
+        let e = sides.into_iter().find_map(|(side, offset)| {
+            let [a, b] = side.map(|child| self.extent(child, vertical, last, memo));
+            let e = match (a, b) {
+                (Some(a), Some(b)) if last => a.max(b),
+                (Some(a), Some(b)) => a.min(b),
+                (a, b) => a.or(b)?,
+            };
+            Some(e + offset)
+        })?;
+        memo.insert(n, e);
+        Some(e)
+    }
+
+    /// the centred square of half the size, `2^j` generations on, where `j` is at most
+    /// two less than the level of `n`
+    fn successor(&mut self, n: NodeId, j: u32, table: &[bool]) -> NodeId {
+        let level = self.level(n);
+        assert!(level >= 2 && j <= level - 2);
+
+        if self.population(n) == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(s) = self.successors.get(&(n, j)) {
+            return *s;
+        }
+
+        let s = if level == 2 {
+            self.successor_of_leaf(n, table)
+        } else {
+            // nine overlapping squares of half the size, by row then column
+            let [nw, ne, sw, se] = self.children(n);
+            let [_nw_nw, nw_ne, nw_sw, nw_se] = self.children(nw);
+            let [ne_nw, _ne_ne, ne_sw, ne_se] = self.children(ne);
+            let [sw_nw, sw_ne, _sw_sw, sw_se] = self.children(sw);
+            let [se_nw, se_ne, se_sw, _se_se] = self.children(se);
+
+            let squares = [
+                nw,
+                self.join([nw_ne, ne_nw, nw_se, ne_sw]),
+                ne,
+                self.join([nw_sw, nw_se, sw_nw, sw_ne]),
+                self.join([nw_se, ne_sw, sw_ne, se_nw]),
+                self.join([ne_sw, ne_se, se_nw, se_ne]),
+                sw,
+                self.join([sw_ne, se_nw, sw_se, se_sw]),
+                se,
+            ];
+
+            // advance by half in each of two stages for a full step, otherwise only in the second
+            let full = j == level - 2;
+            let mut r = [DEAD; 9];
+            for (square, r) in squares.iter().zip(r.iter_mut()) {
+                *r = if full {
+                    self.successor(*square, j - 1, table)
+                } else {
+                    self.centre(*square)
+                };
+            }
+
+            let j_second = if full { j - 1 } else { j };
+            let quadrants = [
+                self.join([r[0], r[1], r[3], r[4]]),
+                self.join([r[1], r[2], r[4], r[5]]),
+                self.join([r[3], r[4], r[6], r[7]]),
+                self.join([r[4], r[5], r[7], r[8]]),
+            ];
+            let mut s = [DEAD; 4];
+            for (quadrant, s) in quadrants.iter().zip(s.iter_mut()) {
+                *s = self.successor(*quadrant, j_second, table);
+            }
+            self.join(s)
+        };
+
+        self.successors.insert((n, j), s);
+        s
+    }
+
+    /// the centre 2x2 of a 4x4 node, one generation on
+    fn successor_of_leaf(&mut self, n: NodeId, table: &[bool]) -> NodeId {
+        let mut centre = [DEAD; 4];
+        for (u, cell) in centre.iter_mut().enumerate() {
+            let (x0, y0) = ((u % 2) as u128, (u / 2) as u128);
+            let mut index = 0;
+            for y in y0..y0 + 3 {
+                for x in x0..x0 + 3 {
+                    index = (index << 1) | self.get(n, x, y) as usize;
+                }
+            }
+            *cell = if table[index] { LIVE } else { DEAD };
+        }
+        self.join(centre)
+    }
+
+    /// copy node `n` and everything reachable from it, including memoised successors,
+    /// into `store`, returning its id there
+    fn copy_into(
+        &self,
+        n: NodeId,
+        store: &mut NodeStore,
+        copied: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if n == DEAD || n == LIVE {
+            return n;
+        }
+        if let Some(c) = copied.get(&n) {
+            return *c;
+        }
+
+        let mut children = self.children(n);
+        for child in children.iter_mut() {
+            *child = self.copy_into(*child, store, copied);
+        }
+        let c = store.join(children);
+        copied.insert(n, c);
+
+        for j in 0..=self.level(n).saturating_sub(2) {
+            if let Some(s) = self.successors.get(&(n, j)) {
+                let s = self.copy_into(*s, store, copied);
+                store.successors.insert((c, j), s);
+            }
+        }
+        c
+    }
+}
+
+/// the HashLife engine, a quadtree of canonical nodes with memoised successors,
+/// centred on the origin
+#[derive(Debug)]
+pub struct HashLife {
+    store: NodeStore,
+    root: NodeId,
+    rule: Rule,
+    table: Vec<bool>,
+    generation: u64,
+    node_limit: usize,
+}
+
+impl HashLife {
+    pub fn new(rule: &Rule) -> HashLife {
+        let mut store = NodeStore::new();
+        let root = store.empty(MIN_ROOT_LEVEL);
+        HashLife {
+            store,
+            root,
+            rule: rule.clone(),
+            table: rule.transition_table(),
+            generation: 0,
+            node_limit: DEFAULT_NODE_LIMIT,
+        }
+    }
+
+    pub fn from_cells<I>(rule: &Rule, cells: I) -> HashLife
+    where
+        I: IntoIterator<Item = Coordinate<i64>>,
+    {
+        let mut hashlife = HashLife::new(rule);
+        for cell in cells {
+            hashlife.set_cell(cell.x, cell.y, true);
+        }
+        hashlife
+    }
+
+    pub fn from_playfield<Idx, T>(rule: &Rule, playfield: &Playfield<Idx, T>) -> HashLife
+    where
+        T: Block,
+        Idx: Copy
+            + Default
+            + One
+            + FromPrimitive
+            + AsPrimitive<usize>
+            + AsPrimitive<i64>
+            + Add<Output = Idx>
+            + Sub<Output = Idx>
+            + PartialOrd
+            + AddAssign
+            + SubAssign
+            + Integer
+            + Debug,
+    {
+        HashLife::from_cells(
+            rule,
+            playfield.cells().into_iter().map(|cell| Coordinate {
+                x: cell.x.as_(),
+                y: cell.y.as_(),
+            }),
+        )
+    }
+
+    pub fn to_playfield<Idx, T>(&self) -> Playfield<Idx, T>
+    where
+        T: Block,
+        u128: AsPrimitive<T>,
+        Idx: Copy
+            + Default
+            + One
+            + FromPrimitive
+            + AsPrimitive<usize>
+            + Add<Output = Idx>
+            + Sub<Output = Idx>
+            + PartialOrd
+            + AddAssign
+            + SubAssign
+            + Integer
+            + Debug,
+    {
+        Playfield::from_cells(self.cells().into_iter().map(|cell| Coordinate {
+            x: Idx::from_i64(cell.x).unwrap(),
+            y: Idx::from_i64(cell.y).unwrap(),
+        }))
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// run under `rule` from now on, forgetting the successors memoised under the last
+    pub fn set_rule(&mut self, rule: &Rule) {
+        self.rule = rule.clone();
+        self.table = rule.transition_table();
+        self.store.successors.clear();
+    }
+
+    /// the root node, whose centre is the origin
+    pub(crate) fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// replace the root node, which must be at least level 1
+    pub(crate) fn set_root(&mut self, root: NodeId) {
+        self.root = root;
+        while self.store.level(self.root) < MIN_ROOT_LEVEL {
+            self.root = self.store.expand(self.root);
+        }
+    }
+
+    pub(crate) fn node_level(&self, n: NodeId) -> u32 {
+        self.store.level(n)
+    }
+
+    pub(crate) fn node_population(&self, n: NodeId) -> u64 {
+        self.store.population(n)
+    }
+
+    /// quadrants in the order nw, ne, sw, se
+    pub(crate) fn node_children(&self, n: NodeId) -> [NodeId; 4] {
+        self.store.children(n)
+    }
+
+    /// whether cell `x`, `y` of node `n` is live, with the origin at the top left corner
+    pub(crate) fn node_cell(&self, n: NodeId, x: u64, y: u64) -> bool {
+        self.store.get(n, x as u128, y as u128)
+    }
+
+    /// the canonical node with the given quadrants, which must all be of the same level
+    pub(crate) fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        self.store.join(children)
+    }
+
+    pub(crate) fn empty(&mut self, level: u32) -> NodeId {
+        self.store.empty(level)
+    }
+
+    /// set the number of nodes beyond which the store is garbage collected after a step
+    pub fn set_node_limit(&mut self, node_limit: usize) {
+        self.node_limit = node_limit;
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u64 {
+        self.store.population(self.root)
+    }
+
+    /// number of nodes in the store, whether or not still reachable
+    pub fn node_count(&self) -> usize {
+        self.store.len()
+    }
+
+    /// half the side of the root, so the root covers `-half..half` in each direction
+    ///
+    /// This is wider than `i64`, as the root reaches level 64 to hold cells at the extremes
+    /// of `i64`, and grows beyond that as they are stepped.
+    fn half(&self) -> i128 {
+        1 << (self.store.level(self.root) - 1)
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        (-half..half).contains(&(x as i128)) && (-half..half).contains(&(y as i128))
+    }
+
+    pub fn get_cell(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        self.contains(x, y)
+            && self.store.get(
+                self.root,
+                (x as i128 + half) as u128,
+                (y as i128 + half) as u128,
+            )
+    }
+
+    pub fn set_cell(&mut self, x: i64, y: i64, live: bool) {
+        while !self.contains(x, y) {
+            self.root = self.store.expand(self.root);
+        }
+        let half = self.half();
+        self.root = self.store.set(
+            self.root,
+            (x as i128 + half) as u128,
+            (y as i128 + half) as u128,
+            live,
+        );
+    }
+
+    /// top left and bottom right corners of the smallest rectangle containing all live
+    /// cells, found from the nodes rather than the cells, and so wider than `i64`
+    pub fn bounding_box(&self) -> Option<(Coordinate<i128>, Coordinate<i128>)> {
+        let half = self.half();
+        let extent = |vertical, last| {
+            let e = self
+                .store
+                .extent(self.root, vertical, last, &mut HashMap::new())?;
+            Some(e as i128 - half)
+        };
+        Some((
+            Coordinate {
+                x: extent(false, false)?,
+                y: extent(true, false)?,
+            },
+            Coordinate {
+                x: extent(false, true)?,
+                y: extent(true, true)?,
+            },
+        ))
+    }
+
+    /// the coordinates of all live cells, other than any run beyond the range of `i64`
+    pub fn cells(&self) -> Vec<Coordinate<i64>> {
+        let mut cells = Vec::new();
+        let half = self.half();
+        self.store.cells(self.root, -half, -half, &mut cells);
+        cells
+    }
+
+    /// advance by `generations`, in steps of powers of two
+    pub fn step(&mut self, generations: u64) {
+        for j in (0..u64::BITS).filter(|j| generations & (1 << j) != 0) {
+            self.step_pow2(j);
+            if self.store.len() > self.node_limit {
+                self.collect_garbage();
+            }
+        }
+    }
+
+    /// advance by `2^j` generations
+    pub fn step_pow2(&mut self, j: u32) {
+        // the pattern must lie within the centre quarter of the root, and the root must be
+        // large enough that its successor for this step contains everything reachable
+        while self.store.level(self.root) < j + 3 || !self.is_padded() {
+            self.root = self.store.expand(self.root);
+        }
+        let successor = self.store.successor(self.root, j, &self.table);
+        self.generation += 1 << j;
+
+        // the successor is half the size, so may be below the smallest root level, and may
+        // be padded more than needed, which would only grow the root with every step
+        self.set_root(successor);
+        while self.store.level(self.root) > MIN_ROOT_LEVEL {
+            let centre = self.store.centre(self.root);
+            if self.store.population(centre) != self.population() {
+                break;
+            }
+            self.root = centre;
+        }
+    }
+
+    /// whether all live cells are within the centre quarter of the root
+    fn is_padded(&mut self) -> bool {
+        let centre = self.store.centre(self.root);
+        let centre = self.store.centre(centre);
+        self.store.population(centre) == self.population()
+    }
+
+    /// discard all nodes not reachable from the root
+    pub fn collect_garbage(&mut self) {
+        let mut store = NodeStore::new();
+        let mut copied = HashMap::new();
+        self.root = self.store.copy_into(self.root, &mut store, &mut copied);
+        self.store = store;
+    }
+}
+
+mod tests;
@@ -0,0 +1,180 @@
+#![cfg(test)]
+use super::*;
+
+fn sorted(mut cells: Vec<Coordinate<i64>>) -> Vec<(i64, i64)> {
+    cells.sort_by_key(|c| (c.y, c.x));
+    cells.into_iter().map(|c| (c.x, c.y)).collect()
+}
+
+fn coordinates(cells: &[(i64, i64)]) -> Vec<Coordinate<i64>> {
+    cells.iter().map(|&(x, y)| Coordinate { x, y }).collect()
+}
+
+const GLIDER: [(i64, i64); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+const R_PENTOMINO: [(i64, i64); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+#[test]
+fn test_get_set_cell() {
+    let mut h = HashLife::new(&Rule::conway());
+    h.set_cell(-100, 37, true);
+    h.set_cell(5, 5, true);
+    h.set_cell(5, 5, false);
+    h.set_cell(0, 0, true);
+
+    assert!(h.get_cell(-100, 37));
+    assert!(h.get_cell(0, 0));
+    assert!(!h.get_cell(5, 5));
+    assert!(!h.get_cell(1 << 40, 0));
+    assert_eq!(h.population(), 2);
+    assert_eq!(sorted(h.cells()), vec![(0, 0), (-100, 37)]);
+}
+
+#[test]
+fn test_cells_at_the_extremes() {
+    // cells from 2^62, beyond which the root passes level 63, out to the ends of `i64`
+    let extremes = [
+        (1 << 62, 0),
+        (0, -(1 << 62) - 1),
+        (i64::MAX, i64::MIN),
+        (i64::MIN, i64::MAX),
+    ];
+    let h = HashLife::from_cells(&Rule::conway(), coordinates(&extremes));
+
+    for (x, y) in extremes {
+        assert!(h.get_cell(x, y));
+    }
+    assert!(!h.get_cell(i64::MAX, i64::MAX));
+    assert_eq!(h.population(), 4);
+    assert_eq!(sorted(h.cells()), sorted(coordinates(&extremes)));
+
+    // a blinker at the edge runs as anywhere else
+    let blinker = [(i64::MAX - 2, 0), (i64::MAX - 1, 0), (i64::MAX, 0)];
+    let mut h = HashLife::from_cells(&Rule::conway(), coordinates(&blinker));
+    h.step(1);
+    assert_eq!(
+        sorted(h.cells()),
+        vec![(i64::MAX - 1, -1), (i64::MAX - 1, 0), (i64::MAX - 1, 1)]
+    );
+    h.step(1);
+    assert_eq!(sorted(h.cells()), sorted(coordinates(&blinker)));
+}
+
+#[test]
+fn test_bounding_box() {
+    let wide = |x: i64, y: i64| Coordinate {
+        x: x as i128,
+        y: y as i128,
+    };
+    let mut h = HashLife::new(&Rule::conway());
+    assert_eq!(h.bounding_box(), None);
+
+    h.set_cell(3, -2, true);
+    assert_eq!(h.bounding_box(), Some((wide(3, -2), wide(3, -2))));
+    for (x, y) in R_PENTOMINO {
+        h.set_cell(x - 50, y + 70, true);
+    }
+    assert_eq!(h.bounding_box(), Some((wide(-50, -2), wide(3, 72))));
+
+    h.set_cell(i64::MIN, i64::MAX, true);
+    assert_eq!(
+        h.bounding_box(),
+        Some((wide(i64::MIN, -2), wide(3, i64::MAX)))
+    );
+}
+
+#[test]
+fn test_set_rule() {
+    // the successors under the first rule are not used under the second
+    let mut h = HashLife::from_cells(&Rule::conway(), coordinates(&R_PENTOMINO));
+    h.step(8);
+    let rule = "B36/S23".parse::<Rule>().unwrap();
+    h.set_rule(&rule);
+    let mut reference = HashLife::from_cells(&rule, h.cells());
+    h.step(8);
+    reference.step(8);
+    assert_eq!(sorted(h.cells()), sorted(reference.cells()));
+}
+
+#[test]
+fn test_node_store_is_canonical() {
+    let mut h = HashLife::new(&Rule::conway());
+    let empty = h.root;
+    h.set_cell(3, -2, true);
+    let one = h.root;
+
+    // the same squares are always the same nodes
+    h.set_cell(3, -2, false);
+    assert_eq!(h.root, empty);
+    let n = h.node_count();
+    h.set_cell(3, -2, true);
+    assert_eq!(h.root, one);
+    assert_eq!(h.node_count(), n);
+}
+
+#[test]
+fn test_step_glider() {
+    let mut h = HashLife::from_cells(&Rule::conway(), coordinates(&GLIDER));
+
+    h.step(4);
+    assert_eq!(h.generation(), 4);
+    assert_eq!(
+        sorted(h.cells()),
+        GLIDER
+            .iter()
+            .map(|&(x, y)| (x + 1, y + 1))
+            .collect::<Vec<_>>()
+    );
+
+    // a billion generations, a quarter of that in each direction
+    h.step(1 << 30);
+    let d = 1 + (1 << 28);
+    assert_eq!(
+        sorted(h.cells()),
+        GLIDER
+            .iter()
+            .map(|&(x, y)| (x + d, y + d))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_step_matches_playfield() {
+    let rule = Rule::conway();
+    let mut p = Playfield::<i32, u16>::from_cells(R_PENTOMINO.iter().map(|&(x, y)| Coordinate {
+        x: x as i32,
+        y: y as i32,
+    }));
+    let mut h = HashLife::from_playfield(&rule, &p);
+
+    for _ in 0..100 {
+        p = p.step(&rule);
+    }
+    h.step(100);
+
+    let (rows, _) = h.to_playfield::<i32, u16>().to_rows();
+    assert_eq!(rows, p.to_rows().0);
+    assert_eq!(h.population(), p.cells().len() as u64);
+}
+
+#[test]
+fn test_collect_garbage() {
+    let rule = Rule::conway();
+    let mut h = HashLife::from_cells(&rule, coordinates(&R_PENTOMINO));
+    let mut reference = HashLife::from_cells(&rule, coordinates(&R_PENTOMINO));
+    reference.set_node_limit(usize::MAX);
+
+    h.step(200);
+    reference.step(200);
+    let before = h.node_count();
+    h.collect_garbage();
+    assert!(h.node_count() < before);
+    assert_eq!(sorted(h.cells()), sorted(reference.cells()));
+
+    // continues correctly, including with collection after every step
+    h.set_node_limit(0);
+    h.step(300);
+    reference.step(300);
+    assert_eq!(h.generation(), 500);
+    assert_eq!(sorted(h.cells()), sorted(reference.cells()));
+}
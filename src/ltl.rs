@@ -0,0 +1,454 @@
+// TODO remove suppression for dead code warning
+#![allow(dead_code)]
+
+use super::generations::{next_state, GenerationsPlayfield, State, DEAD, LIVE};
+use super::rule::RuleError;
+use num::cast::AsPrimitive;
+use num::FromPrimitive;
+use num::One;
+use num::Zero;
+use std::cmp::PartialOrd;
+use std::fmt;
+use std::fmt::Debug;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::RangeInclusive;
+use std::ops::Sub;
+use std::ops::SubAssign;
+use std::str::FromStr;
+
+/// largest supported range, as in Golly
+pub const MAX_RANGE: usize = 500;
+
+/// the shape of the neighbourhood
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LtlNeighbourhood {
+    /// the square of cells within the range in both directions
+    Moore,
+    /// the diamond of cells within the range in Manhattan distance
+    VonNeumann,
+    /// the disc of cells within the range in Euclidean distance
+    Circular,
+}
+
+impl LtlNeighbourhood {
+    fn letter(self) -> char {
+        match self {
+            LtlNeighbourhood::Moore => 'M',
+            LtlNeighbourhood::VonNeumann => 'N',
+            LtlNeighbourhood::Circular => 'C',
+        }
+    }
+
+    /// half the width of the row `dy` away from the centre, for range `r`
+    fn half_width(self, r: usize, dy: usize) -> usize {
+        match self {
+            LtlNeighbourhood::Moore => r,
+            LtlNeighbourhood::VonNeumann => r - dy,
+            LtlNeighbourhood::Circular => {
+                (0..=r).rev().find(|dx| dx * dx + dy * dy <= r * r).unwrap()
+            }
+        }
+    }
+}
+
+/// a Larger than Life rule, in which cells count live neighbours within a range, and
+/// birth and survival are by ranges of counts
+///
+/// With more than two states, cells which fail to survive pass through refractory states
+/// as for Generations rules.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LtlRule {
+    range: usize,
+    /// number of states, including dead and live
+    states: usize,
+    /// whether the centre cell counts towards its own neighbourhood
+    middle: bool,
+    survival: Vec<RangeInclusive<usize>>,
+    birth: Vec<RangeInclusive<usize>>,
+    neighbourhood: LtlNeighbourhood,
+}
+
+impl LtlRule {
+    pub fn new(
+        range: usize,
+        states: usize,
+        middle: bool,
+        survival: Vec<RangeInclusive<usize>>,
+        birth: Vec<RangeInclusive<usize>>,
+        neighbourhood: LtlNeighbourhood,
+    ) -> Result<LtlRule, RuleError> {
+        if !(1..=MAX_RANGE).contains(&range) {
+            Err(RuleError::Range(range))
+        } else if !(2..=State::MAX as usize + 1).contains(&states) {
+            Err(RuleError::States(states))
+        } else if birth.iter().any(|counts| counts.contains(&0)) {
+            Err(RuleError::BirthOnZero)
+        } else {
+            Ok(LtlRule {
+                range,
+                states,
+                middle,
+                survival,
+                birth,
+                neighbourhood,
+            })
+        }
+    }
+
+    /// Bosco's Rule, R5,C0,M1,S34..58,B34..45,NM
+    pub fn bosco() -> LtlRule {
+        LtlRule {
+            range: 5,
+            states: 2,
+            middle: true,
+            survival: vec![34..=58],
+            birth: vec![34..=45],
+            neighbourhood: LtlNeighbourhood::Moore,
+        }
+    }
+
+    pub fn range(&self) -> usize {
+        self.range
+    }
+
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    /// the neighbourhood as rectangles of rows with the same width, each as first and last
+    /// row relative to the centre, and half width
+    fn strips(&self) -> Vec<(isize, isize, isize)> {
+        let r = self.range as isize;
+        let mut strips: Vec<(isize, isize, isize)> = Vec::new();
+        for dy in -r..=r {
+            let half = self.neighbourhood.half_width(self.range, dy.unsigned_abs()) as isize;
+            match strips.last_mut() {
+                Some((_, last, last_half)) if *last_half == half => *last = dy,
+                _ => strips.push((dy, dy, half)),
+            }
+        }
+        strips
+    }
+
+    /// lookup tables from count to whether a dead cell is born and a live cell survives
+    fn count_tables(&self) -> [Vec<bool>; 2] {
+        let size = (2 * self.range + 1) * (2 * self.range + 1);
+        let table = |ranges: &[RangeInclusive<usize>]| {
+            (0..=size)
+                .map(|count| ranges.iter().any(|counts| counts.contains(&count)))
+                .collect::<Vec<bool>>()
+        };
+        [table(&self.birth), table(&self.survival)]
+    }
+}
+
+/// parse counts as either `a..b` or a single count
+fn parse_counts(s: &str) -> Option<RangeInclusive<usize>> {
+    match s.split_once("..") {
+        Some((min, max)) => Some(min.parse().ok()?..=max.parse().ok()?),
+        None => s.parse().ok().map(|count| count..=count),
+    }
+}
+
+impl FromStr for LtlRule {
+    type Err = RuleError;
+
+    /// parse notation such as `R5,C0,M1,S34..58,B34..45,NM`, where `C0` is the same as `C2`,
+    /// and either of `S` and `B` may be followed by further comma separated ranges
+    fn from_str(s: &str) -> Result<LtlRule, RuleError> {
+        let syntax = || RuleError::Syntax(s.to_string());
+
+        let mut range = None;
+        let mut states = 2;
+        let mut middle = false;
+        let mut survival = Vec::new();
+        let mut birth = Vec::new();
+        let mut neighbourhood = LtlNeighbourhood::Moore;
+        // whether further counts are for birth rather than survival, if any
+        let mut counting_birth = None;
+
+        for token in s.trim().split(',').map(str::trim) {
+            let mut chars = token.chars();
+            let key = chars.next().ok_or_else(syntax)?.to_ascii_uppercase();
+            let value = chars.as_str();
+
+            match key {
+                'R' => range = Some(value.parse::<usize>().map_err(|_| syntax())?),
+                'C' => {
+                    states = match value.parse::<usize>().map_err(|_| syntax())? {
+                        0 => 2,
+                        n => n,
+                    }
+                }
+                'M' => {
+                    middle = match value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(syntax()),
+                    }
+                }
+                'N' => {
+                    neighbourhood = match value.to_ascii_uppercase().as_str() {
+                        "M" => LtlNeighbourhood::Moore,
+                        "N" => LtlNeighbourhood::VonNeumann,
+                        "C" => LtlNeighbourhood::Circular,
+                        _ => return Err(syntax()),
+                    }
+                }
+                'S' | 'B' => {
+                    counting_birth = Some(key == 'B');
+                    if !value.is_empty() {
+                        let counts = parse_counts(value).ok_or_else(syntax)?;
+                        if key == 'B' {
+                            birth.push(counts);
+                        } else {
+                            survival.push(counts);
+                        }
+                    }
+                }
+                c if c.is_ascii_digit() => {
+                    let counts = parse_counts(token).ok_or_else(syntax)?;
+                    match counting_birth {
+                        Some(true) => birth.push(counts),
+                        Some(false) => survival.push(counts),
+                        None => return Err(syntax()),
+                    }
+                }
+                _ => return Err(syntax()),
+            }
+            if !matches!(key, 'S' | 'B') && !key.is_ascii_digit() {
+                counting_birth = None;
+            }
+        }
+
+        LtlRule::new(
+            range.ok_or_else(syntax)?,
+            states,
+            middle,
+            survival,
+            birth,
+            neighbourhood,
+        )
+    }
+}
+
+impl fmt::Display for LtlRule {
+    /// canonical notation, with `C0` for two states
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts = |ranges: &[RangeInclusive<usize>]| {
+            ranges
+                .iter()
+                .map(|counts| format!("{}..{}", counts.start(), counts.end()))
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        write!(
+            f,
+            "R{},C{},M{},S{},B{},N{}",
+            self.range,
+            if self.states == 2 { 0 } else { self.states },
+            self.middle as u8,
+            counts(&self.survival),
+            counts(&self.birth),
+            self.neighbourhood.letter()
+        )
+    }
+}
+
+/// counts of live cells in any rectangle in constant time
+struct SummedAreaTable {
+    width: usize,
+    height: usize,
+    /// live cells above and left of each corner, in rows of `width + 1`
+    sums: Vec<u32>,
+}
+
+impl SummedAreaTable {
+    fn new(width: usize, height: usize, live: &[(usize, usize)]) -> SummedAreaTable {
+        let stride = width + 1;
+        let mut sums = vec![0u32; stride * (height + 1)];
+        for (x, y) in live.iter() {
+            sums[(y + 1) * stride + x + 1] = 1;
+        }
+        for y in 1..=height {
+            for x in 1..=width {
+                sums[y * stride + x] += sums[(y - 1) * stride + x] + sums[y * stride + x - 1]
+                    - sums[(y - 1) * stride + x - 1];
+            }
+        }
+        SummedAreaTable {
+            width,
+            height,
+            sums,
+        }
+    }
+
+    /// live cells in columns `x0..=x1` and rows `y0..=y1`, clipped to the table
+    fn count(&self, x0: isize, x1: isize, y0: isize, y1: isize) -> u32 {
+        let clip = |i: isize, n: usize| i.clamp(0, n as isize) as usize;
+        let (x0, x1) = (clip(x0, self.width), clip(x1 + 1, self.width));
+        let (y0, y1) = (clip(y0, self.height), clip(y1 + 1, self.height));
+        if x0 >= x1 || y0 >= y1 {
+            return 0;
+        }
+        let stride = self.width + 1;
+        self.sums[y1 * stride + x1] + self.sums[y0 * stride + x0]
+            - self.sums[y0 * stride + x1]
+            - self.sums[y1 * stride + x0]
+    }
+}
+
+/// least rows of output counted from one set of summed-area tables, which for longer ranges
+/// is twice the range, so that the margin of a table is no more than its band
+const BAND: usize = 32;
+
+/// the cells of a row which are not dead, with their columns, in order
+type CellRow = (usize, Vec<(usize, State)>);
+
+/// the columns within `range` of any cell of `rows`, as disjoint spans in order, each
+/// from first to last column
+fn spans(rows: &[CellRow], range: usize) -> Vec<(usize, usize)> {
+    let mut columns = rows
+        .iter()
+        .flat_map(|(_, cells)| cells.iter().map(|(x, _)| *x))
+        .collect::<Vec<_>>();
+    columns.sort_unstable();
+    columns.dedup();
+
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for x in columns {
+        match spans.last_mut() {
+            Some((_, last)) if x - range <= *last + 1 => *last = x + range,
+            _ => spans.push((x - range, x + range)),
+        }
+    }
+    spans
+}
+
+impl<Idx> GenerationsPlayfield<Idx>
+where
+    Idx: Copy
+        + Default
+        + One
+        + FromPrimitive
+        + AsPrimitive<usize>
+        + Add<Output = Idx>
+        + Sub<Output = Idx>
+        + PartialOrd
+        + AddAssign
+        + SubAssign
+        + Zero
+        + Ord
+        + Debug,
+{
+    /// compute the next generation under the Larger than Life `rule`
+    ///
+    /// Rows are taken in bands, and counts are from a summed-area table of live cells for
+    /// each span of columns within range of a cell of the rows the band can see, so that
+    /// only cells near some cell are visited however far apart the cells are.
+    pub fn step_ltl(&self, rule: &LtlRule) -> GenerationsPlayfield<Idx> {
+        let mut next = GenerationsPlayfield::<Idx>::new();
+        let cc = match &self.cc {
+            Some(cc) => cc,
+            None => return next,
+        };
+
+        // cells which are not dead, relative to the top left of a margin of the range
+        let range = rule.range;
+        let origin = cc.origin();
+        let rows = cc
+            .rows_enumerator()
+            .map(|(y, row)| {
+                let cells = row
+                    .enumerator()
+                    .filter(|(_, state)| **state != DEAD)
+                    .map(|(x, state)| ((x - origin.x).as_() + range, *state))
+                    .collect::<Vec<_>>();
+                ((y - origin.y).as_() + range, cells)
+            })
+            .filter(|(_, cells)| !cells.is_empty())
+            .collect::<Vec<CellRow>>();
+
+        let strips = rule.strips();
+        let [birth, survival] = rule.count_tables();
+        let r = range as isize;
+        let r_idx = Idx::from_usize(range).unwrap();
+
+        // the rows within range of the band from `top` are those from `start` to `end`
+        let band = BAND.max(2 * range);
+        let mut top = 0;
+        let mut start = 0;
+        while start < rows.len() {
+            top = top.max(rows[start].0 - range);
+            let bottom = top + band;
+            let end = start + rows[start..].partition_point(|(ly, _)| *ly < bottom + range);
+            let window = &rows[start..end];
+            // the first row of each table
+            let table_top = top as isize - r;
+
+            for (x0, x1) in spans(window, range) {
+                let live = window
+                    .iter()
+                    .flat_map(|(ly, cells)| {
+                        let first = cells.partition_point(|(lx, _)| *lx < x0);
+                        let last = cells.partition_point(|(lx, _)| *lx <= x1);
+                        cells[first..last]
+                            .iter()
+                            .filter(|(_, state)| *state == LIVE)
+                            .map(move |(lx, _)| (lx - x0, (*ly as isize - table_top) as usize))
+                    })
+                    .collect::<Vec<_>>();
+                let sat = SummedAreaTable::new(x1 - x0 + 1, band + 2 * range, &live);
+
+                for ly in top..bottom {
+                    let y = Idx::from_usize(ly).unwrap() + origin.y - r_idx;
+                    let ty = ly as isize - table_top;
+                    let centre = window
+                        .binary_search_by_key(&ly, |(ly, _)| *ly)
+                        .map_or(&[][..], |k| &window[k].1[..]);
+
+                    for lx in x0..=x1 {
+                        let state = centre
+                            .binary_search_by_key(&lx, |(lx, _)| *lx)
+                            .map_or(DEAD, |k| centre[k].1);
+                        let tx = (lx - x0) as isize;
+
+                        // nothing within range, so nothing to do
+                        if state == DEAD && sat.count(tx - r, tx + r, ty - r, ty + r) == 0 {
+                            continue;
+                        }
+
+                        let mut count = strips
+                            .iter()
+                            .map(|(dy0, dy1, half)| {
+                                sat.count(tx - half, tx + half, ty + dy0, ty + dy1)
+                            })
+                            .sum::<u32>() as usize;
+                        if state == LIVE && !rule.middle {
+                            count -= 1;
+                        }
+
+                        let transition = [
+                            if birth[count] { LIVE } else { DEAD },
+                            if survival[count] { LIVE } else { LIVE + 1 },
+                        ];
+                        let state = next_state(rule.states, state, transition);
+                        if state != DEAD {
+                            let x = Idx::from_usize(lx).unwrap() + origin.x - r_idx;
+                            next.set_cell(x, y, state);
+                        }
+                    }
+                }
+            }
+
+            // rows above the reach of the next band are done with
+            start += window.partition_point(|(ly, _)| *ly + range < bottom);
+            top = bottom;
+        }
+
+        next
+    }
+}
+
+mod tests;
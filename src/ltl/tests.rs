@@ -0,0 +1,356 @@
+#![cfg(test)]
+use super::*;
+use crate::cartesian::Coordinate;
+use crate::generations::GenerationsRule;
+use crate::rule::Rule;
+
+/// cells which are not dead, with their states, sorted by row then column
+fn cells(p: &GenerationsPlayfield<i32>) -> Vec<(i32, i32, State)> {
+    let (rows, origin) = p.to_rows();
+    let mut cells = Vec::new();
+    for (y_u, row) in rows.iter().enumerate() {
+        for (x_u, state) in row.iter().enumerate() {
+            if *state != DEAD {
+                cells.push((origin.x + x_u as i32, origin.y + y_u as i32, *state));
+            }
+        }
+    }
+    cells
+}
+
+fn single_cell() -> GenerationsPlayfield<i32> {
+    GenerationsPlayfield::from_rows(&[vec![LIVE]], Coordinate { x: 0, y: 0 })
+}
+
+#[test]
+fn test_parse_ltl_rule() {
+    assert_eq!(
+        "R5,C0,M1,S34..58,B34..45,NM".parse::<LtlRule>(),
+        Ok(LtlRule::bosco())
+    );
+    assert_eq!(
+        "r5,c2,m1,s34..58,b34..45,nm".parse::<LtlRule>(),
+        Ok(LtlRule::bosco())
+    );
+    assert_eq!(LtlRule::bosco().to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+
+    let rule = "R2,C3,M0,S2..3,5,B4,7..8,NN".parse::<LtlRule>().unwrap();
+    assert_eq!(
+        rule,
+        LtlRule::new(
+            2,
+            3,
+            false,
+            vec![2..=3, 5..=5],
+            vec![4..=4, 7..=8],
+            LtlNeighbourhood::VonNeumann
+        )
+        .unwrap()
+    );
+    assert_eq!(rule.to_string(), "R2,C3,M0,S2..3,5..5,B4..4,7..8,NN");
+    assert_eq!(rule.to_string().parse::<LtlRule>(), Ok(rule));
+}
+
+#[test]
+fn test_parse_ltl_rule_errors() {
+    let syntax = |s: &str| Err(RuleError::Syntax(s.to_string()));
+
+    for s in [
+        "C0,M1,S34..58,B34..45,NM",
+        "R5,M2",
+        "R5,NX",
+        "R5,S3..",
+        "R5,4",
+        "R5,X1",
+        "R5,,B3",
+    ] {
+        assert_eq!(s.parse::<LtlRule>(), syntax(s));
+    }
+    assert_eq!("R0,B3".parse::<LtlRule>(), Err(RuleError::Range(0)));
+    assert_eq!(
+        "R501,B3".parse::<LtlRule>(),
+        Err(RuleError::Range(MAX_RANGE + 1))
+    );
+    assert_eq!("R1,C1,B3".parse::<LtlRule>(), Err(RuleError::States(1)));
+    assert_eq!("R1,B0..2".parse::<LtlRule>(), Err(RuleError::BirthOnZero));
+}
+
+#[test]
+fn test_strips() {
+    let strips = |s: &str| s.parse::<LtlRule>().unwrap().strips();
+
+    assert_eq!(strips("R2,B1,NM"), vec![(-2, 2, 2)]);
+    assert_eq!(
+        strips("R2,B1,NN"),
+        vec![(-2, -2, 0), (-1, -1, 1), (0, 0, 2), (1, 1, 1), (2, 2, 0)]
+    );
+    assert_eq!(
+        strips("R3,B1,NC"),
+        vec![(-3, -3, 0), (-2, -1, 2), (0, 0, 3), (1, 2, 2), (3, 3, 0)]
+    );
+}
+
+#[test]
+fn test_summed_area_table() {
+    let sat = SummedAreaTable::new(4, 3, &[(0, 0), (3, 0), (1, 1), (3, 2)]);
+
+    assert_eq!(sat.count(0, 3, 0, 2), 4);
+    assert_eq!(sat.count(1, 3, 0, 1), 2);
+    assert_eq!(sat.count(3, 3, 2, 2), 1);
+    assert_eq!(sat.count(2, 2, 0, 2), 0);
+    // clipped
+    assert_eq!(sat.count(-5, 0, -5, 10), 1);
+    assert_eq!(sat.count(4, 9, 0, 2), 0);
+}
+
+#[test]
+fn test_neighbourhood_shapes() {
+    // every cell within range of a single cell is born, and the single cell dies
+    let born = |s: &str| cells(&single_cell().step_ltl(&s.parse::<LtlRule>().unwrap())).len();
+
+    assert_eq!(born("R3,C0,M0,S,B1,NM"), 48);
+    assert_eq!(born("R3,C0,M0,S,B1,NN"), 24);
+    assert_eq!(born("R3,C0,M0,S,B1,NC"), 28);
+}
+
+#[test]
+fn test_middle() {
+    let with = "R1,C0,M1,S1,B3,NM".parse::<LtlRule>().unwrap();
+    let without = "R1,C0,M0,S1,B3,NM".parse::<LtlRule>().unwrap();
+
+    assert_eq!(cells(&single_cell().step_ltl(&with)), vec![(0, 0, LIVE)]);
+    assert_eq!(cells(&single_cell().step_ltl(&without)), vec![]);
+}
+
+#[test]
+fn test_states() {
+    let rule = "R2,C4,M0,S,B9,NM".parse::<LtlRule>().unwrap();
+
+    let p = single_cell().step_ltl(&rule);
+    assert_eq!(cells(&p), vec![(0, 0, 2)]);
+    let p = p.step_ltl(&rule);
+    assert_eq!(cells(&p), vec![(0, 0, 3)]);
+    let p = p.step_ltl(&rule);
+    assert_eq!(cells(&p), vec![]);
+}
+
+#[test]
+fn test_range_one_agrees_with_generations() {
+    // Conway's Life and Star Wars as range 1 rules
+    for (ltl, generations) in [
+        (
+            "R1,C0,M0,S2..3,B3,NM",
+            GenerationsRule::new(Rule::conway(), 2),
+        ),
+        ("R1,C4,M0,S3..5,B2,NM", "345/2/4".parse::<GenerationsRule>()),
+    ] {
+        let ltl = ltl.parse::<LtlRule>().unwrap();
+        let generations = generations.unwrap();
+
+        // R-pentomino
+        let rows = vec![vec![0, 1, 1], vec![1, 1, 0], vec![0, 1, 0]];
+        let mut p = GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+        let mut q = GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+        for _ in 0..40 {
+            p = p.step_ltl(&ltl);
+            q = q.step(&generations);
+            assert_eq!(cells(&p), cells(&q));
+        }
+    }
+}
+
+/// step by counting every neighbourhood cell by cell
+fn naive_step(cells: &[(i32, i32, State)], rule: &LtlRule) -> Vec<(i32, i32, State)> {
+    let r = rule.range as i32;
+    let state_at = |x: i32, y: i32| {
+        cells
+            .iter()
+            .find(|(cx, cy, _)| (*cx, *cy) == (x, y))
+            .map_or(DEAD, |(_, _, state)| *state)
+    };
+    let in_shape = |dx: i32, dy: i32| match rule.neighbourhood {
+        LtlNeighbourhood::Moore => true,
+        LtlNeighbourhood::VonNeumann => dx.abs() + dy.abs() <= r,
+        LtlNeighbourhood::Circular => dx * dx + dy * dy <= r * r,
+    };
+    let in_ranges = |ranges: &[RangeInclusive<usize>], count: usize| {
+        ranges.iter().any(|counts| counts.contains(&count))
+    };
+
+    let mut next = Vec::new();
+    let (x0, x1) = (
+        cells.iter().map(|c| c.0).min().unwrap(),
+        cells.iter().map(|c| c.0).max().unwrap(),
+    );
+    let (y0, y1) = (
+        cells.iter().map(|c| c.1).min().unwrap(),
+        cells.iter().map(|c| c.1).max().unwrap(),
+    );
+    for y in y0 - r..=y1 + r {
+        for x in x0 - r..=x1 + r {
+            let mut count = 0;
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    if in_shape(dx, dy)
+                        && (rule.middle || (dx, dy) != (0, 0))
+                        && state_at(x + dx, y + dy) == LIVE
+                    {
+                        count += 1;
+                    }
+                }
+            }
+            let state = match state_at(x, y) {
+                DEAD if in_ranges(&rule.birth, count) => LIVE,
+                DEAD => DEAD,
+                LIVE if in_ranges(&rule.survival, count) => LIVE,
+                dying => dying + 1,
+            };
+            if state != DEAD && (state as usize) < rule.states {
+                next.push((x, y, state));
+            }
+        }
+    }
+    next
+}
+
+#[test]
+fn test_agrees_with_naive_counting() {
+    // a random soup, from a simple linear congruential generator
+    let mut seed = 12345u32;
+    let mut random = || {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) % 2
+    };
+    let rows = (0..12)
+        .map(|_| (0..12).map(|_| random() as State).collect::<Vec<State>>())
+        .collect::<Vec<_>>();
+
+    for rule in [
+        LtlRule::bosco().to_string().as_str(),
+        "R2,C3,M0,S3..6,B4..5,NN",
+        "R3,C0,M1,S8..14,B9..12,NC",
+        "R4,C5,M0,S10..24,B14..19,NM",
+    ] {
+        let rule = rule.parse::<LtlRule>().unwrap();
+        let mut p = GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: -5, y: 3 });
+        for _ in 0..4 {
+            let expected = naive_step(&cells(&p), &rule);
+            p = p.step_ltl(&rule);
+            assert_eq!(cells(&p), expected, "{}", rule);
+            if expected.is_empty() {
+                break;
+            }
+        }
+    }
+}
+
+/// a random soup of `width` by `height`, from a simple linear congruential generator
+fn soup(seed: u32, width: usize, height: usize) -> Vec<Vec<State>> {
+    let mut seed = seed;
+    let mut random = || {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) % 2
+    };
+    (0..height)
+        .map(|_| (0..width).map(|_| random() as State).collect())
+        .collect()
+}
+
+/// cells which are not dead, as `cells`, without filling in the rows between them
+fn sparse_cells(p: &GenerationsPlayfield<i32>) -> Vec<(i32, i32, State)> {
+    let mut cells = Vec::new();
+    if let Some(cc) = &p.cc {
+        for (y, row) in cc.rows_enumerator() {
+            for (x, state) in row.enumerator() {
+                if *state != DEAD {
+                    cells.push((x, y, *state));
+                }
+            }
+        }
+    }
+    cells
+}
+
+#[test]
+fn test_agrees_with_naive_counting_across_bands() {
+    // taller than a band, with the range either side
+    let rows = soup(777, 8, 3 * BAND / 2);
+
+    for rule in ["R2,C3,M0,S3..6,B4..5,NN", "R3,C0,M1,S8..14,B9..12,NC"] {
+        let rule = rule.parse::<LtlRule>().unwrap();
+        let mut p = GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: 3, y: -20 });
+        for _ in 0..3 {
+            let expected = naive_step(&cells(&p), &rule);
+            p = p.step_ltl(&rule);
+            assert_eq!(cells(&p), expected, "{}", rule);
+        }
+    }
+}
+
+#[test]
+fn test_distant_cells() {
+    // soups far apart, in the same rows, the same columns and neither, which step alone
+    let rule = "R3,C4,M0,S5..12,B6..9,NM".parse::<LtlRule>().unwrap();
+    let origins = [
+        (0, 0),
+        (1_000_000, 2),
+        (-3, 1_000_000),
+        (-1_000_000, -1_000_000),
+    ];
+
+    let mut soups = origins
+        .iter()
+        .enumerate()
+        .map(|(k, (x, y))| {
+            let rows = soup(k as u32 + 1, 10, 10);
+            GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: *x, y: *y })
+        })
+        .collect::<Vec<_>>();
+    let mut p = GenerationsPlayfield::<i32>::new();
+    for soup in soups.iter() {
+        for (x, y, state) in sparse_cells(soup) {
+            p.set_cell(x, y, state);
+        }
+    }
+
+    for _ in 0..3 {
+        p = p.step_ltl(&rule);
+        let mut expected = Vec::new();
+        for soup in soups.iter_mut() {
+            *soup = soup.step_ltl(&rule);
+            expected.extend(sparse_cells(soup));
+        }
+        expected.sort_by_key(|(x, y, _)| (*y, *x));
+        assert!(!expected.is_empty());
+        assert_eq!(sparse_cells(&p), expected);
+    }
+}
+
+#[test]
+fn test_last_of_every_state() {
+    // with every state in use, a cell in the largest state dies rather than overflowing
+    let rule = "R2,C256,M0,S,B30,NM".parse::<LtlRule>().unwrap();
+    let rows = [vec![254, 255]];
+    let p = GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+
+    let p = p.step_ltl(&rule);
+    assert_eq!(cells(&p), vec![(0, 0, 255)]);
+    assert_eq!(cells(&p.step_ltl(&rule)), vec![]);
+}
+
+#[test]
+fn test_long_range() {
+    // ranges as long as Golly's, in bands longer than the least
+    let born = |s: &str| cells(&single_cell().step_ltl(&s.parse::<LtlRule>().unwrap())).len();
+    assert_eq!(born("R200,C0,M0,S,B1,NM"), 401 * 401 - 1);
+    assert_eq!(born("R40,C0,M0,S,B1,NN"), 2 * 40 * 41);
+
+    let rule = "R17,C3,M1,S4..10,B5..9,NC".parse::<LtlRule>().unwrap();
+    let rows = soup(99, 5, 5);
+    let p = GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+    let expected = naive_step(&cells(&p), &rule);
+    // more live than the whole soup, so some were born
+    let live = expected.iter().filter(|(_, _, state)| *state == LIVE);
+    assert!(live.count() > 25);
+    assert_eq!(cells(&p.step_ltl(&rule)), expected);
+}
@@ -1,9 +1,19 @@
 fn main() {
-    println!("Hello, world!");
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    std::process::exit(cli::main(&args));
 }
 
 mod cartesian;
+mod cli;
 mod contig;
+mod generations;
+mod grid;
+mod hashlife;
+mod ltl;
 mod multi_iterator;
 mod neighbourhood;
+mod pattern;
 mod playfield;
+mod rule;
+mod topology;
+mod triangular;
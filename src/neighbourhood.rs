@@ -2,11 +2,112 @@
 #![allow(dead_code)]
 
 use super::multi_iterator::Indexed;
+use std::iter::{once, Chain, Once};
 use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use std::slice;
 
-pub const N_SIZE: usize = 3;
+/// A centre and `R` items either side, indexed from 0 for the leftmost.
+///
+/// Stable Rust cannot size an array as `2 * R + 1`, hence the separate halves.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Window<X, const R: usize> {
+    before: [X; R],
+    centre: X,
+    after: [X; R],
+}
+
+/// iterator over the items of a window in order
+pub type WindowIter<'b, X> = Chain<Chain<slice::Iter<'b, X>, Once<&'b X>>, slice::Iter<'b, X>>;
+
+impl<X, const R: usize> Window<X, R> {
+    /// number of items
+    pub const WIDTH: usize = 2 * R + 1;
+
+    /// create by calling `f` for each index in order
+    pub fn from_fn<F>(mut f: F) -> Window<X, R>
+    where
+        F: FnMut(usize) -> X,
+    {
+        let before = std::array::from_fn(&mut f);
+        let centre = f(R);
+        let after = std::array::from_fn(|k| f(R + 1 + k));
+        Window {
+            before,
+            centre,
+            after,
+        }
+    }
+
+    pub fn centre(&self) -> &X {
+        &self.centre
+    }
+
+    pub fn iter(&self) -> WindowIter<'_, X> {
+        self.before
+            .iter()
+            .chain(once(&self.centre))
+            .chain(self.after.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut X> {
+        self.before
+            .iter_mut()
+            .chain(once(&mut self.centre))
+            .chain(self.after.iter_mut())
+    }
+}
+
+impl<X> From<[X; 3]> for Window<X, 1> {
+    fn from(items: [X; 3]) -> Window<X, 1> {
+        let [before, centre, after] = items;
+        Window {
+            before: [before],
+            centre,
+            after: [after],
+        }
+    }
+}
+
+/// for nested windows, as in two dimensional neighbourhoods
+impl<X> From<[[X; 3]; 3]> for Window<Window<X, 1>, 1> {
+    fn from(items: [[X; 3]; 3]) -> Window<Window<X, 1>, 1> {
+        Window::from(items.map(Window::from))
+    }
+}
 
-/// An item and its siblings, any of which may be missing.
+impl<X, const R: usize> Index<usize> for Window<X, R> {
+    type Output = X;
+
+    fn index(&self, k: usize) -> &X {
+        match k {
+            k if k < R => &self.before[k],
+            k if k == R => &self.centre,
+            k => &self.after[k - R - 1],
+        }
+    }
+}
+
+impl<X, const R: usize> IndexMut<usize> for Window<X, R> {
+    fn index_mut(&mut self, k: usize) -> &mut X {
+        match k {
+            k if k < R => &mut self.before[k],
+            k if k == R => &mut self.centre,
+            k => &mut self.after[k - R - 1],
+        }
+    }
+}
+
+impl<'b, X, const R: usize> IntoIterator for &'b Window<X, R> {
+    type Item = &'b X;
+    type IntoIter = WindowIter<'b, X>;
+
+    fn into_iter(self) -> WindowIter<'b, X> {
+        self.iter()
+    }
+}
+
+/// An item and its siblings within radius `R`, any of which may be missing.
 ///
 /// # Intended Usage
 ///
@@ -18,14 +119,20 @@ pub const N_SIZE: usize = 3;
 /// The phantom lifetime addresses exactly this, exposing the lifetime of any reference in `T`
 /// into the neighbourhood itself.
 #[derive(Debug)]
-pub struct Neighbourhood<'a, Idx, T> {
+pub struct Neighbourhood<'a, Idx, T, const R: usize = 1> {
     pub i: Idx,
-    pub items: [Option<T>; N_SIZE],
+    pub items: Window<Option<T>, R>,
     phantom: PhantomData<&'a ()>,
 }
 
 impl<'a, Idx, T> Neighbourhood<'a, Idx, T> {
-    pub fn new(i: Idx, items: [Option<T>; N_SIZE]) -> Neighbourhood<'a, Idx, T> {
+    pub fn new(i: Idx, items: [Option<T>; 3]) -> Neighbourhood<'a, Idx, T> {
+        Neighbourhood::from_window(i, Window::from(items))
+    }
+}
+
+impl<'a, Idx, T, const R: usize> Neighbourhood<'a, Idx, T, R> {
+    pub fn from_window(i: Idx, items: Window<Option<T>, R>) -> Neighbourhood<'a, Idx, T, R> {
         Neighbourhood {
             i,
             items,
@@ -33,16 +140,13 @@ impl<'a, Idx, T> Neighbourhood<'a, Idx, T> {
         }
     }
 
-    pub fn empty(i: Idx) -> Neighbourhood<'a, Idx, T> {
-        Neighbourhood {
-            i,
-            items: [None, None, None], // to shortcut as [None; N_SIZE] would require T: Copy
-            phantom: PhantomData,
-        }
+    pub fn empty(i: Idx) -> Neighbourhood<'a, Idx, T, R> {
+        // from_fn rather than [None; N] which would require T: Copy
+        Neighbourhood::from_window(i, Window::from_fn(|_| None))
     }
 }
 
-impl<'a, Idx, T> PartialEq for Neighbourhood<'a, Idx, T>
+impl<'a, Idx, T, const R: usize> PartialEq for Neighbourhood<'a, Idx, T, R>
 where
     Idx: PartialEq,
     T: PartialEq,
@@ -52,7 +156,7 @@ where
     }
 }
 
-impl<'a, Idx, T> Indexed<Idx> for Neighbourhood<'a, Idx, T>
+impl<'a, Idx, T, const R: usize> Indexed<Idx> for Neighbourhood<'a, Idx, T, R>
 where
     Idx: Copy,
 {
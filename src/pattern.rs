@@ -0,0 +1,168 @@
+// TODO remove suppression for dead code warning
+#![allow(dead_code)]
+
+use super::cartesian::Coordinate;
+use super::playfield::{Block, Playfield};
+use num::cast::AsPrimitive;
+use num::FromPrimitive;
+use num::Integer;
+use num::One;
+use std::cmp::PartialOrd;
+use std::fmt;
+use std::fmt::Debug;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Sub;
+use std::ops::SubAssign;
+
+/// Life 1.05 format, of blocks of rows each at a given position
+pub mod life105;
+/// Life 1.06 format, a list of coordinates of live cells
+pub mod life106;
+/// Golly macrocell format, a quadtree of squares in which identical squares are written once
+pub mod macrocell;
+/// plaintext `.cells` format
+pub mod plaintext;
+/// Run Length Encoded format, as used by Golly and most pattern collections
+pub mod rle;
+
+/// most cells written as a grid, where every dead cell within the bounds takes space
+const MAX_GRID_CELLS: u128 = 1 << 26;
+
+/// a two-state pattern as read from or written to a pattern file, with its metadata
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Pattern {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    /// the rulestring as given in the file, if any
+    pub rule: Option<String>,
+    /// coordinates of live cells
+    pub cells: Vec<Coordinate<i64>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PatternError {
+    /// missing or invalid header
+    Header(String),
+    /// invalid content at the given line, counting from 1
+    Syntax(usize, String),
+    /// too many cells within the bounds to write as a grid, being the width and height
+    TooLarge(u128, u128),
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatternError::Header(s) => write!(f, "invalid header: {}", s),
+            PatternError::Syntax(line, s) => write!(f, "line {}: {}", line, s),
+            PatternError::TooLarge(width, height) => {
+                write!(f, "pattern too large: {} by {} cells", width, height)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+impl Pattern {
+    pub fn from_cells(cells: Vec<Coordinate<i64>>) -> Pattern {
+        Pattern {
+            cells,
+            ..Default::default()
+        }
+    }
+
+    /// top left and bottom right corners of the smallest rectangle containing all live cells
+    pub fn bounds(&self) -> Option<(Coordinate<i64>, Coordinate<i64>)> {
+        let first = *self.cells.first()?;
+        Some(self.cells.iter().fold((first, first), |(min, max), cell| {
+            (
+                Coordinate {
+                    x: min.x.min(cell.x),
+                    y: min.y.min(cell.y),
+                },
+                Coordinate {
+                    x: max.x.max(cell.x),
+                    y: max.y.max(cell.y),
+                },
+            )
+        }))
+    }
+
+    /// rows of cells within the bounds, with the top left corner, unless there would be
+    /// more than `MAX_GRID_CELLS`
+    pub(crate) fn rows(&self) -> Result<(Vec<Vec<bool>>, Coordinate<i64>), PatternError> {
+        match self.bounds() {
+            Some((min, max)) => {
+                // as the extent may be one more than fits an `i64`
+                let width = max.x.abs_diff(min.x) as u128 + 1;
+                let height = max.y.abs_diff(min.y) as u128 + 1;
+                if width.saturating_mul(height) > MAX_GRID_CELLS {
+                    return Err(PatternError::TooLarge(width, height));
+                }
+
+                let mut rows = vec![vec![false; width as usize]; height as usize];
+                for cell in self.cells.iter() {
+                    rows[(cell.y - min.y) as usize][(cell.x - min.x) as usize] = true;
+                }
+                Ok((rows, min))
+            }
+            None => Ok((Vec::new(), Coordinate::default())),
+        }
+    }
+
+    pub fn from_playfield<Idx, T>(playfield: &Playfield<Idx, T>) -> Pattern
+    where
+        T: Block,
+        Idx: Copy
+            + Default
+            + One
+            + FromPrimitive
+            + AsPrimitive<usize>
+            + AsPrimitive<i64>
+            + Add<Output = Idx>
+            + Sub<Output = Idx>
+            + PartialOrd
+            + AddAssign
+            + SubAssign
+            + Integer
+            + Debug,
+    {
+        Pattern::from_cells(
+            playfield
+                .cells()
+                .into_iter()
+                .map(|cell| Coordinate {
+                    x: cell.x.as_(),
+                    y: cell.y.as_(),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn to_playfield<Idx, T>(&self) -> Playfield<Idx, T>
+    where
+        T: Block,
+        u128: AsPrimitive<T>,
+        Idx: Copy
+            + Default
+            + One
+            + FromPrimitive
+            + AsPrimitive<usize>
+            + Add<Output = Idx>
+            + Sub<Output = Idx>
+            + PartialOrd
+            + AddAssign
+            + SubAssign
+            + Integer
+            + Debug,
+    {
+        Playfield::from_cells(self.cells.iter().map(|cell| Coordinate {
+            x: Idx::from_i64(cell.x).unwrap(),
+            y: Idx::from_i64(cell.y).unwrap(),
+        }))
+    }
+}
+
+mod tests;
@@ -0,0 +1,136 @@
+use super::{Pattern, PatternError};
+use crate::cartesian::Coordinate;
+
+const HEADER: &str = "#Life 1.05";
+
+/// most dead cells across or down between cells of the same block, beyond which a new
+/// block is cheaper than writing the dead cells
+const BLOCK_GAP: u64 = 16;
+
+pub fn read(s: &str) -> Result<Pattern, PatternError> {
+    let mut lines = s.lines().enumerate().map(|(u, line)| (u + 1, line.trim()));
+    match lines.next() {
+        Some((_, HEADER)) => (),
+        Some((_, line)) => return Err(PatternError::Header(line.to_string())),
+        None => return Err(PatternError::Header("missing".to_string())),
+    }
+
+    let mut pattern = Pattern::default();
+    // top left of the current block, and the row within it
+    let mut block: Option<(Coordinate<i64>, i64)> = None;
+
+    for (u, line) in lines {
+        if let Some(directive) = line.strip_prefix('#') {
+            let mut chars = directive.chars();
+            let tag = chars.next();
+            let text = chars.as_str().trim();
+            match tag {
+                Some('D') | Some('C') => pattern.comments.push(text.to_string()),
+                Some('N') => pattern.rule = None,
+                Some('R') => pattern.rule = Some(text.to_string()),
+                Some('P') => {
+                    let mut numbers = text.split_whitespace().map(|n| n.parse::<i64>());
+                    match (numbers.next(), numbers.next(), numbers.next()) {
+                        (Some(Ok(x)), Some(Ok(y)), None) => block = Some((Coordinate { x, y }, 0)),
+                        _ => {
+                            return Err(PatternError::Syntax(
+                                u,
+                                format!("invalid position \"{}\"", text),
+                            ))
+                        }
+                    }
+                }
+                _ => (),
+            }
+            continue;
+        }
+
+        // rows before any position are centred on the origin by convention, here taken as 0, 0
+        let (origin, row) = block.get_or_insert((Coordinate::default(), 0));
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' => (),
+                '*' => pattern.cells.push(Coordinate {
+                    x: origin.x + x as i64,
+                    y: origin.y + *row,
+                }),
+                c => return Err(PatternError::Syntax(u, format!("unexpected '{}'", c))),
+            }
+        }
+        *row += 1;
+    }
+
+    Ok(pattern)
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut s = format!("{}\n", HEADER);
+    for comment in pattern.name.iter().chain(pattern.comments.iter()) {
+        s.push_str(&format!("#D {}\n", comment));
+    }
+    match &pattern.rule {
+        Some(rule) => s.push_str(&format!("#R {}\n", rule)),
+        None => s.push_str("#N\n"),
+    }
+
+    for cells in blocks(&pattern.cells) {
+        let origin = Coordinate {
+            x: cells.iter().map(|cell| cell.x).min().unwrap(),
+            y: cells[0].y,
+        };
+        s.push_str(&format!("#P {} {}\n", origin.x, origin.y));
+
+        // the row being written, and the next column in it
+        let mut y = origin.y;
+        let mut line = String::new();
+        for cell in cells {
+            while y != cell.y {
+                // an empty line would be taken as the end of the block by some readers
+                s.push_str(if line.is_empty() { "." } else { &line });
+                s.push('\n');
+                line.clear();
+                y += 1;
+            }
+            let x = origin.x + line.len() as i64;
+            line.extend(std::iter::repeat_n('.', (cell.x - x) as usize));
+            line.push('*');
+        }
+        s.push_str(&line);
+        s.push('\n');
+    }
+    s
+}
+
+/// the cells in blocks, each sorted by row then column, and separated from the others by
+/// more than `BLOCK_GAP` dead cells across or down
+///
+/// The cells are cut along every such gap of rows or columns in turn, until no part has
+/// any, and the parts are then in order of their first cells.
+fn blocks(cells: &[Coordinate<i64>]) -> Vec<Vec<Coordinate<i64>>> {
+    let axes: [fn(&Coordinate<i64>) -> i64; 2] = [|cell| cell.y, |cell| cell.x];
+    let mut cells = cells.to_vec();
+    cells.sort_unstable_by_key(|cell| (cell.y, cell.x));
+    cells.dedup();
+
+    let mut blocks = Vec::new();
+    let mut parts = vec![cells];
+    'parts: while let Some(mut part) = parts.pop() {
+        for axis in axes {
+            part.sort_by_key(axis);
+            let cut = part
+                .chunk_by(|a, b| axis(b).abs_diff(axis(a)) <= BLOCK_GAP + 1)
+                .map(<[_]>::to_vec)
+                .collect::<Vec<_>>();
+            if cut.len() > 1 {
+                parts.extend(cut);
+                continue 'parts;
+            }
+        }
+        part.sort_unstable_by_key(|cell| (cell.y, cell.x));
+        blocks.push(part);
+    }
+    blocks.sort_unstable_by_key(|block| (block[0].y, block[0].x));
+    blocks
+}
+
+mod tests;
@@ -0,0 +1,114 @@
+#![cfg(test)]
+use super::*;
+
+#[test]
+fn test_read() {
+    let pattern = read(
+        "#Life 1.05
+#D Two blocks of a pattern
+#R 23/36
+#P -1 -1
+.*
+*.*
+#P 10 -20
+**
+",
+    )
+    .unwrap();
+
+    assert_eq!(
+        pattern.comments,
+        vec!["Two blocks of a pattern".to_string()]
+    );
+    assert_eq!(pattern.rule.as_deref(), Some("23/36"));
+    assert_eq!(
+        pattern.cells,
+        [(0, -1), (-1, 0), (1, 0), (10, -20), (11, -20)]
+            .iter()
+            .map(|&(x, y)| Coordinate { x, y })
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_read_errors() {
+    assert_eq!(
+        read("#Life 1.06\n0 0\n"),
+        Err(PatternError::Header("#Life 1.06".to_string()))
+    );
+    assert_eq!(
+        read("#Life 1.05\n#P 1\n*\n"),
+        Err(PatternError::Syntax(
+            2,
+            "invalid position \"1\"".to_string()
+        ))
+    );
+    assert_eq!(
+        read("#Life 1.05\n*O\n"),
+        Err(PatternError::Syntax(2, "unexpected 'O'".to_string()))
+    );
+}
+
+#[test]
+fn test_roundtrip() {
+    let s = "#Life 1.05\n#D a comment\n#N\n#P -3 7\n*.*\n.\n..*\n";
+    let pattern = read(s).unwrap();
+    assert_eq!(pattern.rule, None);
+    assert_eq!(write(&pattern), s);
+}
+
+#[test]
+fn test_write_blocks() {
+    // cells far apart are each in a block of their own, and those near one another share
+    let cells = [
+        (4000000000, 4000000000),
+        (0, 0),
+        (2, 1),
+        (19, 1),
+        (0, 18),
+        (40, 0),
+        (i64::MIN, i64::MAX),
+    ]
+    .iter()
+    .map(|&(x, y)| Coordinate { x, y })
+    .collect::<Vec<_>>();
+    let s = write(&Pattern::from_cells(cells.clone()));
+    assert_eq!(
+        s,
+        "#Life 1.05
+#N
+#P 0 0
+*
+..*................*
+.
+.
+.
+.
+.
+.
+.
+.
+.
+.
+.
+.
+.
+.
+.
+.
+*
+#P 40 0
+*
+#P 4000000000 4000000000
+*
+#P -9223372036854775808 9223372036854775807
+*
+"
+    );
+
+    let mut roundtrip = read(&s).unwrap().cells;
+    roundtrip.sort_by_key(|c| (c.y, c.x));
+    let mut expected = cells;
+    expected.sort_by_key(|c| (c.y, c.x));
+    assert_eq!(roundtrip, expected);
+}
@@ -0,0 +1,43 @@
+use super::{Pattern, PatternError};
+use crate::cartesian::Coordinate;
+
+const HEADER: &str = "#Life 1.06";
+
+pub fn read(s: &str) -> Result<Pattern, PatternError> {
+    let mut lines = s.lines().enumerate().map(|(u, line)| (u + 1, line.trim()));
+    match lines.next() {
+        Some((_, HEADER)) => (),
+        Some((_, line)) => return Err(PatternError::Header(line.to_string())),
+        None => return Err(PatternError::Header("missing".to_string())),
+    }
+
+    let mut pattern = Pattern::default();
+    for (u, line) in lines {
+        if line.is_empty() {
+            continue;
+        }
+        let mut numbers = line.split_whitespace().map(|n| n.parse::<i64>());
+        match (numbers.next(), numbers.next(), numbers.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => pattern.cells.push(Coordinate { x, y }),
+            _ => {
+                return Err(PatternError::Syntax(
+                    u,
+                    format!("invalid coordinates \"{}\"", line),
+                ))
+            }
+        }
+    }
+
+    Ok(pattern)
+}
+
+/// write the cells in the order given, with no metadata since the format has none
+pub fn write(pattern: &Pattern) -> String {
+    let mut s = format!("{}\n", HEADER);
+    for cell in pattern.cells.iter() {
+        s.push_str(&format!("{} {}\n", cell.x, cell.y));
+    }
+    s
+}
+
+mod tests;
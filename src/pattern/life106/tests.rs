@@ -0,0 +1,33 @@
+#![cfg(test)]
+use super::*;
+
+#[test]
+fn test_read() {
+    let pattern = read("#Life 1.06\n0 -1\n1000000000 7\n\n-2 -2\n").unwrap();
+
+    assert_eq!(
+        pattern.cells,
+        [(0, -1), (1000000000, 7), (-2, -2)]
+            .iter()
+            .map(|&(x, y)| Coordinate { x, y })
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        read("#Life 1.05\n"),
+        Err(PatternError::Header("#Life 1.05".to_string()))
+    );
+    assert_eq!(
+        read("#Life 1.06\n1 2 3\n"),
+        Err(PatternError::Syntax(
+            2,
+            "invalid coordinates \"1 2 3\"".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_roundtrip() {
+    let s = "#Life 1.06\n0 -1\n1000000000 7\n-2 -2\n";
+    assert_eq!(write(&read(s).unwrap()), s);
+}
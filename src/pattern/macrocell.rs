@@ -0,0 +1,339 @@
+use super::{Pattern, PatternError};
+use crate::cartesian::Coordinate;
+use crate::hashlife::{HashLife, NodeId, DEAD, LIVE};
+use crate::rule::Rule;
+use std::collections::HashMap;
+
+const HEADER: &str = "[M2]";
+
+/// level of the leaf nodes, which are 8x8 squares written as rows of cells
+const LEAF_LEVEL: u32 = 3;
+const LEAF_SIZE: usize = 1 << LEAF_LEVEL;
+
+/// highest level read, being the largest whose square, centred on the origin, has
+/// coordinates that fit an `i64`, which is also as large as the HashLife engine grows to
+/// hold any cell that is set
+const MAX_LEVEL: u32 = 64;
+
+/// a node as written, either a leaf of rows of cells with the most significant bit
+/// leftmost, or a level with quadrants numbered from 1, where 0 is empty
+#[derive(Debug)]
+enum MacrocellNode {
+    Leaf([u8; LEAF_SIZE]),
+    Branch(u32, [usize; 4]),
+}
+
+/// metadata and nodes as written, with the root last
+struct Macrocell {
+    pattern: Pattern,
+    /// line of the rule, if any
+    rule_line: Option<usize>,
+    nodes: Vec<MacrocellNode>,
+}
+
+impl Macrocell {
+    fn parse(s: &str) -> Result<Macrocell, PatternError> {
+        let mut lines = s.lines().enumerate().map(|(u, line)| (u + 1, line.trim()));
+        match lines.next() {
+            Some((_, line)) if line.starts_with(HEADER) => (),
+            Some((_, line)) => return Err(PatternError::Header(line.to_string())),
+            None => return Err(PatternError::Header("missing".to_string())),
+        }
+
+        let mut macrocell = Macrocell {
+            pattern: Pattern::default(),
+            rule_line: None,
+            nodes: Vec::new(),
+        };
+
+        for (u, line) in lines {
+            if let Some(directive) = line.strip_prefix('#') {
+                let mut chars = directive.chars();
+                let tag = chars.next();
+                let text = chars.as_str().trim().to_string();
+                match tag {
+                    Some('N') => macrocell.pattern.name = Some(text),
+                    Some('O') => macrocell.pattern.author = Some(text),
+                    Some('C') => macrocell.pattern.comments.push(text),
+                    Some('R') => {
+                        macrocell.pattern.rule = Some(text);
+                        macrocell.rule_line = Some(u);
+                    }
+                    _ => (),
+                }
+            } else if line.starts_with(|c: char| c.is_ascii_digit()) {
+                let node = macrocell.parse_branch(line, u)?;
+                macrocell.nodes.push(node);
+            } else if !line.is_empty() {
+                let node = Macrocell::parse_leaf(line, u)?;
+                macrocell.nodes.push(node);
+            }
+        }
+
+        Ok(macrocell)
+    }
+
+    fn parse_leaf(line: &str, u: usize) -> Result<MacrocellNode, PatternError> {
+        let mut rows = [0u8; LEAF_SIZE];
+        let mut x = 0;
+        let mut y = 0;
+        for c in line.chars() {
+            match c {
+                '.' => x += 1,
+                '*' => {
+                    if x < LEAF_SIZE && y < LEAF_SIZE {
+                        rows[y] |= 0x80 >> x;
+                    }
+                    x += 1;
+                }
+                '$' => {
+                    x = 0;
+                    y += 1;
+                }
+                c => return Err(PatternError::Syntax(u, format!("unexpected '{}'", c))),
+            }
+            if x > LEAF_SIZE || y > LEAF_SIZE {
+                return Err(PatternError::Syntax(u, "leaf too large".to_string()));
+            }
+        }
+        Ok(MacrocellNode::Leaf(rows))
+    }
+
+    fn parse_branch(&self, line: &str, u: usize) -> Result<MacrocellNode, PatternError> {
+        let invalid = || PatternError::Syntax(u, format!("invalid node \"{}\"", line));
+        let numbers = line
+            .split_whitespace()
+            .map(|n| n.parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| invalid())?;
+
+        match numbers[..] {
+            [level, ..] if level > MAX_LEVEL as usize => {
+                Err(PatternError::Syntax(u, format!("level {} too high", level)))
+            }
+            [level, nw, ne, sw, se] if level > LEAF_LEVEL as usize => {
+                let children = [nw, ne, sw, se];
+                // children must be earlier nodes, of the level below
+                for child in children.iter().filter(|child| **child != 0) {
+                    match self.nodes.get(child - 1) {
+                        Some(node) if self.level(node) + 1 == level as u32 => (),
+                        _ => return Err(invalid()),
+                    }
+                }
+                Ok(MacrocellNode::Branch(level as u32, children))
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    fn level(&self, node: &MacrocellNode) -> u32 {
+        match node {
+            MacrocellNode::Leaf(_) => LEAF_LEVEL,
+            MacrocellNode::Branch(level, _) => *level,
+        }
+    }
+
+    /// append the live cells of node `n`, numbered from 1, with top left corner at `x`, `y`,
+    /// which must be within `MAX_LEVEL` of the origin
+    fn cells(&self, n: usize, x: i128, y: i128, cells: &mut Vec<Coordinate<i64>>) {
+        if n == 0 {
+            return;
+        }
+        match &self.nodes[n - 1] {
+            MacrocellNode::Leaf(rows) => {
+                for (row, bits) in rows.iter().enumerate() {
+                    for col in (0..LEAF_SIZE).filter(|col| bits & (0x80 >> col) != 0) {
+                        cells.push(Coordinate {
+                            x: (x + col as i128) as i64,
+                            y: (y + row as i128) as i64,
+                        });
+                    }
+                }
+            }
+            MacrocellNode::Branch(level, [nw, ne, sw, se]) => {
+                let half = 1i128 << (level - 1);
+                self.cells(*nw, x, y, cells);
+                self.cells(*ne, x + half, y, cells);
+                self.cells(*sw, x, y + half, cells);
+                self.cells(*se, x + half, y + half, cells);
+            }
+        }
+    }
+
+    /// the HashLife engine under `rule` with the nodes as written
+    fn hashlife(&self, rule: &Rule) -> HashLife {
+        let mut hashlife = HashLife::new(rule);
+        if !self.nodes.is_empty() {
+            let root = self.build(&mut hashlife);
+            hashlife.set_root(root);
+        }
+        hashlife
+    }
+
+    /// build the HashLife nodes for all the nodes as written, returning the root
+    fn build(&self, hashlife: &mut HashLife) -> NodeId {
+        let mut built: Vec<NodeId> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let n = match node {
+                MacrocellNode::Leaf(rows) => leaf_node(hashlife, rows, 0, 0, LEAF_LEVEL),
+                MacrocellNode::Branch(level, children) => {
+                    let mut quadrants = [DEAD; 4];
+                    for (quadrant, child) in quadrants.iter_mut().zip(children.iter()) {
+                        *quadrant = match child {
+                            0 => hashlife.empty(level - 1),
+                            child => built[child - 1],
+                        };
+                    }
+                    hashlife.join(quadrants)
+                }
+            };
+            built.push(n);
+        }
+        built.last().copied().unwrap_or(DEAD)
+    }
+}
+
+/// the node of `level` for the square of the leaf rows with top left at `x`, `y`
+fn leaf_node(
+    hashlife: &mut HashLife,
+    rows: &[u8; LEAF_SIZE],
+    x: usize,
+    y: usize,
+    level: u32,
+) -> NodeId {
+    if level == 0 {
+        if rows[y] & (0x80 >> x) != 0 {
+            LIVE
+        } else {
+            DEAD
+        }
+    } else {
+        let half = 1 << (level - 1);
+        let nw = leaf_node(hashlife, rows, x, y, level - 1);
+        let ne = leaf_node(hashlife, rows, x + half, y, level - 1);
+        let sw = leaf_node(hashlife, rows, x, y + half, level - 1);
+        let se = leaf_node(hashlife, rows, x + half, y + half, level - 1);
+        hashlife.join([nw, ne, sw, se])
+    }
+}
+
+/// read a macrocell file, where the root is centred on the origin
+pub fn read(s: &str) -> Result<Pattern, PatternError> {
+    let macrocell = Macrocell::parse(s)?;
+    let mut pattern = macrocell.pattern.clone();
+
+    if let Some(root) = macrocell.nodes.last() {
+        let half = 1i128 << (macrocell.level(root) - 1);
+        macrocell.cells(macrocell.nodes.len(), -half, -half, &mut pattern.cells);
+    }
+
+    Ok(pattern)
+}
+
+/// read a macrocell file directly into the HashLife engine, under the rule given in the file,
+/// or Conway's Life if none
+pub fn read_hashlife(s: &str) -> Result<HashLife, PatternError> {
+    let macrocell = Macrocell::parse(s)?;
+    let rule = match &macrocell.pattern.rule {
+        Some(rule) => rule
+            .parse::<Rule>()
+            .map_err(|e| PatternError::Syntax(macrocell.rule_line.unwrap_or(0), e.to_string()))?,
+        None => Rule::conway(),
+    };
+    Ok(macrocell.hashlife(&rule))
+}
+
+/// read the metadata of a macrocell file, without its cells, and the nodes directly into the
+/// HashLife engine under Conway's Life, for when the rule given may be one it cannot run
+pub fn read_tree(s: &str) -> Result<(Pattern, HashLife), PatternError> {
+    let macrocell = Macrocell::parse(s)?;
+    let hashlife = macrocell.hashlife(&Rule::conway());
+    Ok((macrocell.pattern, hashlife))
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let hashlife = HashLife::from_cells(&Rule::conway(), pattern.cells.iter().copied());
+    write_tree(&hashlife, pattern)
+}
+
+pub fn write_hashlife(hashlife: &HashLife) -> String {
+    let pattern = Pattern {
+        rule: Some(hashlife.rule().to_string()),
+        ..Default::default()
+    };
+    write_tree(hashlife, &pattern)
+}
+
+/// write the metadata of `pattern`, but not its cells, with the nodes of `hashlife`
+pub fn write_tree(hashlife: &HashLife, pattern: &Pattern) -> String {
+    let mut s = format!("{} (life)\n", HEADER);
+    if let Some(rule) = &pattern.rule {
+        s.push_str(&format!("#R {}\n", rule));
+    }
+    if let Some(name) = &pattern.name {
+        s.push_str(&format!("#N {}\n", name));
+    }
+    if let Some(author) = &pattern.author {
+        s.push_str(&format!("#O {}\n", author));
+    }
+    for comment in pattern.comments.iter() {
+        s.push_str(&format!("#C {}\n", comment));
+    }
+
+    let mut numbers = HashMap::new();
+    write_node(hashlife, hashlife.root(), &mut numbers, &mut s);
+    s
+}
+
+/// write node `n` after its quadrants, if not already written, returning its number,
+/// or 0 if empty
+fn write_node(
+    hashlife: &HashLife,
+    n: NodeId,
+    numbers: &mut HashMap<NodeId, usize>,
+    s: &mut String,
+) -> usize {
+    if hashlife.node_population(n) == 0 {
+        return 0;
+    }
+    if let Some(number) = numbers.get(&n) {
+        return *number;
+    }
+
+    let level = hashlife.node_level(n);
+    if level == LEAF_LEVEL {
+        let rows = (0..LEAF_SIZE as u64)
+            .map(|y| {
+                let row = (0..LEAF_SIZE as u64)
+                    .map(|x| {
+                        if hashlife.node_cell(n, x, y) {
+                            '*'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>();
+                row.trim_end_matches('.').to_string()
+            })
+            .collect::<Vec<String>>();
+        let height = rows.iter().rposition(|row| !row.is_empty()).unwrap() + 1;
+        for row in rows[..height].iter() {
+            s.push_str(row);
+            s.push('$');
+        }
+        s.push('\n');
+    } else {
+        let mut children = [0; 4];
+        for (number, child) in children.iter_mut().zip(hashlife.node_children(n)) {
+            *number = write_node(hashlife, child, numbers, s);
+        }
+        let [nw, ne, sw, se] = children;
+        s.push_str(&format!("{} {} {} {} {}\n", level, nw, ne, sw, se));
+    }
+
+    let number = numbers.len() + 1;
+    numbers.insert(n, number);
+    number
+}
+
+mod tests;
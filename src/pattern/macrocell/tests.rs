@@ -0,0 +1,172 @@
+#![cfg(test)]
+use super::*;
+
+fn sorted(mut cells: Vec<Coordinate<i64>>) -> Vec<Coordinate<i64>> {
+    cells.sort_by_key(|c| (c.y, c.x));
+    cells
+}
+
+fn coordinates(cells: &[(i64, i64)]) -> Vec<Coordinate<i64>> {
+    sorted(cells.iter().map(|&(x, y)| Coordinate { x, y }).collect())
+}
+
+const GLIDER_MC: &str = "[M2] (golly 4.2)
+#R B3/S23
+#N Glider
+#C far from the origin
+.*$..*$***$
+4 0 0 0 1
+5 0 2 0 0
+";
+
+#[test]
+fn test_read() {
+    let pattern = read(GLIDER_MC).unwrap();
+
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(pattern.comments, vec!["far from the origin".to_string()]);
+    assert_eq!(pattern.rule.as_deref(), Some("B3/S23"));
+    // level 5 root spans -16..16, with the level 4 node in its north east quadrant,
+    // and the leaf in the south east quadrant of that
+    assert_eq!(
+        sorted(pattern.cells),
+        coordinates(&[(9, -8), (10, -7), (8, -6), (9, -6), (10, -6)])
+    );
+}
+
+#[test]
+fn test_read_shared_nodes() {
+    // the same leaf in all four quadrants
+    let pattern = read("[M2]\n*$\n4 1 1 1 1\n").unwrap();
+
+    assert_eq!(
+        sorted(pattern.cells),
+        coordinates(&[(-8, -8), (0, -8), (-8, 0), (0, 0)])
+    );
+}
+
+#[test]
+fn test_read_errors() {
+    assert_eq!(
+        read("x = 3, y = 3\n"),
+        Err(PatternError::Header("x = 3, y = 3".to_string()))
+    );
+    assert_eq!(
+        read("[M2]\n.*$\n.q\n"),
+        Err(PatternError::Syntax(3, "unexpected 'q'".to_string()))
+    );
+    assert_eq!(
+        read("[M2]\n*********$\n"),
+        Err(PatternError::Syntax(2, "leaf too large".to_string()))
+    );
+    // child must be of the level below
+    assert_eq!(
+        read("[M2]\n*$\n5 0 1 0 0\n"),
+        Err(PatternError::Syntax(
+            3,
+            "invalid node \"5 0 1 0 0\"".to_string()
+        ))
+    );
+    // child must already be defined
+    assert_eq!(
+        read("[M2]\n*$\n4 0 2 0 0\n"),
+        Err(PatternError::Syntax(
+            3,
+            "invalid node \"4 0 2 0 0\"".to_string()
+        ))
+    );
+    // levels beyond the range of coordinates
+    assert_eq!(
+        read("[M2]\n65 0 0 0 0\n"),
+        Err(PatternError::Syntax(2, "level 65 too high".to_string()))
+    );
+    assert_eq!(
+        read("[M2]\n*$\n4 1 0 0 0\n70 0 0 0 0\n"),
+        Err(PatternError::Syntax(4, "level 70 too high".to_string()))
+    );
+    assert!(matches!(
+        read_hashlife("[M2]\n65 0 0 0 0\n"),
+        Err(PatternError::Syntax(2, _))
+    ));
+    assert!(matches!(
+        read_hashlife("[M2]\n#R nonsense\n*$\n"),
+        Err(PatternError::Syntax(2, _))
+    ));
+}
+
+#[test]
+fn test_read_highest_level() {
+    // a level 64 root spans every coordinate, and is what is written for cells at the edge
+    let corners = coordinates(&[
+        (i64::MIN, i64::MIN),
+        (i64::MAX, i64::MIN),
+        (i64::MIN, i64::MAX),
+        (i64::MAX, i64::MAX),
+    ]);
+    let s = write(&Pattern::from_cells(corners.clone()));
+    assert!(s.lines().last().unwrap().starts_with("64 "));
+
+    assert_eq!(sorted(read(&s).unwrap().cells), corners);
+    assert_eq!(sorted(read_hashlife(&s).unwrap().cells()), corners);
+    let (pattern, hashlife) = read_tree(&s).unwrap();
+    assert_eq!(pattern.cells, Vec::new());
+    assert_eq!(hashlife.population(), 4);
+}
+
+#[test]
+fn test_read_hashlife() {
+    let mut hashlife = read_hashlife(GLIDER_MC).unwrap();
+
+    assert_eq!(hashlife.rule(), &Rule::conway());
+    assert_eq!(
+        sorted(hashlife.cells()),
+        sorted(read(GLIDER_MC).unwrap().cells)
+    );
+
+    hashlife.step(4);
+    assert_eq!(
+        sorted(hashlife.cells()),
+        coordinates(&[(10, -7), (11, -6), (9, -5), (10, -5), (11, -5)])
+    );
+}
+
+#[test]
+fn test_write_roundtrip() {
+    let pattern = read(GLIDER_MC).unwrap();
+    let s = write(&pattern);
+
+    assert!(s.starts_with("[M2] (life)\n#R B3/S23\n#N Glider\n#C far from the origin\n"));
+    let roundtrip = read(&s).unwrap();
+    assert_eq!(roundtrip.name, pattern.name);
+    assert_eq!(roundtrip.comments, pattern.comments);
+    assert_eq!(sorted(roundtrip.cells), sorted(pattern.cells));
+
+    assert_eq!(write(&Pattern::default()), "[M2] (life)\n");
+    assert_eq!(
+        read(&write(&Pattern::default())).unwrap(),
+        Pattern::default()
+    );
+}
+
+#[test]
+fn test_write_shares_nodes() {
+    let cells = coordinates(&[(-8, -8), (0, -8), (-8, 0), (0, 0)]);
+    let s = write(&Pattern::from_cells(cells.clone()));
+
+    // one leaf, shared by all four quadrants of the root
+    assert_eq!(s, "[M2] (life)\n*$\n4 1 1 1 1\n");
+    assert_eq!(sorted(read(&s).unwrap().cells), cells);
+}
+
+#[test]
+fn test_write_hashlife() {
+    let rule = "B36/S23".parse::<Rule>().unwrap();
+    let mut hashlife = HashLife::from_cells(&rule, coordinates(&[(0, 0), (1, 0), (2, 0)]));
+    hashlife.step(1);
+    let s = write_hashlife(&hashlife);
+
+    assert!(s.starts_with("[M2] (life)\n#R B36/S23\n"));
+    let roundtrip = read_hashlife(&s).unwrap();
+    assert_eq!(roundtrip.rule(), &rule);
+    assert_eq!(sorted(roundtrip.cells()), sorted(hashlife.cells()));
+}
@@ -0,0 +1,55 @@
+use super::{Pattern, PatternError};
+use crate::cartesian::Coordinate;
+
+pub fn read(s: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut y = 0;
+
+    for (u, line) in s.lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(comment) = line.strip_prefix('!') {
+            let comment = comment.trim();
+            match comment.strip_prefix("Name:") {
+                Some(name) if pattern.name.is_none() => {
+                    pattern.name = Some(name.trim().to_string())
+                }
+                _ => pattern.comments.push(comment.to_string()),
+            }
+            continue;
+        }
+
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' => (),
+                'O' | '*' => pattern.cells.push(Coordinate { x: x as i64, y }),
+                c => return Err(PatternError::Syntax(u + 1, format!("unexpected '{}'", c))),
+            }
+        }
+        y += 1;
+    }
+
+    Ok(pattern)
+}
+
+pub fn write(pattern: &Pattern) -> Result<String, PatternError> {
+    let mut s = String::new();
+    if let Some(name) = &pattern.name {
+        s.push_str(&format!("!Name: {}\n", name));
+    }
+    for comment in pattern.comments.iter() {
+        s.push_str(&format!("!{}\n", comment));
+    }
+
+    let (rows, _) = pattern.rows()?;
+    for row in rows {
+        let line = row
+            .iter()
+            .map(|live| if *live { 'O' } else { '.' })
+            .collect::<String>();
+        s.push_str(line.trim_end_matches('.'));
+        s.push('\n');
+    }
+    Ok(s)
+}
+
+mod tests;
@@ -0,0 +1,63 @@
+#![cfg(test)]
+use super::*;
+
+const GLIDER_CELLS: &str = "!Name: Glider
+!The smallest spaceship.
+.O
+..O
+OOO
+";
+
+#[test]
+fn test_read() {
+    let pattern = read(GLIDER_CELLS).unwrap();
+
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(
+        pattern.comments,
+        vec!["The smallest spaceship.".to_string()]
+    );
+    assert_eq!(
+        pattern.cells,
+        [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+            .iter()
+            .map(|&(x, y)| Coordinate { x, y })
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        read("!\n..\n.x\n"),
+        Err(PatternError::Syntax(3, "unexpected 'x'".to_string()))
+    );
+}
+
+#[test]
+fn test_write() {
+    assert_eq!(
+        write(&read(GLIDER_CELLS).unwrap()),
+        Ok(GLIDER_CELLS.to_string())
+    );
+
+    // empty rows are kept, as is the shape but not the position
+    let pattern = Pattern::from_cells(vec![
+        Coordinate { x: -4, y: 10 },
+        Coordinate { x: -2, y: 12 },
+    ]);
+    assert_eq!(write(&pattern), Ok("O\n\n..O\n".to_string()));
+}
+
+#[test]
+fn test_write_too_large() {
+    // every dead cell within the bounds is written, so cells far apart are refused
+    let pattern = Pattern::from_cells(vec![
+        Coordinate { x: 0, y: 0 },
+        Coordinate {
+            x: 4000000000,
+            y: 4000000000,
+        },
+    ]);
+    assert_eq!(
+        write(&pattern),
+        Err(PatternError::TooLarge(4000000001, 4000000001))
+    );
+}
@@ -0,0 +1,224 @@
+use super::{Pattern, PatternError};
+use crate::cartesian::Coordinate;
+
+/// maximum length of lines written, as recommended for the format
+const LINE_LENGTH: usize = 70;
+
+/// longest run of live cells read, which bounds the cells that a single run can add
+const MAX_RUN: u64 = 1 << 24;
+
+/// parse the numbers of a `#P` or `#R` line, being the top left corner of the pattern
+fn parse_position(s: &str, line: usize) -> Result<Coordinate<i64>, PatternError> {
+    let invalid = || PatternError::Syntax(line, format!("invalid position \"{}\"", s));
+    let mut numbers = s.split_whitespace().map(|n| n.parse::<i64>());
+    match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Ok(Coordinate { x, y }),
+        _ => Err(invalid()),
+    }
+}
+
+/// parse the `x = m, y = n, rule = abc` header, returning the rule if any
+///
+/// The rule is the rest of the line, as the suffix of a bounded grid such as `:T10,10`
+/// has commas of its own.
+fn parse_header(s: &str) -> Result<Option<String>, PatternError> {
+    let invalid = || PatternError::Header(s.to_string());
+    let mut rule = None;
+    let mut has_x = false;
+    let mut has_y = false;
+
+    let mut rest = s;
+    while !rest.is_empty() {
+        let (key, value) = rest.split_once('=').ok_or_else(invalid)?;
+        if key.trim() == "rule" {
+            rule = Some(value.trim().to_string());
+            break;
+        }
+        let (value, next) = value.split_once(',').unwrap_or((value, ""));
+        let value = value.trim();
+        match key.trim() {
+            "x" => has_x = value.parse::<u128>().is_ok(),
+            "y" => has_y = value.parse::<u128>().is_ok(),
+            _ => return Err(invalid()),
+        }
+        rest = next;
+    }
+
+    if has_x && has_y {
+        Ok(rule)
+    } else {
+        Err(invalid())
+    }
+}
+
+pub fn read(s: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut origin = Coordinate::default();
+    let mut lines = s.lines().enumerate().map(|(u, line)| (u + 1, line.trim()));
+
+    // comment lines, up to the header
+    loop {
+        match lines.next() {
+            Some((_, "")) => (),
+            Some((u, line)) if line.starts_with('#') => {
+                let mut chars = line[1..].chars();
+                let tag = chars.next();
+                let text = chars.as_str().trim().to_string();
+                match tag {
+                    Some('N') => pattern.name = Some(text),
+                    Some('O') => pattern.author = Some(text),
+                    Some('C') | Some('c') => pattern.comments.push(text),
+                    Some('P') | Some('R') => origin = parse_position(&text, u)?,
+                    Some('r') => pattern.rule = Some(text),
+                    _ => (),
+                }
+            }
+            Some((_, line)) => {
+                if let Some(rule) = parse_header(line)? {
+                    pattern.rule = Some(rule);
+                }
+                break;
+            }
+            None => return Err(PatternError::Header("missing".to_string())),
+        }
+    }
+
+    // the position of the next cell, wider than a coordinate so that a run may end at the
+    // edge, and any cell beyond it is found
+    let mut x = origin.x as i128;
+    let mut y = origin.y as i128;
+    'body: for (u, line) in lines {
+        let too_long = || PatternError::Syntax(u, "run too long".to_string());
+        let out_of_range = || PatternError::Syntax(u, "cell out of range".to_string());
+        let mut count: Option<u64> = None;
+        for c in line.chars() {
+            if let Some(d) = c.to_digit(10) {
+                count = count
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(d as u64));
+                if count.is_none() {
+                    return Err(too_long());
+                }
+                continue;
+            }
+            let n = count.take().unwrap_or(1);
+            match c {
+                'b' => x += n as i128,
+                'o' => {
+                    if n > MAX_RUN {
+                        return Err(too_long());
+                    }
+                    let y = i64::try_from(y).map_err(|_| out_of_range())?;
+                    for _ in 0..n {
+                        let cell_x = i64::try_from(x).map_err(|_| out_of_range())?;
+                        pattern.cells.push(Coordinate { x: cell_x, y });
+                        x += 1;
+                    }
+                }
+                '$' => {
+                    x = origin.x as i128;
+                    y += n as i128;
+                }
+                '!' => break 'body,
+                c if c.is_whitespace() => (),
+                c => return Err(PatternError::Syntax(u, format!("unexpected '{}'", c))),
+            }
+        }
+        if count.is_some() {
+            return Err(PatternError::Syntax(u, "count at end of line".to_string()));
+        }
+    }
+
+    Ok(pattern)
+}
+
+/// append `token` to the last line, starting a new line if it would be too long
+fn push_token(lines: &mut Vec<String>, token: String) {
+    match lines.last_mut() {
+        Some(line) if line.len() + token.len() <= LINE_LENGTH => line.push_str(&token),
+        _ => lines.push(token),
+    }
+}
+
+fn run(n: u64, tag: char) -> String {
+    if n == 1 {
+        tag.to_string()
+    } else {
+        format!("{}{}", n, tag)
+    }
+}
+
+/// Runs are written from the cells in order, with gaps of rows and of dead cells as single
+/// runs, so that cells far apart take no more than cells close together.
+pub fn write(pattern: &Pattern) -> String {
+    let mut s = String::new();
+    if let Some(name) = &pattern.name {
+        s.push_str(&format!("#N {}\n", name));
+    }
+    if let Some(author) = &pattern.author {
+        s.push_str(&format!("#O {}\n", author));
+    }
+    for comment in pattern.comments.iter() {
+        s.push_str(&format!("#C {}\n", comment));
+    }
+
+    let (origin, width, height) = match pattern.bounds() {
+        // as the extent may be one more than fits an `i64`
+        Some((min, max)) => (
+            min,
+            max.x.abs_diff(min.x) as u128 + 1,
+            max.y.abs_diff(min.y) as u128 + 1,
+        ),
+        None => (Coordinate::default(), 0, 0),
+    };
+    if origin != Coordinate::default() {
+        s.push_str(&format!("#R {} {}\n", origin.x, origin.y));
+    }
+    s.push_str(&format!("x = {}, y = {}", width, height));
+    if let Some(rule) = &pattern.rule {
+        s.push_str(&format!(", rule = {}", rule));
+    }
+    s.push('\n');
+
+    let mut cells = pattern.cells.clone();
+    cells.sort_unstable_by_key(|cell| (cell.y, cell.x));
+    cells.dedup();
+
+    let mut lines = Vec::new();
+    // the position of the next cell to be written, and the live cells owed before it
+    let mut y = origin.y;
+    let mut x = origin.x;
+    let mut live = 0;
+    for cell in cells {
+        if cell.y != y || cell.x != x {
+            if live > 0 {
+                push_token(&mut lines, run(live, 'o'));
+                live = 0;
+            }
+            if cell.y != y {
+                push_token(&mut lines, run(cell.y.abs_diff(y), '$'));
+                y = cell.y;
+                x = origin.x;
+            }
+            if cell.x != x {
+                push_token(&mut lines, run(cell.x.abs_diff(x), 'b'));
+            }
+        }
+        live += 1;
+        // past the edge there is no next cell, so nothing to compare with
+        x = cell.x.wrapping_add(1);
+    }
+    if live > 0 {
+        push_token(&mut lines, run(live, 'o'));
+    }
+    push_token(&mut lines, "!".to_string());
+
+    for line in lines {
+        s.push_str(&line);
+        s.push('\n');
+    }
+    s
+}
+
+mod tests;
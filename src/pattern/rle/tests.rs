@@ -0,0 +1,167 @@
+#![cfg(test)]
+use super::*;
+
+fn coordinates(cells: &[(i64, i64)]) -> Vec<Coordinate<i64>> {
+    cells.iter().map(|&(x, y)| Coordinate { x, y }).collect()
+}
+
+const GLIDER_RLE: &str = "#N Glider
+#O Richard K. Guy
+#C The smallest, most common, and first discovered spaceship.
+#C www.conwaylife.com/wiki/index.php?title=Glider
+x = 3, y = 3, rule = B3/S23
+bo$2bo$3o!
+";
+
+#[test]
+fn test_read_glider() {
+    let pattern = read(GLIDER_RLE).unwrap();
+
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(pattern.author.as_deref(), Some("Richard K. Guy"));
+    assert_eq!(pattern.comments.len(), 2);
+    assert_eq!(pattern.rule.as_deref(), Some("B3/S23"));
+    assert_eq!(
+        pattern.cells,
+        coordinates(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)])
+    );
+}
+
+#[test]
+fn test_read_runs_and_position() {
+    // runs continue across lines
+    let pattern = read("#P -10 5\nx=12,y=3\n2o3b\n2o$$\n 10bo!\nignored").unwrap();
+
+    assert_eq!(pattern.rule, None);
+    assert_eq!(
+        pattern.cells,
+        coordinates(&[(-10, 5), (-9, 5), (-5, 5), (-4, 5), (0, 7)])
+    );
+}
+
+#[test]
+fn test_read_bounded_grid_rule() {
+    let pattern = read("x = 3, y = 3, rule = B3/S23:T10,10\nbo$2bo$3o!").unwrap();
+
+    assert_eq!(pattern.rule.as_deref(), Some("B3/S23:T10,10"));
+    assert_eq!(pattern.cells.len(), 5);
+
+    let pattern = read("x=2,y=1,rule=B3/S23:P8,4\n2o!").unwrap();
+    assert_eq!(pattern.rule.as_deref(), Some("B3/S23:P8,4"));
+}
+
+#[test]
+fn test_read_errors() {
+    assert_eq!(
+        read("#C nothing else\n"),
+        Err(PatternError::Header("missing".to_string()))
+    );
+    assert_eq!(
+        read("x = 3, z = 3\n3o!"),
+        Err(PatternError::Header("x = 3, z = 3".to_string()))
+    );
+    assert_eq!(
+        read("x = 3\n3o!"),
+        Err(PatternError::Header("x = 3".to_string()))
+    );
+    assert_eq!(
+        read("x = 3, y = 1\n\n3q!"),
+        Err(PatternError::Syntax(3, "unexpected 'q'".to_string()))
+    );
+    assert_eq!(
+        read("#R 1\nx = 3, y = 1\n3o!"),
+        Err(PatternError::Syntax(
+            1,
+            "invalid position \"1\"".to_string()
+        ))
+    );
+    assert_eq!(
+        read("x = 3, y = 1\n99999999999999999999o!"),
+        Err(PatternError::Syntax(2, "run too long".to_string()))
+    );
+    assert_eq!(
+        read("x = 3, y = 1\n16777217o!"),
+        Err(PatternError::Syntax(2, "run too long".to_string()))
+    );
+    assert_eq!(
+        read("#R 9223372036854775806 0\nx = 3, y = 1\n3o!"),
+        Err(PatternError::Syntax(3, "cell out of range".to_string()))
+    );
+    assert_eq!(
+        read("#R 0 1\nx = 3, y = 1\n9223372036854775807$o!"),
+        Err(PatternError::Syntax(3, "cell out of range".to_string()))
+    );
+    // gaps of dead cells are not cells, so may be as long as any coordinate
+    assert_eq!(
+        read("x = 3, y = 1\n16777217bo9223372036854775806$o!").map(|p| p.cells),
+        Ok(coordinates(&[(16777217, 0), (0, i64::MAX - 1)]))
+    );
+}
+
+#[test]
+fn test_write() {
+    let mut pattern = read(GLIDER_RLE).unwrap();
+    assert_eq!(write(&pattern), GLIDER_RLE);
+
+    pattern.cells = coordinates(&[(-3, 2), (-2, 2), (1, 2), (-3, 5)]);
+    pattern.name = None;
+    pattern.author = None;
+    pattern.comments.clear();
+    pattern.rule = None;
+    assert_eq!(write(&pattern), "#R -3 2\nx = 5, y = 4\n2o2bo3$o!\n");
+
+    assert_eq!(write(&Pattern::default()), "x = 0, y = 0\n!\n");
+}
+
+#[test]
+fn test_write_wraps_lines() {
+    let cells = (0..100)
+        .map(|i| Coordinate { x: i * 2, y: i % 2 })
+        .collect::<Vec<_>>();
+    let pattern = Pattern::from_cells(cells.clone());
+    let s = write(&pattern);
+
+    assert!(s.lines().all(|line| line.len() <= LINE_LENGTH));
+    let mut roundtrip = read(&s).unwrap().cells;
+    roundtrip.sort_by_key(|c| (c.y, c.x));
+    let mut expected = cells;
+    expected.sort_by_key(|c| (c.y, c.x));
+    assert_eq!(roundtrip, expected);
+}
+
+#[test]
+fn test_write_far_apart() {
+    let pattern = Pattern::from_cells(coordinates(&[
+        (4000000000, 4000000000),
+        (0, 0),
+        (4000000001, 4000000000),
+        (4000000003, 4000000000),
+    ]));
+    let s = write(&pattern);
+    assert_eq!(
+        s,
+        "x = 4000000004, y = 4000000001\no4000000000$4000000000b2obo!\n"
+    );
+    let mut roundtrip = read(&s).unwrap().cells;
+    roundtrip.sort_by_key(|c| (c.y, c.x));
+    assert_eq!(
+        roundtrip,
+        coordinates(&[
+            (0, 0),
+            (4000000000, 4000000000),
+            (4000000001, 4000000000),
+            (4000000003, 4000000000)
+        ])
+    );
+
+    // the corners of the plane, whose extent is one more than fits an `i64`
+    let corners = coordinates(&[
+        (i64::MIN, i64::MIN),
+        (i64::MAX, i64::MIN),
+        (i64::MIN, i64::MAX),
+        (i64::MAX, i64::MAX),
+    ]);
+    let s = write(&Pattern::from_cells(corners.clone()));
+    assert!(s.contains("x = 18446744073709551616, y = 18446744073709551616\n"));
+    assert_eq!(read(&s).unwrap().cells, corners);
+}
@@ -0,0 +1,27 @@
+#![cfg(test)]
+use super::*;
+
+#[test]
+fn test_bounds() {
+    assert_eq!(Pattern::default().bounds(), None);
+
+    let pattern = Pattern::from_cells(vec![
+        Coordinate { x: 3, y: -1 },
+        Coordinate { x: -2, y: 4 },
+        Coordinate { x: 0, y: 0 },
+    ]);
+    assert_eq!(
+        pattern.bounds(),
+        Some((Coordinate { x: -2, y: -1 }, Coordinate { x: 3, y: 4 }))
+    );
+}
+
+#[test]
+fn test_playfield_roundtrip() {
+    let pattern = rle::read("#R -5 -7\nx = 3, y = 3\nbo$2bo$3o!").unwrap();
+    let playfield = pattern.to_playfield::<i32, u16>();
+
+    let mut cells = Pattern::from_playfield(&playfield).cells;
+    cells.sort_by_key(|c| (c.y, c.x));
+    assert_eq!(cells, pattern.cells);
+}
@@ -1,19 +1,43 @@
 // TODO remove suppression for dead code warning
-#![allow(dead_code, unused_variables)]
+#![allow(dead_code)]
 
-use super::cartesian::{CartesianContig, Coordinate};
+use super::cartesian::{CartesianContig, CartesianNeighbourhood, Coordinate};
+use super::contig::{CompactionPolicy, Contig};
+use super::neighbourhood::Window;
+use super::rule::Rule;
+use super::topology::Topology;
+pub use block::Block;
+use kernel::Kernel;
 use num::cast::AsPrimitive;
 use num::FromPrimitive;
+use num::Integer;
 use num::One;
 use num::Zero;
 use std::cmp::PartialOrd;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::iter::Iterator;
 use std::ops::Add;
 use std::ops::AddAssign;
+use std::ops::Range;
 use std::ops::Sub;
 use std::ops::SubAssign;
 
+/// packing of pairs of half rows into blocks
+pub mod block;
+
+/// fewest block rows worth a thread of their own
+const MIN_BAND_ROWS: usize = 16;
+
+/// computing the next generation of half rows
+mod kernel;
+
+/// translations, rotations and reflections of whole playfields
+pub mod transform;
+
+/// repeatable random numbers for tests
+mod xorshift;
+
 /// turns separate iterators into iterator of pairs
 struct PairwiseOrDefault<I> {
     i0: I,
@@ -63,6 +87,10 @@ where
 ///
 /// The reason for double rows is so that non-contiguous rows cannot both influence
 /// new cell birth in the intermediate row, which would otherwise be possible.
+///
+/// Within each half-block the most significant bit is the leftmost cell, and the lower
+/// half of the block is the upper of the two rows, that is, the one with smaller `y`.
+#[derive(Clone)]
 pub struct Playfield<Idx, T>
 where
     Idx: Copy
@@ -75,14 +103,40 @@ where
         + PartialOrd
         + AddAssign
         + SubAssign
+        + Eq
         + Debug,
 {
-    cc: CartesianContig<Idx, T>,
+    cc: Option<CartesianContig<Idx, T>>,
+    /// threads to step with, or every core if `None`
+    threads: Option<usize>,
+    /// how the underlying contig compacts itself, if at all
+    compaction: Option<CompactionPolicy>,
+    /// the blocks which changed in the step to this generation, if known
+    changes: Option<Changes<Idx>>,
+    /// the number of live cells
+    population: usize,
+    /// the top left and bottom right corners of the live cells, if any
+    bounds: Option<Bounds<Idx>>,
+}
+
+/// the top left and bottom right corners of a rectangle
+type Bounds<Idx> = (Coordinate<Idx>, Coordinate<Idx>);
+
+/// the blocks which changed in a step, and the kernel of that step
+///
+/// Where the neighbourhood of a block did not change, nor will the block in the following
+/// step by the same kernel, so that only the neighbourhoods of changed blocks need be
+/// evaluated again, as in QuickLife.
+#[derive(Clone)]
+struct Changes<Idx> {
+    kernel: Kernel,
+    /// the runs of blocks which changed, by row, in order
+    rows: Vec<(Idx, Vec<Range<Idx>>)>,
 }
 
 impl<Idx, T> Playfield<Idx, T>
 where
-    T: Debug,
+    T: Block,
     Idx: Copy
         + Default
         + One
@@ -96,97 +150,121 @@ where
         + Zero
         + Ord
         + Debug,
-    T: Zero,
 {
-    fn new() -> Playfield<Idx, T> {
+    pub fn new() -> Playfield<Idx, T> {
         Playfield {
-            cc: CartesianContig::new(Idx::zero(), Idx::zero(), T::zero()),
+            cc: None,
+            threads: None,
+            compaction: None,
+            changes: None,
+            population: 0,
+            bounds: None,
         }
     }
 
-    /// pack a pair of halfblocks into a block
-    /// the halfblock type H must be half the size of the block type T
-    fn pack<H>(pair: (H, H)) -> T {
-        use std::mem::size_of;
-        assert!(size_of::<H>() * 2 == size_of::<T>());
+    /// set the number of threads to step with, or every core if `None`
+    ///
+    /// Stepping in parallel gives the same result, and the setting is kept by each
+    /// generation stepped from this one.
+    pub fn set_threads(&mut self, threads: Option<usize>) {
+        self.threads = threads;
+    }
 
-        let packed: T = T::zero();
-        unsafe {
-            let p = &packed as *const T as *mut H;
-            *p = pair.0;
-            *(p.offset(1)) = pair.1
+    /// compact the blocks automatically as `policy`, or never if `None`, where compaction
+    /// may keep zero blocks to make fewer spans
+    ///
+    /// As for threads, the setting is kept by each generation stepped from this one.
+    pub fn set_compaction(&mut self, policy: Option<CompactionPolicy>) {
+        self.compaction = policy;
+        if let Some(cc) = &mut self.cc {
+            cc.set_auto_compaction(policy);
         }
-        packed
     }
 
-    /// unpack a pair of halfblocks from a block
-    /// the halfblock type H must be half the size of the block type T
-    fn unpack<H>(packed: T) -> (H, H)
-    where
-        H: Zero + Copy,
-    {
-        use std::mem::size_of;
-        assert!(size_of::<H>() * 2 == size_of::<T>());
-
-        let p0: H;
-        let p1: H;
+    /// empty, with the same settings
+    fn empty_like(&self) -> Playfield<Idx, T> {
+        let mut playfield = Playfield::<Idx, T>::new();
+        playfield.threads = self.threads;
+        playfield.compaction = self.compaction;
+        playfield
+    }
 
-        unsafe {
-            let p = &packed as *const T as *mut H;
-            p0 = *p;
-            p1 = *(p.offset(1));
+    /// set the block at `x`, `y`, creating the underlying contig if required, and dropping
+    /// it if compaction leaves it empty
+    fn set_block(&mut self, x: Idx, y: Idx, block: T) {
+        match &mut self.cc {
+            Some(cc) => {
+                cc.set(x, y, block);
+                if cc.is_empty() {
+                    self.cc = None;
+                }
+            }
+            None => {
+                let mut cc = CartesianContig::new(x, y, block);
+                cc.set_auto_compaction(self.compaction);
+                self.cc = Some(cc);
+            }
         }
+    }
 
-        (p0, p1)
+    /// remove the block at `x`, `y`, dropping the underlying contig if it becomes empty
+    fn remove_block(&mut self, x: Idx, y: Idx) {
+        if let Some(cc) = &mut self.cc {
+            cc.remove(x, y);
+            if cc.is_empty() {
+                self.cc = None;
+            }
+        }
     }
 
-    pub fn from_rows<H>(rows_of_bytes: &[Vec<H>], origin: Coordinate<Idx>) -> Playfield<Idx, T>
+    pub fn from_rows(rows: &[Vec<T::Half>], origin: Coordinate<Idx>) -> Playfield<Idx, T>
     where
-        T: std::fmt::LowerHex,
-        H: Copy + Default + std::fmt::LowerHex,
+        Idx: Integer,
     {
         use std::vec::IntoIter;
 
         let mut playfield = Playfield::<Idx, T>::new();
 
-        for (y_u, chunk) in rows_of_bytes.chunks(2).enumerate() {
-            for (x_u, p) in PairwiseOrDefault::<IntoIter<H>>::from(chunk).enumerate() {
-                let merged_pair = Self::pack::<H>(p);
-                println!(
-                    "pair ({}, {}) {:02x}{:02x}\n----------- {:04x}",
-                    x_u, y_u, p.1, p.0, merged_pair
-                );
-                if !T::is_zero(&merged_pair) {
+        for (y_u, chunk) in rows.chunks(2).enumerate() {
+            for (x_u, pair) in PairwiseOrDefault::<IntoIter<T::Half>>::from(chunk).enumerate() {
+                let block = T::pack(pair);
+                if !T::is_zero(&block) {
                     let x = Idx::from_usize(x_u).unwrap() + origin.x;
                     let y = Idx::from_usize(y_u).unwrap() + origin.y;
-                    playfield.cc.set(x, y, merged_pair);
+                    playfield.set_block(x, y, block);
                 }
             }
         }
 
+        playfield.recount();
         playfield
     }
 
     // space wasting conversion into packed vectors
-    pub fn to_rows<H>(&self) -> (Vec<Vec<H>>, Coordinate<Idx>)
-    where
-        Idx: FromPrimitive + Debug,
-        T: Copy,
-        H: Zero + Copy,
-    {
-        let origin = self.cc.origin();
-        let mut rows: Vec<Vec<H>> = Vec::new();
+    pub fn to_rows(&self) -> (Vec<Vec<T::Half>>, Coordinate<Idx>) {
+        let cc = match &self.cc {
+            Some(cc) => cc,
+            None => return (Vec::new(), Coordinate::default()),
+        };
+        let origin = cc.origin();
+        let mut rows: Vec<Vec<T::Half>> = Vec::new();
+
+        for (y, row) in cc.rows_enumerator() {
+            // empty double rows for any gap
+            while origin.y < y - Idx::from_usize(rows.len() / 2).unwrap() {
+                rows.push(Vec::new());
+                rows.push(Vec::new());
+            }
 
-        for (y, row) in self.cc.rows_enumerator() {
             let mut lower_items = Vec::new();
             let mut upper_items = Vec::new();
             for (x, merged_item) in row.enumerator() {
                 while origin.x < x - Idx::from_usize(lower_items.len()).unwrap() {
-                    lower_items.push(H::zero());
-                    upper_items.push(H::zero());
+                    lower_items.push(T::Half::zero());
+                    upper_items.push(T::Half::zero());
                 }
 
-                let (lower, upper) = Self::unpack::<H>(*merged_item);
+                let (lower, upper) = merged_item.unpack();
 
                 lower_items.push(lower);
                 upper_items.push(upper);
@@ -196,6 +274,723 @@ where
         }
         (rows, origin)
     }
+
+    /// width in cells of a block, that is, of each of its half rows
+    fn block_width() -> usize {
+        T::WIDTH
+    }
+
+    /// the block containing the cell at `x`, `y`, and the mask of the cell within it
+    fn locate_cell(x: Idx, y: Idx) -> (Idx, Idx, u128)
+    where
+        Idx: Integer,
+    {
+        let width = Self::block_width();
+        let (x_block, col) = x.div_mod_floor(&Idx::from_usize(width).unwrap());
+        let (y_block, row) = y.div_mod_floor(&Idx::from_usize(2).unwrap());
+        let bit = row.as_() * width + width - 1 - col.as_();
+        (x_block, y_block, 1 << bit)
+    }
+
+    /// create from the coordinates of live cells
+    pub fn from_cells<I>(cells: I) -> Playfield<Idx, T>
+    where
+        I: IntoIterator<Item = Coordinate<Idx>>,
+        Idx: Integer,
+        u128: AsPrimitive<T>,
+    {
+        let mut playfield = Playfield::<Idx, T>::new();
+
+        for cell in cells {
+            playfield.set_cell(cell.x, cell.y, true);
+        }
+
+        playfield
+    }
+
+    /// the bits of the block containing the cell at `x`, `y`, its location, and the mask of the cell
+    fn locate_block(&self, x: Idx, y: Idx) -> (u128, Idx, Idx, u128)
+    where
+        Idx: Integer,
+    {
+        let (x_block, y_block, mask) = Self::locate_cell(x, y);
+        let block = self
+            .cc
+            .as_ref()
+            .and_then(|cc| cc.get(x_block, y_block))
+            .map_or(0, |block| block.as_());
+        (block, x_block, y_block, mask)
+    }
+
+    pub fn get_cell(&self, x: Idx, y: Idx) -> bool
+    where
+        Idx: Integer,
+    {
+        let (block, _, _, mask) = self.locate_block(x, y);
+        block & mask != 0
+    }
+
+    /// set the cell at `x`, `y` live or dead, removing its block if no live cells remain
+    pub fn set_cell(&mut self, x: Idx, y: Idx, live: bool)
+    where
+        Idx: Integer,
+        u128: AsPrimitive<T>,
+    {
+        let (block, x_block, y_block, mask) = self.locate_block(x, y);
+        if (block & mask != 0) == live {
+            return;
+        }
+        let block = block ^ mask;
+        self.changes = None;
+        if block == 0 {
+            self.remove_block(x_block, y_block);
+        } else {
+            self.set_block(x_block, y_block, block.as_());
+        }
+
+        let cell = Some((Coordinate { x, y }, Coordinate { x, y }));
+        if live {
+            self.population += 1;
+            self.bounds = Self::union(self.bounds, cell);
+        } else {
+            self.population -= 1;
+            if Self::on_edge(self.bounds, cell) {
+                self.bounds = self.measure_bounds();
+            }
+        }
+    }
+
+    pub fn toggle_cell(&mut self, x: Idx, y: Idx)
+    where
+        Idx: Integer,
+        u128: AsPrimitive<T>,
+    {
+        let live = self.get_cell(x, y);
+        self.set_cell(x, y, !live);
+    }
+
+    /// the coordinates of all live cells, in order of blocks, so not strictly ordered by row
+    pub fn cells(&self) -> Vec<Coordinate<Idx>>
+    where
+        Idx: Integer,
+    {
+        let width = Self::block_width();
+        let mut cells = Vec::new();
+
+        if let Some(cc) = &self.cc {
+            for (y_block, row) in cc.rows_enumerator() {
+                for (x_block, block) in row.enumerator() {
+                    let block: u128 = block.as_();
+                    for bit in (0..width * 2).filter(|bit| block & (1 << bit) != 0) {
+                        let col = width - 1 - bit % width;
+                        let row = bit / width;
+                        cells.push(Coordinate {
+                            x: x_block * Idx::from_usize(width).unwrap()
+                                + Idx::from_usize(col).unwrap(),
+                            y: y_block * Idx::from_usize(2).unwrap()
+                                + Idx::from_usize(row).unwrap(),
+                        });
+                    }
+                }
+            }
+        }
+
+        cells
+    }
+
+    /// the number of live cells
+    pub fn population(&self) -> usize {
+        self.population
+    }
+
+    /// the top left and bottom right corners of the smallest rectangle containing all live
+    /// cells, if any
+    pub fn bounding_box(&self) -> Option<(Coordinate<Idx>, Coordinate<Idx>)> {
+        self.bounds
+    }
+
+    /// the bounding box of the cells `bits` of the block at `x`, `y`, if any
+    fn block_bounds(x: Idx, y: Idx, bits: u128) -> Option<Bounds<Idx>>
+    where
+        Idx: Integer,
+    {
+        let width = Self::block_width();
+        let halves = [bits & ((1 << width) - 1), bits >> width];
+        let columns = halves[0] | halves[1];
+        if columns == 0 {
+            return None;
+        }
+
+        let from = |u: usize| Idx::from_usize(u).unwrap();
+        let (x0, y0) = (x * from(width), y * from(2));
+        let first_row = if halves[0] != 0 { 0 } else { 1 };
+        let last_row = if halves[1] != 0 { 1 } else { 0 };
+        // the most significant bit is the leftmost cell
+        let first_column = columns.leading_zeros() as usize - (128 - width);
+        let last_column = width - 1 - columns.trailing_zeros() as usize;
+        Some((
+            Coordinate {
+                x: x0 + from(first_column),
+                y: y0 + from(first_row),
+            },
+            Coordinate {
+                x: x0 + from(last_column),
+                y: y0 + from(last_row),
+            },
+        ))
+    }
+
+    /// the smallest bounding box containing both
+    fn union(a: Option<Bounds<Idx>>, b: Option<Bounds<Idx>>) -> Option<Bounds<Idx>> {
+        match (a, b) {
+            (Some((a_min, a_max)), Some((b_min, b_max))) => Some((
+                Coordinate {
+                    x: a_min.x.min(b_min.x),
+                    y: a_min.y.min(b_min.y),
+                },
+                Coordinate {
+                    x: a_max.x.max(b_max.x),
+                    y: a_max.y.max(b_max.y),
+                },
+            )),
+            (a, None) => a,
+            (None, b) => b,
+        }
+    }
+
+    /// whether `inner`, within `outer`, reaches any of its edges
+    fn on_edge(outer: Option<Bounds<Idx>>, inner: Option<Bounds<Idx>>) -> bool {
+        match (outer, inner) {
+            (Some((min, max)), Some((inner_min, inner_max))) => {
+                inner_min.x == min.x
+                    || inner_min.y == min.y
+                    || inner_max.x == max.x
+                    || inner_max.y == max.y
+            }
+            _ => false,
+        }
+    }
+
+    /// the bounding box from the blocks, where only the first and last live blocks of each
+    /// row can reach the left and right edges, and only the first and last rows with any
+    /// live blocks the top and bottom edges
+    ///
+    /// Blocks are live other than where compaction keeps zero blocks.
+    fn measure_bounds(&self) -> Option<Bounds<Idx>>
+    where
+        Idx: Integer,
+    {
+        let cc = self.cc.as_ref()?;
+        let live = |block: &T| !block.is_zero();
+        let mut bounds = None;
+        let mut live_rows = (None, None);
+        for (y, row) in cc.rows_enumerator() {
+            let first = row.enumerator().find(|(_, block)| live(block));
+            let last = row.rfind(live);
+            for (x, block) in first.into_iter().chain(last) {
+                bounds = Self::union(bounds, Self::block_bounds(x, y, block.as_()));
+            }
+            if first.is_some() {
+                live_rows.0.get_or_insert((y, row));
+                live_rows.1 = Some((y, row));
+            }
+        }
+
+        for (y, row) in live_rows.0.into_iter().chain(live_rows.1) {
+            for (x, block) in row.enumerator() {
+                bounds = Self::union(bounds, Self::block_bounds(x, y, block.as_()));
+            }
+        }
+        bounds
+    }
+
+    /// count the population and measure the bounding box afresh
+    fn recount(&mut self)
+    where
+        Idx: Integer,
+    {
+        self.population = self.cc.as_ref().map_or(0, |cc| {
+            cc.rows_enumerator()
+                .flat_map(|(_, row)| row.enumerator())
+                .map(|(_, block)| block.as_().count_ones() as usize)
+                .sum()
+        });
+        self.bounds = self.measure_bounds();
+    }
+
+    /// compute the next generation under `rule`
+    ///
+    /// This copies every block, so to run a pattern, `advance` in place instead.
+    pub fn step(&self, rule: &Rule) -> Playfield<Idx, T>
+    where
+        Idx: Integer + Send + Sync,
+        u128: AsPrimitive<T>,
+    {
+        self.step_with_kernel(&Kernel::new(rule))
+    }
+
+    /// advance to the next generation under `rule` in place, touching only the blocks which
+    /// change
+    pub fn advance(&mut self, rule: &Rule)
+    where
+        Idx: Integer + Send + Sync,
+        u128: AsPrimitive<T>,
+    {
+        self.advance_with_kernel(&Kernel::new(rule));
+    }
+
+    fn step_with_kernel(&self, kernel: &Kernel) -> Playfield<Idx, T>
+    where
+        Idx: Integer + Send + Sync,
+        u128: AsPrimitive<T>,
+    {
+        let mut next = self.clone();
+        next.advance_with_kernel(kernel);
+        next
+    }
+
+    /// advance a row of blocks at a time, over each run of blocks which may change, so that
+    /// the kernel can process the blocks of a run together
+    ///
+    /// Where the changes of the last step by the same kernel are known, only the blocks
+    /// around them are evaluated, and the rest are unchanged.  Otherwise, every block
+    /// within a block of a live one is evaluated.
+    ///
+    /// Large patterns are split into bands of rows stepped in parallel, each reading the
+    /// rows either side of it, so that the changes are stitched together unchanged.
+    ///
+    /// The population and bounding box are updated from the changes, unless cells die on
+    /// the edge of the bounding box, when it is measured again.
+    fn advance_with_kernel(&mut self, kernel: &Kernel)
+    where
+        Idx: Integer + Send + Sync,
+        u128: AsPrimitive<T>,
+    {
+        let cc = match &self.cc {
+            Some(cc) => cc,
+            None => return,
+        };
+
+        let work = match &self.changes {
+            Some(changes) if changes.kernel == *kernel => Self::around(&changes.rows),
+            _ => cc
+                .row_neighbourhood_enumerator_with_radius::<1>()
+                .map(|nbh| (nbh.i, Self::runs(&nbh.items)))
+                .collect(),
+        };
+        let bands = self.bands(work.len());
+        let stepped = if bands == 1 {
+            vec![Self::step_band(cc, &work, kernel)]
+        } else {
+            std::thread::scope(|scope| {
+                let handles = work
+                    .chunks(work.len().div_ceil(bands))
+                    .map(|band| scope.spawn(move || Self::step_band(cc, band, kernel)))
+                    .collect::<Vec<_>>();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("stepping thread panicked"))
+                    .collect()
+            })
+        };
+
+        let bounds = self.bounds;
+        let mut rows: Vec<(Idx, Vec<Range<Idx>>)> = Vec::new();
+        let mut shrunk = false;
+        for (x, y, old, block) in stepped.into_iter().flatten() {
+            let died = self.replace_block(x, y, old.as_(), block.as_());
+            shrunk = shrunk || Self::on_edge(bounds, died);
+
+            match rows.last_mut() {
+                Some((row_y, runs)) if *row_y == y => match runs.last_mut() {
+                    Some(run) if run.end == x => run.end = x + Idx::one(),
+                    _ => runs.push(x..x + Idx::one()),
+                },
+                _ => rows.push((y, vec![x..x + Idx::one()])),
+            }
+        }
+        if shrunk {
+            self.bounds = self.measure_bounds();
+        }
+        self.changes = Some(Changes {
+            kernel: kernel.clone(),
+            rows,
+        });
+    }
+
+    /// replace the block at `x`, `y`, which was `old`, with `new`, counting the cells born
+    /// and died and growing the bounding box by those born, and returning the bounding box
+    /// of those which died, if any
+    fn replace_block(&mut self, x: Idx, y: Idx, old: u128, new: u128) -> Option<Bounds<Idx>>
+    where
+        Idx: Integer,
+        u128: AsPrimitive<T>,
+    {
+        if new == 0 {
+            self.remove_block(x, y);
+        } else {
+            self.set_block(x, y, new.as_());
+        }
+
+        let (born, died) = (new & !old, old & !new);
+        self.population = self.population + born.count_ones() as usize - died.count_ones() as usize;
+        self.bounds = Self::union(self.bounds, Self::block_bounds(x, y, born));
+        Self::block_bounds(x, y, died)
+    }
+
+    /// the number of bands to step `rows` block rows in, one per thread, where each band
+    /// has at least `MIN_BAND_ROWS` rows
+    fn bands(&self, rows: usize) -> usize {
+        let most = rows / MIN_BAND_ROWS;
+        if most <= 1 {
+            return 1;
+        }
+        let threads = self.threads.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        });
+        threads.clamp(1, most)
+    }
+
+    /// the blocks of the next generation which differ from those of `cc`, within the runs
+    /// of each row of `work`, in order, with those they replace
+    fn step_band(
+        cc: &CartesianContig<Idx, T>,
+        work: &[(Idx, Vec<Range<Idx>>)],
+        kernel: &Kernel,
+    ) -> Vec<(Idx, Idx, T, T)>
+    where
+        u128: AsPrimitive<T>,
+    {
+        let mut stepped = Vec::new();
+        let mut padded: [Vec<T>; 3] = Default::default();
+        let mut blocks = Vec::new();
+        for (y, runs) in work {
+            let contigs = [cc.row(*y - Idx::one()), cc.row(*y), cc.row(*y + Idx::one())];
+            for run in runs {
+                let len = (run.end - run.start).as_();
+
+                // each row of the run, with a block of padding either side
+                for (row, contig) in padded.iter_mut().zip(contigs) {
+                    row.clear();
+                    row.resize(len + 2, T::zero());
+                    if let Some(contig) = contig {
+                        for (x, block) in contig.range(run.start - Idx::one()..run.end + Idx::one())
+                        {
+                            row[(x + Idx::one() - run.start).as_()] = *block;
+                        }
+                    }
+                }
+
+                blocks.clear();
+                blocks.resize(len, T::zero());
+                kernel.step_row([&padded[0], &padded[1], &padded[2]], &mut blocks);
+
+                for (u, block) in blocks.iter().enumerate() {
+                    let old = padded[1][u + 1];
+                    if *block != old {
+                        let x = run.start + Idx::from_usize(u).unwrap();
+                        stepped.push((x, *y, old, *block));
+                    }
+                }
+            }
+        }
+        stepped
+    }
+
+    /// the runs of blocks of the centre row of `rows` which may be live next, being those
+    /// within one block of a block in any of the rows
+    fn runs(rows: &Window<Option<&Contig<Idx, T>>, 1>) -> Vec<Range<Idx>> {
+        Self::merged(
+            rows.iter()
+                .flatten()
+                .flat_map(|contig| contig.spans())
+                .map(|(origin, items)| {
+                    origin - Idx::one()..origin + Idx::from_usize(items.len() + 1).unwrap()
+                })
+                .collect(),
+        )
+    }
+
+    /// the runs of blocks within one block of the runs of `changed`, by row, in order
+    fn around(changed: &[(Idx, Vec<Range<Idx>>)]) -> Vec<(Idx, Vec<Range<Idx>>)> {
+        let mut rows: Vec<(Idx, Vec<Range<Idx>>)> = Vec::new();
+        for (y, runs) in changed {
+            for row_y in [*y - Idx::one(), *y, *y + Idx::one()] {
+                let widened = runs
+                    .iter()
+                    .map(|run| run.start - Idx::one()..run.end + Idx::one());
+                // rows arrive in order, so any earlier entry for the row is one of the last two
+                match rows.iter_mut().rev().take(2).find(|(y, _)| *y == row_y) {
+                    Some((_, runs)) => runs.extend(widened),
+                    None => rows.push((row_y, widened.collect())),
+                }
+            }
+        }
+        rows.into_iter()
+            .map(|(y, runs)| (y, Self::merged(runs)))
+            .collect()
+    }
+
+    /// `ranges` sorted, and merged where they overlap or touch
+    fn merged(mut ranges: Vec<Range<Idx>>) -> Vec<Range<Idx>> {
+        ranges.sort_by_key(|range| range.start);
+
+        let mut merged: Vec<Range<Idx>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    /// compute the next generation block by block from their neighbourhoods, as the
+    /// reference for stepping by rows
+    fn step_by_blocks(&self, kernel: &Kernel) -> Playfield<Idx, T>
+    where
+        Idx: Integer,
+        u128: AsPrimitive<T>,
+    {
+        let mut next = self.empty_like();
+
+        if let Some(cc) = &self.cc {
+            for nbh in cc.neighbourhood_enumerator() {
+                let block = Self::next_block(&nbh, kernel);
+                if !T::is_zero(&block) {
+                    next.set_block(nbh.i_col, nbh.i_row, block);
+                }
+            }
+        }
+
+        next.recount();
+        next
+    }
+
+    /// compute the next generation under `rule` on `topology`, whose cells must all be
+    /// within its bounds
+    ///
+    /// This does not go through `CartesianContig::neighbourhood_enumerator_with_topology`,
+    /// as Generations playfields do, since that gives the neighbourhood of each item, and
+    /// here the items are blocks. Edges rarely fall on block boundaries, so the neighbours
+    /// of a block across an edge are cells of several blocks, mirrored where the edge is
+    /// twisted, and not blocks the kernel could step with. Instead the cells across each
+    /// edge are copied as ghost cells just outside it, the blocks are stepped as on the
+    /// plane, and anything outside is masked off afterwards, in both cases a block at a time.
+    pub fn step_with_topology(&self, rule: &Rule, topology: Topology) -> Playfield<Idx, T>
+    where
+        Idx: Integer + Send + Sync,
+        u128: AsPrimitive<T>,
+    {
+        let mut next = self.clone();
+        next.advance_with_topology(rule, topology);
+        next
+    }
+
+    /// advance to the next generation under `rule` on `topology` in place, as
+    /// `step_with_topology`
+    pub fn advance_with_topology(&mut self, rule: &Rule, topology: Topology)
+    where
+        Idx: Integer + Send + Sync,
+        u128: AsPrimitive<T>,
+    {
+        let (min, max) = match topology.bounds::<Idx>() {
+            Some(bounds) => bounds,
+            None => return self.advance(rule),
+        };
+
+        for ((y, x), ghosts) in self.ghosts(topology, min, max) {
+            let old = self.block_bits(x, y);
+            self.replace_block(x, y, old, old | ghosts);
+        }
+        self.changes = None;
+        self.advance(rule);
+
+        // the blocks which may hold cells within two of the edges, outside them
+        let two = Idx::from_usize(2).unwrap();
+        let (outer_min, outer_max) = (
+            Coordinate {
+                x: min.x - two,
+                y: min.y - two,
+            },
+            Coordinate {
+                x: max.x + two,
+                y: max.y + two,
+            },
+        );
+        let (x0, y0, _) = Self::locate_cell(outer_min.x, outer_min.y);
+        let (x1, y1, _) = Self::locate_cell(outer_max.x, outer_max.y);
+        let (inner_x0, inner_y0, _) = Self::locate_cell(min.x + two, min.y + two);
+        let (inner_x1, inner_y1, _) = Self::locate_cell(max.x - two, max.y - two);
+        let mut edges = Vec::new();
+        if let Some(cc) = &self.cc {
+            let mut y = y0;
+            while y <= y1 {
+                // away from the top and bottom, only the blocks by the left and right edges
+                let ranges = if inner_y0 < y && y < inner_y1 && inner_x0 < inner_x1 {
+                    [x0..inner_x0 + Idx::one(), inner_x1..x1 + Idx::one()]
+                } else {
+                    [x0..x1 + Idx::one(), x1..x1]
+                };
+                for range in ranges {
+                    let blocks = cc
+                        .row(y)
+                        .into_iter()
+                        .flat_map(|row| row.range(range.clone()));
+                    edges.extend(blocks.map(|(x, block)| (x, y, block.as_())));
+                }
+                y += Idx::one();
+            }
+        }
+
+        let bounds = self.bounds;
+        let mut shrunk = false;
+        for (x, y, old) in edges {
+            let new = old & Self::mask_within(x, y, min, max);
+            if new != old {
+                shrunk = Self::on_edge(bounds, self.replace_block(x, y, old, new)) || shrunk;
+            }
+        }
+        if shrunk {
+            self.bounds = self.measure_bounds();
+        }
+        self.changes = None;
+    }
+
+    /// the cells to copy just outside the edges of `topology`, whose bounds are `min` and
+    /// `max`, from across them, by block
+    ///
+    /// A row across an edge is read a block at a time, mirrored by whole blocks where the
+    /// edge is twisted, and a column a cell at a time, as each cell is in a different row.
+    fn ghosts(
+        &self,
+        topology: Topology,
+        min: Coordinate<Idx>,
+        max: Coordinate<Idx>,
+    ) -> BTreeMap<(Idx, Idx), u128>
+    where
+        Idx: Integer,
+    {
+        let width = Self::block_width();
+        let from = |u: usize| Idx::from_usize(u).unwrap();
+        let mut ghosts: BTreeMap<(Idx, Idx), u128> = BTreeMap::new();
+        let mut add = |x: Idx, y: Idx, bits: u128| {
+            if bits != 0 {
+                *ghosts.entry((y, x)).or_insert(0) |= bits;
+            }
+        };
+
+        // the rows above and below
+        for y in [min.y - Idx::one(), max.y + Idx::one()] {
+            let source = match topology.wrap(min.x, y) {
+                Some(source) => source,
+                None => continue,
+            };
+            let mirrored = source.x != min.x;
+            let (x0, y_block, _) = Self::locate_cell(min.x, y);
+            let (x1, _, _) = Self::locate_cell(max.x, y);
+            let row = Coordinate { x: min.x, y };
+            let mut x = x0;
+            while x <= x1 {
+                let start = x * from(width);
+                let bits = if mirrored {
+                    // the cells mirrored onto those of this block, in reverse
+                    let end = min.x + max.x - start;
+                    let cells = self.half_row_at(end - from(width - 1), source.y);
+                    cells.reverse_bits() >> (128 - width)
+                } else {
+                    self.half_row_at(start, source.y)
+                };
+                let mask = Self::mask_within(x, y_block, row, Coordinate { x: max.x, y });
+                let shift = if y.mod_floor(&from(2)).is_zero() {
+                    0
+                } else {
+                    width
+                };
+                add(x, y_block, (bits << shift) & mask);
+                x += Idx::one();
+            }
+        }
+
+        // the columns either side, and the corners
+        let mut y = min.y - Idx::one();
+        while y <= max.y + Idx::one() {
+            for x in [min.x - Idx::one(), max.x + Idx::one()] {
+                if let Some(source) = topology.wrap(x, y) {
+                    if self.get_cell(source.x, source.y) {
+                        let (x_block, y_block, mask) = Self::locate_cell(x, y);
+                        add(x_block, y_block, mask);
+                    }
+                }
+            }
+            y += Idx::one();
+        }
+        ghosts
+    }
+
+    /// the bits of the block at `x`, `y`
+    fn block_bits(&self, x: Idx, y: Idx) -> u128 {
+        self.cc
+            .as_ref()
+            .and_then(|cc| cc.get(x, y))
+            .map_or(0, |block| block.as_())
+    }
+
+    /// the `T::WIDTH` cells of row `y` from `x` onwards, with the first the most significant
+    fn half_row_at(&self, x: Idx, y: Idx) -> u128
+    where
+        Idx: Integer,
+    {
+        let width = Self::block_width();
+        let mask = (1u128 << width) - 1;
+        let (x_block, offset) = x.div_mod_floor(&Idx::from_usize(width).unwrap());
+        let (y_block, row) = y.div_mod_floor(&Idx::from_usize(2).unwrap());
+        let half = |x| (self.block_bits(x, y_block) >> (row.as_() * width)) & mask;
+        let offset = offset.as_();
+        ((half(x_block) << offset) | (half(x_block + Idx::one()) >> (width - offset))) & mask
+    }
+
+    /// the mask of the cells of the block at `x`, `y` within the rectangle from `min` to
+    /// `max`
+    fn mask_within(x: Idx, y: Idx, min: Coordinate<Idx>, max: Coordinate<Idx>) -> u128
+    where
+        Idx: Integer,
+    {
+        let width = Self::block_width();
+        let from = |u: usize| Idx::from_usize(u).unwrap();
+        let (x0, y0) = (x * from(width), y * from(2));
+        if max.x < x0 || x0 + from(width) <= min.x {
+            return 0;
+        }
+
+        // the columns within, counted from the left
+        let first = if min.x > x0 { (min.x - x0).as_() } else { 0 };
+        let last = if max.x < x0 + from(width) {
+            (max.x - x0).as_()
+        } else {
+            width - 1
+        };
+        let columns = ((1u128 << (last - first + 1)) - 1) << (width - 1 - last);
+
+        let mut mask = 0;
+        for row in 0..2 {
+            if (min.y..=max.y).contains(&(y0 + from(row))) {
+                mask |= columns << (row * width);
+            }
+        }
+        mask
+    }
+
+    /// compute the next state of the focused block in the neighbourhood
+    fn next_block(nbh: &CartesianNeighbourhood<Idx, &T>, kernel: &Kernel) -> T
+    where
+        u128: AsPrimitive<T>,
+    {
+        let blocks = std::array::from_fn(|r| {
+            std::array::from_fn(|c| nbh.items[r][c].map_or(0, |block| block.as_()))
+        });
+        kernel.next_block(blocks, Self::block_width()).as_()
+    }
 }
 
 mod tests;
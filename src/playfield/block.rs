@@ -0,0 +1,68 @@
+use num::cast::AsPrimitive;
+use num::Zero;
+use std::fmt::Debug;
+
+/// a block of cells, being two half rows packed into an unsigned integer
+///
+/// The first half row of the pair is in the less significant half, and within each half
+/// row the most significant bit is the leftmost cell.  Packing is by shifts alone, so the
+/// layout is the same on every platform.
+pub trait Block: Copy + Debug + Zero + Eq + Send + Sync + AsPrimitive<u128> {
+    /// a single half row, of half the width of the block
+    type Half: Copy + Debug + Default + Zero + Eq;
+
+    /// width in cells of each half row
+    const WIDTH: usize;
+
+    /// pack a pair of half rows into a block
+    fn pack(pair: (Self::Half, Self::Half)) -> Self;
+
+    /// unpack a block into its pair of half rows
+    fn unpack(self) -> (Self::Half, Self::Half);
+
+    /// the blocks as `u64`, for the vectorised kernel, if that is their type
+    fn as_u64s(_blocks: &[Self]) -> Option<&[u64]> {
+        None
+    }
+
+    /// the blocks as mutable `u64`, if that is their type
+    fn as_u64s_mut(_blocks: &mut [Self]) -> Option<&mut [u64]> {
+        None
+    }
+}
+
+macro_rules! impl_block {
+    ($block:ty, $half:ty $(, $extra:item)*) => {
+        impl Block for $block {
+            type Half = $half;
+
+            const WIDTH: usize = <$half>::BITS as usize;
+
+            fn pack(pair: ($half, $half)) -> $block {
+                (pair.0 as $block) | ((pair.1 as $block) << Self::WIDTH)
+            }
+
+            fn unpack(self) -> ($half, $half) {
+                (self as $half, (self >> Self::WIDTH) as $half)
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+impl_block!(u16, u8);
+impl_block!(u32, u16);
+impl_block!(
+    u64,
+    u32,
+    fn as_u64s(blocks: &[u64]) -> Option<&[u64]> {
+        Some(blocks)
+    },
+    fn as_u64s_mut(blocks: &mut [u64]) -> Option<&mut [u64]> {
+        Some(blocks)
+    }
+);
+impl_block!(u128, u64);
+
+mod tests;
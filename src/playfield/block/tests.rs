@@ -0,0 +1,43 @@
+#![cfg(test)]
+use super::*;
+use assert_hex::assert_eq_hex;
+
+#[test]
+fn test_pack_unpack_roundtrip() {
+    assert_eq_hex!(u16::pack((0x03, 0x04)), 0x0403);
+    assert_eq_hex!(0x0605u16.unpack(), (0x05, 0x06));
+    assert_eq_hex!(u16::pack((0x01, 0x02)).unpack(), (0x01, 0x02));
+
+    assert_eq_hex!(u32::pack((0x0102, 0x0304)), 0x03040102);
+    assert_eq_hex!(0x05060708u32.unpack(), (0x0708, 0x0506));
+    assert_eq_hex!(u32::pack((0x0102, 0x0304)).unpack(), (0x0102, 0x0304));
+
+    assert_eq_hex!(u64::pack((0x01020304, 0x05060708)), 0x0506070801020304);
+    assert_eq_hex!(0x0506070801020304u64.unpack(), (0x01020304, 0x05060708));
+
+    assert_eq_hex!(
+        u128::pack((0x0102030405060708, 0x090a0b0c0d0e0f10)),
+        0x090a0b0c0d0e0f100102030405060708
+    );
+    assert_eq_hex!(
+        0x090a0b0c0d0e0f100102030405060708u128.unpack(),
+        (0x0102030405060708, 0x090a0b0c0d0e0f10)
+    );
+}
+
+#[test]
+fn test_pack_high_bits() {
+    // the top bit of each half survives, with no sign extension or overlap
+    assert_eq_hex!(u16::pack((0x80, 0x80)), 0x8080);
+    assert_eq_hex!(u16::pack((0xff, 0)), 0x00ff);
+    assert_eq_hex!(u64::pack((0xffffffff, 0)).unpack(), (0xffffffff, 0));
+    assert_eq_hex!(u128::pack((0, u64::MAX)).unpack(), (0, u64::MAX));
+}
+
+#[test]
+fn test_width() {
+    assert_eq!(<u16 as Block>::WIDTH, 8);
+    assert_eq!(<u32 as Block>::WIDTH, 16);
+    assert_eq!(<u64 as Block>::WIDTH, 32);
+    assert_eq!(<u128 as Block>::WIDTH, 64);
+}
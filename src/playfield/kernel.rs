@@ -0,0 +1,209 @@
+use super::Block;
+use crate::rule::Rule;
+use num::cast::AsPrimitive;
+
+/// the largest neighbour count, of the Moore neighbourhood
+const MAX_COUNT: usize = 8;
+
+/// computes a half row of the next generation from the three half rows around it
+///
+/// Each row is widened by one cell either side, so that the cell at column `k` of the
+/// result, counting from the right, is at bit `k + 1` of each row, with its left and right
+/// neighbours at bits `k + 2` and `k`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kernel {
+    /// counts the neighbours of every cell at once with a network of full adders, for
+    /// totalistic rules
+    Adder {
+        /// the neighbours which count, as for the neighbour configuration of a rule
+        mask: usize,
+        birth: [bool; MAX_COUNT + 1],
+        survival: [bool; MAX_COUNT + 1],
+    },
+    /// looks up each cell in turn in the transition table, for any rule
+    Table(Vec<bool>),
+}
+
+/// sum and carry of three bit planes
+fn full_add(a: u128, b: u128, c: u128) -> (u128, u128) {
+    let s = a ^ b;
+    (s ^ c, (a & b) | (s & c))
+}
+
+/// sum and carry of two bit planes
+fn half_add(a: u128, b: u128) -> (u128, u128) {
+    (a ^ b, a & b)
+}
+
+impl Kernel {
+    /// the adder for totalistic rules, and the table otherwise
+    pub fn new(rule: &Rule) -> Kernel {
+        match rule.totalistic_counts() {
+            Some((birth, survival)) => Kernel::Adder {
+                mask: rule.kind().mask(),
+                birth,
+                survival,
+            },
+            None => Kernel::table(rule),
+        }
+    }
+
+    /// the table for any rule, as the reference for the adder
+    pub fn table(rule: &Rule) -> Kernel {
+        Kernel::Table(rule.transition_table())
+    }
+
+    /// the next state of the centre of a 3x3 neighbourhood of blocks of width `width`
+    pub fn next_block(&self, blocks: [[u128; 3]; 3], width: usize) -> u128 {
+        let mask = (1u128 << width) - 1;
+
+        // the half rows of a block row, widened by the adjacent cell either side
+        let widened = |row: &[u128; 3], upper: bool| -> u128 {
+            let half = |block: u128| if upper { block >> width } else { block & mask };
+            let left = half(row[0]) & 1;
+            let right = half(row[2]) >> (width - 1);
+            (left << (width + 1)) | (half(row[1]) << 1) | right
+        };
+
+        let rows = [
+            widened(&blocks[0], true),
+            widened(&blocks[1], false),
+            widened(&blocks[1], true),
+            widened(&blocks[2], false),
+        ];
+
+        let halves = [
+            self.next_half([rows[0], rows[1], rows[2]], width),
+            self.next_half([rows[1], rows[2], rows[3]], width),
+        ];
+
+        (halves[1] << width) | halves[0]
+    }
+
+    /// the next state of each block of the row `rows[1]`, where the rows are padded with a
+    /// block either side, so that `next[k]` is the centre of `rows[..][k..k + 3]`
+    ///
+    /// The adder steps several `u64` blocks at once where the processor allows.
+    pub fn step_row<T>(&self, rows: [&[T]; 3], next: &mut [T])
+    where
+        T: Block,
+        u128: AsPrimitive<T>,
+    {
+        let vectorised = match self {
+            Kernel::Adder {
+                mask,
+                birth,
+                survival,
+            } => match (
+                T::as_u64s(rows[0]),
+                T::as_u64s(rows[1]),
+                T::as_u64s(rows[2]),
+                T::as_u64s_mut(next),
+            ) {
+                (Some(upper), Some(centre), Some(lower), Some(next)) => {
+                    simd::step_row(*mask, birth, survival, [upper, centre, lower], next)
+                }
+                _ => 0,
+            },
+            Kernel::Table(_) => 0,
+        };
+
+        for k in vectorised..next.len() {
+            let blocks = std::array::from_fn(|r| std::array::from_fn(|c| rows[r][k + c].as_()));
+            next[k] = self.next_block(blocks, T::WIDTH).as_();
+        }
+    }
+
+    /// the next state of the `width` cells of the half row `rows[1]`
+    pub fn next_half(&self, rows: [u128; 3], width: usize) -> u128 {
+        match self {
+            Kernel::Adder {
+                mask,
+                birth,
+                survival,
+            } => Kernel::next_half_adder(rows, width, *mask, birth, survival),
+            Kernel::Table(table) => Kernel::next_half_table(rows, width, table),
+        }
+    }
+
+    fn next_half_adder(
+        rows: [u128; 3],
+        width: usize,
+        mask: usize,
+        birth: &[bool; MAX_COUNT + 1],
+        survival: &[bool; MAX_COUNT + 1],
+    ) -> u128 {
+        let cells = (1u128 << width) - 1;
+        let [upper, centre, lower] = rows;
+
+        // in the order of the bits of a neighbour configuration, most significant first
+        let neighbours = [
+            upper >> 2,
+            upper >> 1,
+            upper,
+            centre >> 2,
+            centre,
+            lower >> 2,
+            lower >> 1,
+            lower,
+        ];
+        let n = std::array::from_fn::<u128, MAX_COUNT, _>(|k| {
+            if mask & (1 << (MAX_COUNT - 1 - k)) != 0 {
+                neighbours[k] & cells
+            } else {
+                0
+            }
+        });
+
+        // the count of each cell as four bit planes, from a tree of adders
+        let (s0, c0) = full_add(n[0], n[1], n[2]);
+        let (s1, c1) = full_add(n[3], n[4], n[5]);
+        let (s2, c2) = half_add(n[6], n[7]);
+        let (ones, c3) = full_add(s0, s1, s2);
+        let (t0, c4) = full_add(c0, c1, c2);
+        let (twos, c5) = half_add(t0, c3);
+        let (fours, eights) = half_add(c4, c5);
+        let bits = [ones, twos, fours, eights];
+
+        let mut born = 0;
+        let mut survives = 0;
+        for count in 0..=MAX_COUNT {
+            let equal = bits.iter().enumerate().fold(cells, |equal, (k, bit)| {
+                if count & (1 << k) != 0 {
+                    equal & bit
+                } else {
+                    equal & !bit
+                }
+            });
+            if birth[count] {
+                born |= equal;
+            }
+            if survival[count] {
+                survives |= equal;
+            }
+        }
+
+        let live = (centre >> 1) & cells;
+        (born & !live) | (survives & live)
+    }
+
+    /// the reference, cell by cell, where the table is indexed by the 3x3 neighbourhood of
+    /// a cell as a 9 bit index, with the top left cell as the most significant bit
+    fn next_half_table(rows: [u128; 3], width: usize, table: &[bool]) -> u128 {
+        let mut half = 0;
+        for bit in 0..width {
+            let index = (((rows[0] >> bit) & 7) << 6)
+                | (((rows[1] >> bit) & 7) << 3)
+                | ((rows[2] >> bit) & 7);
+            if table[index as usize] {
+                half |= 1 << bit;
+            }
+        }
+        half
+    }
+}
+
+/// vectorised adders for rows of `u64` blocks
+mod simd;
+
+mod tests;
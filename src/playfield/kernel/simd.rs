@@ -0,0 +1,206 @@
+use super::MAX_COUNT;
+
+/// the next state of as many of the leading blocks of `next` as can be vectorised, as for
+/// `Kernel::step_row` with the adder, returning how many that is
+///
+/// AVX2 is detected at run time, and where it is missing nothing is vectorised, leaving
+/// every block to the scalar adder.
+#[cfg(target_arch = "x86_64")]
+pub fn step_row(
+    mask: usize,
+    birth: &[bool; MAX_COUNT + 1],
+    survival: &[bool; MAX_COUNT + 1],
+    rows: [&[u64]; 3],
+    next: &mut [u64],
+) -> usize {
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: the processor supports AVX2, as just detected
+        unsafe { avx2::step_row(mask, birth, survival, rows, next) }
+    } else {
+        0
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn step_row(
+    _mask: usize,
+    _birth: &[bool; MAX_COUNT + 1],
+    _survival: &[bool; MAX_COUNT + 1],
+    _rows: [&[u64]; 3],
+    _next: &mut [u64],
+) -> usize {
+    0
+}
+
+/// the adder of the scalar kernel with a `u64` block in each of four lanes, so that the
+/// widened half rows of a block, of 34 cells, each fill the lower bits of a lane
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use super::MAX_COUNT;
+    use std::arch::x86_64::*;
+
+    /// blocks per vector
+    const LANES: usize = 4;
+
+    /// width in cells of the half rows of a `u64` block
+    const WIDTH: i64 = 32;
+
+    #[target_feature(enable = "avx2")]
+    fn full_add(a: __m256i, b: __m256i, c: __m256i) -> (__m256i, __m256i) {
+        let s = _mm256_xor_si256(a, b);
+        (
+            _mm256_xor_si256(s, c),
+            _mm256_or_si256(_mm256_and_si256(a, b), _mm256_and_si256(s, c)),
+        )
+    }
+
+    #[target_feature(enable = "avx2")]
+    fn half_add(a: __m256i, b: __m256i) -> (__m256i, __m256i) {
+        (_mm256_xor_si256(a, b), _mm256_and_si256(a, b))
+    }
+
+    /// the lower or upper half rows of each block
+    #[target_feature(enable = "avx2")]
+    fn half(blocks: __m256i, upper: bool) -> __m256i {
+        if upper {
+            _mm256_srli_epi64::<32>(blocks)
+        } else {
+            _mm256_and_si256(blocks, _mm256_set1_epi64x((1 << WIDTH) - 1))
+        }
+    }
+
+    /// the half rows of each block widened by the adjacent cell of its left and right
+    /// neighbours
+    #[target_feature(enable = "avx2")]
+    fn widened(blocks: [__m256i; 3], upper: bool) -> __m256i {
+        let left = _mm256_and_si256(half(blocks[0], upper), _mm256_set1_epi64x(1));
+        let right = _mm256_srli_epi64::<31>(half(blocks[2], upper));
+        _mm256_or_si256(
+            _mm256_or_si256(
+                _mm256_slli_epi64::<33>(left),
+                _mm256_slli_epi64::<1>(half(blocks[1], upper)),
+            ),
+            right,
+        )
+    }
+
+    /// the next state of the half rows `rows[1]`, as `Kernel::next_half_adder`
+    #[target_feature(enable = "avx2")]
+    fn next_half(
+        rows: [__m256i; 3],
+        mask: usize,
+        birth: &[bool; MAX_COUNT + 1],
+        survival: &[bool; MAX_COUNT + 1],
+    ) -> __m256i {
+        let cells = _mm256_set1_epi64x((1 << WIDTH) - 1);
+        let [upper, centre, lower] = rows;
+
+        // in the order of the bits of a neighbour configuration, most significant first
+        let neighbours = [
+            _mm256_srli_epi64::<2>(upper),
+            _mm256_srli_epi64::<1>(upper),
+            upper,
+            _mm256_srli_epi64::<2>(centre),
+            centre,
+            _mm256_srli_epi64::<2>(lower),
+            _mm256_srli_epi64::<1>(lower),
+            lower,
+        ];
+        let mut n = [_mm256_setzero_si256(); MAX_COUNT];
+        for (k, neighbour) in neighbours.into_iter().enumerate() {
+            if mask & (1 << (MAX_COUNT - 1 - k)) != 0 {
+                n[k] = _mm256_and_si256(neighbour, cells);
+            }
+        }
+
+        // the count of each cell as four bit planes, from a tree of adders
+        let (s0, c0) = full_add(n[0], n[1], n[2]);
+        let (s1, c1) = full_add(n[3], n[4], n[5]);
+        let (s2, c2) = half_add(n[6], n[7]);
+        let (ones, c3) = full_add(s0, s1, s2);
+        let (t0, c4) = full_add(c0, c1, c2);
+        let (twos, c5) = half_add(t0, c3);
+        let (fours, eights) = half_add(c4, c5);
+        let bits = [ones, twos, fours, eights];
+
+        let mut born = _mm256_setzero_si256();
+        let mut survives = _mm256_setzero_si256();
+        for count in 0..=MAX_COUNT {
+            if !birth[count] && !survival[count] {
+                continue;
+            }
+            let mut equal = cells;
+            for (k, bit) in bits.iter().enumerate() {
+                equal = if count & (1 << k) != 0 {
+                    _mm256_and_si256(equal, *bit)
+                } else {
+                    _mm256_andnot_si256(*bit, equal)
+                };
+            }
+            if birth[count] {
+                born = _mm256_or_si256(born, equal);
+            }
+            if survival[count] {
+                survives = _mm256_or_si256(survives, equal);
+            }
+        }
+
+        let live = _mm256_and_si256(_mm256_srli_epi64::<1>(centre), cells);
+        _mm256_or_si256(
+            _mm256_andnot_si256(live, born),
+            _mm256_and_si256(survives, live),
+        )
+    }
+
+    /// load the blocks `row[k..k + LANES]`
+    #[target_feature(enable = "avx2")]
+    fn load(row: &[u64], k: usize) -> __m256i {
+        let blocks = &row[k..k + LANES];
+        // SAFETY: `blocks` is `LANES` blocks, and the load need not be aligned
+        unsafe { _mm256_loadu_si256(blocks.as_ptr() as *const __m256i) }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub fn step_row(
+        mask: usize,
+        birth: &[bool; MAX_COUNT + 1],
+        survival: &[bool; MAX_COUNT + 1],
+        rows: [&[u64]; 3],
+        next: &mut [u64],
+    ) -> usize {
+        let vectorised = next.len() - next.len() % LANES;
+
+        for k in (0..vectorised).step_by(LANES) {
+            // each block row, with the blocks to the left and right
+            let blocks = rows.map(|row| [load(row, k), load(row, k + 1), load(row, k + 2)]);
+            let widened_rows = [
+                widened(blocks[0], true),
+                widened(blocks[1], false),
+                widened(blocks[1], true),
+                widened(blocks[2], false),
+            ];
+
+            let lower = next_half(
+                [widened_rows[0], widened_rows[1], widened_rows[2]],
+                mask,
+                birth,
+                survival,
+            );
+            let upper = next_half(
+                [widened_rows[1], widened_rows[2], widened_rows[3]],
+                mask,
+                birth,
+                survival,
+            );
+            let result = _mm256_or_si256(lower, _mm256_slli_epi64::<32>(upper));
+
+            let next = &mut next[k..k + LANES];
+            // SAFETY: `next` is `LANES` blocks, and the store need not be aligned
+            unsafe { _mm256_storeu_si256(next.as_mut_ptr() as *mut __m256i, result) };
+        }
+
+        vectorised
+    }
+}
+
+mod tests;
@@ -0,0 +1,75 @@
+#![cfg(test)]
+use super::super::super::xorshift::XorShift;
+use super::super::Kernel;
+use super::*;
+use crate::rule::Rule;
+
+/// whether `step_row` vectorises on this processor
+#[cfg(target_arch = "x86_64")]
+fn avx2() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn avx2() -> bool {
+    false
+}
+
+#[test]
+fn test_step_row_agrees_with_scalar() {
+    // rows of random blocks
+    let mut rng = XorShift(0x2545f4914f6cdd1d);
+
+    for rule in [
+        "B3/S23",
+        "B36/S23",
+        "B2/S",
+        "B1/S012345678",
+        "B2/S34H",
+        "B13/S024V",
+    ] {
+        let rule = rule.parse::<Rule>().unwrap();
+        let adder = Kernel::new(&rule);
+        let (mask, birth, survival) = match &adder {
+            Kernel::Adder {
+                mask,
+                birth,
+                survival,
+            } => (*mask, *birth, *survival),
+            Kernel::Table(_) => panic!("{} is totalistic", rule),
+        };
+        let kernel = Kernel::table(&rule);
+
+        for len in [0, 1, 4, 7, 16, 33] {
+            let rows: [Vec<u64>; 3] =
+                std::array::from_fn(|_| (0..len + 2).map(|_| rng.next()).collect());
+            let rows = [&rows[0][..], &rows[1][..], &rows[2][..]];
+            let expected = (0..len)
+                .map(|k| {
+                    let around =
+                        std::array::from_fn(|r| std::array::from_fn(|c| rows[r][k + c] as u128));
+                    kernel.next_block(around, 32) as u64
+                })
+                .collect::<Vec<_>>();
+
+            let mut blocks = vec![0; len];
+            let vectorised = step_row(mask, &birth, &survival, rows, &mut blocks);
+            assert!(vectorised <= len);
+            if avx2() {
+                assert!(len - vectorised < 4);
+            }
+            assert_eq!(
+                blocks[..vectorised],
+                expected[..vectorised],
+                "{} {}",
+                rule,
+                len
+            );
+
+            // the whole row, with the scalar adder for what is not vectorised
+            let mut blocks = vec![0; len];
+            adder.step_row(rows, &mut blocks);
+            assert_eq!(blocks, expected, "{} {}", rule, len);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(test)]
+use super::super::xorshift::XorShift;
+use super::*;
+
+#[test]
+fn test_adders() {
+    assert_eq!(
+        full_add(0b0101_0101, 0b0011_0011, 0b0000_1111),
+        (0b0110_1001, 0b0001_0111)
+    );
+    assert_eq!(half_add(0b0101, 0b0011), (0b0110, 0b0001));
+}
+
+#[test]
+fn test_kernel_choice() {
+    assert!(matches!(Kernel::new(&Rule::conway()), Kernel::Adder { .. }));
+    assert!(matches!(
+        Kernel::new(&"B2/S34H".parse::<Rule>().unwrap()),
+        Kernel::Adder { .. }
+    ));
+    assert!(matches!(
+        Kernel::new(&"B2n3/S23-q".parse::<Rule>().unwrap()),
+        Kernel::Table(_)
+    ));
+    assert!(matches!(Kernel::table(&Rule::conway()), Kernel::Table(_)));
+}
+
+#[test]
+fn test_adder_agrees_with_table() {
+    // rows of random cells
+    let mut rng = XorShift(0x2545f4914f6cdd1d);
+    let mut next = || {
+        let state = rng.next();
+        (state as u128) << 64 | state.rotate_left(29) as u128
+    };
+
+    for rule in [
+        "B3/S23",
+        "B36/S23",
+        "B3678/S34678",
+        "B2/S",
+        "B1/S012345678",
+        "B2/S34H",
+        "B13/S024V",
+        "B1234/S01234V",
+    ] {
+        let rule = rule.parse::<Rule>().unwrap();
+        let (adder, table) = (Kernel::new(&rule), Kernel::table(&rule));
+        assert!(matches!(adder, Kernel::Adder { .. }));
+
+        for width in [8, 16, 32, 64] {
+            let widened = (1u128 << (width + 2)) - 1;
+            for _ in 0..200 {
+                let rows = [next() & widened, next() & widened, next() & widened];
+                assert_eq!(
+                    adder.next_half(rows, width),
+                    table.next_half(rows, width),
+                    "{} {} {:x?}",
+                    rule,
+                    width,
+                    rows
+                );
+            }
+        }
+    }
+}
@@ -1,5 +1,9 @@
 #![cfg(test)]
+use super::xorshift::XorShift;
 use super::*;
+use crate::rule::Rule;
+use crate::topology::Topology;
+use num::cast::AsPrimitive;
 
 #[test]
 fn test_pairwise_or_default() {
@@ -71,28 +75,6 @@ fn test_bits_to_bytes() {
     );
 }
 
-#[test]
-fn test_pack_unpack_roundtrip() {
-    use assert_hex::assert_eq_hex;
-
-    type P2 = Playfield<i32, u16>;
-    type P4 = Playfield<i32, u32>;
-
-    assert_eq_hex!(P2::pack::<u8>((0x03, 0x04)), 0x0403);
-    assert_eq_hex!(P2::unpack::<u8>(0x0605), (0x05, 0x06));
-
-    assert_eq_hex!(P2::pack::<u8>((0x03, 0x04)), 0x0403);
-    assert_eq_hex!(P2::unpack::<u8>(0x0605), (5, 6));
-    assert_eq_hex!(P2::unpack::<u8>(P2::pack::<u8>((0x01, 0x02))), (0x01, 0x02));
-
-    assert_eq_hex!(P4::pack::<u16>((0x0102, 0x0304)), 0x03040102);
-    assert_eq_hex!(P4::unpack::<u16>(0x05060708), (0x0708, 0x0506));
-    assert_eq_hex!(
-        P4::unpack::<u16>(P4::pack::<u16>((0x0102, 0x0304))),
-        (0x0102, 0x0304)
-    );
-}
-
 #[test]
 fn test_to_from_bytes_roundtrip() {
     use assert_hex::assert_eq_hex;
@@ -101,8 +83,8 @@ fn test_to_from_bytes_roundtrip() {
     let origin0 = Coordinate { x: 0, y: 0 };
 
     // roundtrip
-    let p = Playfield::<i32, u16>::from_rows::<u8>(&bytes0, origin0);
-    let (bytes1, origin1) = p.to_rows::<u8>();
+    let p = Playfield::<i32, u16>::from_rows(&bytes0, origin0);
+    let (bytes1, origin1) = p.to_rows();
 
     // always comes back as even number of rows
     let mut expected = bytes0.clone();
@@ -111,3 +93,989 @@ fn test_to_from_bytes_roundtrip() {
     assert_eq_hex!(origin1, origin0);
     assert_eq_hex!(bytes1, expected);
 }
+
+/// live cells as coordinates, sorted by row then column
+fn live_cells(p: &Playfield<i32, u16>) -> Vec<(i32, i32)> {
+    let (rows, origin) = p.to_rows();
+    let mut cells = Vec::new();
+    for (y_u, row) in rows.iter().enumerate() {
+        for (x_u, byte) in row.iter().enumerate() {
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    cells.push(((origin.x + x_u as i32) * 8 + bit, origin.y * 2 + y_u as i32));
+                }
+            }
+        }
+    }
+    cells.sort_by_key(|&(x, y)| (y, x));
+    cells
+}
+
+fn translated(cells: &[(i32, i32)], dx: i32, dy: i32) -> Vec<(i32, i32)> {
+    cells.iter().map(|&(x, y)| (x + dx, y + dy)).collect()
+}
+
+#[test]
+fn test_step_blinker() {
+    let rows: Vec<Vec<u8>> = vec![vec![0], vec![0b00111000], vec![0]];
+    let p0 = Playfield::<i32, u16>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+
+    let p1 = p0.step(&Rule::conway());
+    assert_eq!(live_cells(&p1), vec![(3, 0), (3, 1), (3, 2)]);
+
+    let p2 = p1.step(&Rule::conway());
+    assert_eq!(live_cells(&p2), live_cells(&p0));
+}
+
+#[test]
+fn test_step_still_life_across_blocks() {
+    // a block straddling all four packed blocks around the origin
+    let rows: Vec<Vec<u8>> = vec![
+        vec![0, 0],
+        vec![0b00000001, 0b10000000],
+        vec![0b00000001, 0b10000000],
+    ];
+    let p = Playfield::<i32, u16>::from_rows(&rows, Coordinate { x: -1, y: -1 });
+    let cells = vec![(-1, -1), (0, -1), (-1, 0), (0, 0)];
+    assert_eq!(live_cells(&p), cells);
+
+    assert_eq!(live_cells(&p.step(&Rule::conway())), cells);
+}
+
+#[test]
+fn test_step_glider_crossing_blocks() {
+    // glider heading up and to the left, towards negative block indices
+    let rows: Vec<Vec<u8>> = vec![vec![0b11100000], vec![0b10000000], vec![0b01000000]];
+    let mut p = Playfield::<i32, u16>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+    let cells0 = live_cells(&p);
+
+    for generation in 1..=12 {
+        p = p.step(&Rule::conway());
+        if generation % 4 == 0 {
+            assert_eq!(
+                live_cells(&p),
+                translated(&cells0, -generation / 4, -generation / 4)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_step_dies_out() {
+    let rows: Vec<Vec<u8>> = vec![vec![0b01000000], vec![0b00000010]];
+    let p = Playfield::<i32, u16>::from_rows(&rows, Coordinate { x: 3, y: -2 });
+
+    let (rows, origin) = p.step(&Rule::conway()).to_rows();
+    assert!(rows.is_empty());
+    assert_eq!(origin, Coordinate::default());
+}
+
+#[test]
+fn test_step_seeds() {
+    let rule = "B2/S".parse::<Rule>().unwrap();
+    let rows: Vec<Vec<u8>> = vec![vec![0], vec![0b00011000]];
+    let p = Playfield::<i32, u16>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+
+    assert_eq!(
+        live_cells(&p.step(&rule)),
+        vec![(3, 0), (4, 0), (3, 2), (4, 2)]
+    );
+}
+
+#[test]
+fn test_step_isotropic_non_totalistic() {
+    let rows: Vec<Vec<u8>> = vec![vec![0b00100000], vec![0], vec![0b00100000]];
+    let p = Playfield::<i32, u16>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+
+    // the cell between is born, since its neighbours are opposite edges,
+    // but not those either side, whose neighbours are corners on the same side
+    let rule = "B2i/S".parse::<Rule>().unwrap();
+    assert_eq!(live_cells(&p.step(&rule)), vec![(2, 1)]);
+
+    let rule = "B2/S".parse::<Rule>().unwrap();
+    assert_eq!(live_cells(&p.step(&rule)), vec![(1, 1), (2, 1), (3, 1)]);
+}
+
+#[test]
+fn test_from_cells_roundtrip() {
+    let mut cells = vec![
+        Coordinate { x: -9, y: -3 },
+        Coordinate { x: 0, y: 0 },
+        Coordinate { x: 7, y: 1 },
+        Coordinate { x: 8, y: 2 },
+    ];
+    let p = Playfield::<i32, u16>::from_cells(cells.clone());
+
+    assert_eq!(live_cells(&p), vec![(-9, -3), (0, 0), (7, 1), (8, 2)]);
+
+    let mut roundtrip = p.cells();
+    roundtrip.sort_by_key(|c| (c.y, c.x));
+    cells.sort_by_key(|c| (c.y, c.x));
+    assert_eq!(roundtrip, cells);
+}
+
+#[test]
+fn test_get_set_cell() {
+    let mut p = Playfield::<i32, u16>::new();
+    assert!(!p.get_cell(3, -5));
+
+    p.set_cell(3, -5, true);
+    p.set_cell(-9, 2, true);
+    assert!(p.get_cell(3, -5));
+    assert!(p.get_cell(-9, 2));
+    assert!(!p.get_cell(4, -5));
+    assert!(!p.get_cell(3, -4));
+    assert_eq!(live_cells(&p), vec![(3, -5), (-9, 2)]);
+
+    // setting twice is idempotent
+    p.set_cell(3, -5, true);
+    assert_eq!(live_cells(&p), vec![(3, -5), (-9, 2)]);
+
+    p.set_cell(3, -5, false);
+    assert!(!p.get_cell(3, -5));
+    assert_eq!(live_cells(&p), vec![(-9, 2)]);
+}
+
+#[test]
+fn test_set_cell_removes_empty_blocks() {
+    let mut p = Playfield::<i32, u16>::new();
+    p.set_cell(0, 0, true);
+    p.set_cell(1, 1, true);
+    p.set_cell(20, 0, true);
+
+    // both cells share a block, which remains until both are dead
+    p.set_cell(0, 0, false);
+    assert_eq!(
+        p.to_rows(),
+        (
+            vec![vec![0, 0, 0x08], vec![0x40, 0, 0]],
+            Coordinate { x: 0, y: 0 }
+        )
+    );
+    p.set_cell(1, 1, false);
+    assert_eq!(
+        p.to_rows(),
+        (vec![vec![0x08], vec![0]], Coordinate { x: 2, y: 0 })
+    );
+
+    // clearing a dead cell creates no block
+    p.set_cell(100, 100, false);
+    assert_eq!(
+        p.to_rows(),
+        (vec![vec![0x08], vec![0]], Coordinate { x: 2, y: 0 })
+    );
+
+    p.set_cell(20, 0, false);
+    assert!(p.cc.is_none());
+    assert_eq!(p.to_rows(), (vec![], Coordinate::default()));
+}
+
+#[test]
+fn test_toggle_cell() {
+    let mut p = Playfield::<i64, u64>::new();
+    p.toggle_cell(-1, -1);
+    p.toggle_cell(40, 3);
+    assert!(p.get_cell(-1, -1));
+    assert!(p.get_cell(40, 3));
+
+    p.toggle_cell(-1, -1);
+    assert!(!p.get_cell(-1, -1));
+    assert_eq!(p.cells(), vec![Coordinate { x: 40, y: 3 }]);
+
+    p.toggle_cell(40, 3);
+    assert!(p.cc.is_none());
+}
+
+fn sorted_cells<T: Block>(p: &Playfield<i64, T>) -> Vec<Coordinate<i64>> {
+    let mut cells = p.cells();
+    cells.sort_by_key(|c| (c.y, c.x));
+    cells
+}
+
+/// random rows from the half row type of `T`, with the cells they represent
+fn random_rows<T>(
+    rng: &mut XorShift,
+    origin: Coordinate<i64>,
+) -> (Vec<Vec<T::Half>>, Vec<Coordinate<i64>>)
+where
+    T: Block,
+    T::Half: AsPrimitive<u128>,
+    u64: AsPrimitive<T::Half>,
+{
+    let width = T::WIDTH as i64;
+    let n_rows = rng.next() % 9;
+    let mut rows = Vec::new();
+    let mut cells = Vec::new();
+
+    for y in 0..n_rows as i64 {
+        let n_items = rng.next() % 5;
+        let mut row = Vec::new();
+        for x in 0..n_items as i64 {
+            let item: T::Half = rng.sparse().as_();
+            let bits: u128 = item.as_();
+            for col in (0..width).filter(|col| bits & (1 << (width - 1 - col)) != 0) {
+                cells.push(Coordinate {
+                    x: (origin.x + x) * width + col,
+                    y: origin.y * 2 + y,
+                });
+            }
+            row.push(item);
+        }
+        rows.push(row);
+    }
+    cells.sort_by_key(|c| (c.y, c.x));
+    (rows, cells)
+}
+
+fn check_rows_roundtrip<T>(seed: u64)
+where
+    T: Block,
+    T::Half: AsPrimitive<u128>,
+    u64: AsPrimitive<T::Half>,
+{
+    let mut rng = XorShift(seed);
+    for _ in 0..200 {
+        let origin = Coordinate {
+            x: (rng.next() % 7) as i64 - 3,
+            y: (rng.next() % 7) as i64 - 3,
+        };
+        let (rows, cells) = random_rows::<T>(&mut rng, origin);
+
+        let p = Playfield::<i64, T>::from_rows(&rows, origin);
+        assert_eq!(sorted_cells(&p), cells);
+
+        // to_rows normalises, after which the roundtrip is exact
+        let (rows1, origin1) = p.to_rows();
+        let q = Playfield::<i64, T>::from_rows(&rows1, origin1);
+        assert_eq!(sorted_cells(&q), cells);
+        assert_eq!(q.to_rows(), (rows1.clone(), origin1));
+        assert_eq!(rows1.len() % 2, 0);
+    }
+}
+
+#[test]
+fn test_rows_roundtrip_all_widths() {
+    check_rows_roundtrip::<u16>(1);
+    check_rows_roundtrip::<u32>(2);
+    check_rows_roundtrip::<u64>(3);
+    check_rows_roundtrip::<u128>(4);
+}
+
+/// the same cells give the same cells back, whatever the block width
+fn cells_via<T>(cells: &[Coordinate<i64>]) -> Vec<Coordinate<i64>>
+where
+    T: Block,
+    u128: AsPrimitive<T>,
+{
+    let p = Playfield::<i64, T>::from_cells(cells.iter().copied());
+    let (rows, origin) = p.to_rows();
+    sorted_cells(&Playfield::<i64, T>::from_rows(&rows, origin))
+}
+
+#[test]
+fn test_cells_roundtrip_across_widths() {
+    let mut rng = XorShift(5);
+    for _ in 0..100 {
+        let mut cells = (0..rng.next() % 40)
+            .map(|_| Coordinate {
+                x: (rng.next() % 300) as i64 - 150,
+                y: (rng.next() % 30) as i64 - 15,
+            })
+            .collect::<Vec<_>>();
+        cells.sort_by_key(|c| (c.y, c.x));
+        cells.dedup();
+
+        assert_eq!(cells_via::<u16>(&cells), cells);
+        assert_eq!(cells_via::<u32>(&cells), cells);
+        assert_eq!(cells_via::<u64>(&cells), cells);
+        assert_eq!(cells_via::<u128>(&cells), cells);
+    }
+}
+
+/// the same generations on `topology` for the packed and the multi-state playfields
+fn check_step_with_topology<T>(topology: Topology, rng: &mut XorShift)
+where
+    T: Block,
+    u128: AsPrimitive<T>,
+{
+    use crate::generations::{GenerationsPlayfield, GenerationsRule, LIVE};
+
+    let (min, max) = topology.bounds::<i64>().unwrap();
+    let mut cells = Vec::new();
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            if rng.next().is_multiple_of(3) {
+                cells.push(Coordinate { x, y });
+            }
+        }
+    }
+
+    let rule = Rule::conway();
+    let generations_rule = GenerationsRule::new(rule.clone(), 2).unwrap();
+    let mut p = Playfield::<i64, T>::from_cells(cells.iter().copied());
+    let mut q = GenerationsPlayfield::<i64>::new();
+    for cell in cells.iter() {
+        q.set_cell(cell.x, cell.y, LIVE);
+    }
+
+    for generation in 0..12 {
+        p = p.step_with_topology(&rule, topology);
+        q = q.step_with_topology(&generations_rule, topology);
+
+        let (rows, origin) = q.to_rows();
+        let mut expected = Vec::new();
+        for (y_u, row) in rows.iter().enumerate() {
+            for (x_u, state) in row.iter().enumerate() {
+                if *state == LIVE {
+                    expected.push(Coordinate {
+                        x: origin.x + x_u as i64,
+                        y: origin.y + y_u as i64,
+                    });
+                }
+            }
+        }
+        assert_eq!(sorted_cells(&p), expected, "{} {}", topology, generation);
+        assert_eq!((p.population(), p.bounding_box()), measured(&p));
+    }
+}
+
+#[test]
+fn test_step_with_topology_agrees_with_enumeration() {
+    let mut rng = XorShift(6);
+    for topology in [
+        "P20,13", "T20,13", "K20*,13", "K20,13*", "C20,13", "T1,9", "C3,3",
+    ] {
+        let topology = topology.parse::<Topology>().unwrap();
+        for _ in 0..4 {
+            check_step_with_topology::<u16>(topology, &mut rng);
+        }
+    }
+    // edges across several blocks, of either width
+    for topology in ["T75,40", "K75*,41", "K70,41*", "C67,33"] {
+        let topology = topology.parse::<Topology>().unwrap();
+        check_step_with_topology::<u16>(topology, &mut rng);
+        check_step_with_topology::<u64>(topology, &mut rng);
+    }
+}
+
+#[test]
+fn test_step_glider_on_torus() {
+    // a glider returns to where it started after crossing every edge
+    let rows: Vec<Vec<u8>> = vec![vec![0b11100000], vec![0b10000000], vec![0b01000000]];
+    let topology = Topology::Torus {
+        width: 10,
+        height: 6,
+    };
+    let mut p = Playfield::<i32, u16>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+    let cells0 = live_cells(&p);
+
+    // both 10 and 6 divide 30 cells of diagonal movement
+    for _ in 0..30 * 4 {
+        p = p.step_with_topology(&Rule::conway(), topology);
+    }
+    assert_eq!(live_cells(&p), cells0);
+}
+
+#[test]
+fn test_step_glider_on_bounded_plane() {
+    // a glider becomes a block in the corner
+    let rows: Vec<Vec<u8>> = vec![vec![0b11100000], vec![0b10000000], vec![0b01000000]];
+    let topology = Topology::BoundedPlane {
+        width: 8,
+        height: 8,
+    };
+    let mut p = Playfield::<i32, u16>::from_rows(&rows, Coordinate { x: 0, y: 0 });
+    for _ in 0..40 {
+        p = p.step_with_topology(&Rule::conway(), topology);
+    }
+    assert_eq!(live_cells(&p), vec![(-4, -4), (-3, -4), (-4, -3), (-3, -3)]);
+}
+
+/// step under totalistic `birth` and `survival` counts of the neighbours at `offsets`,
+/// both by the playfield with `rule` and by counting
+fn check_step_masked(
+    rule: &Rule,
+    offsets: &[(isize, isize)],
+    birth: &[usize],
+    survival: &[usize],
+    seed: u64,
+) {
+    let mut rng = XorShift(seed);
+    let mut cells = Vec::new();
+    for y in -6..6 {
+        for x in -20..20 {
+            if rng.next().is_multiple_of(3) {
+                cells.push(Coordinate { x, y });
+            }
+        }
+    }
+    let mut p = Playfield::<i64, u16>::from_cells(cells.iter().copied());
+
+    for _ in 0..6 {
+        // every cell within reach of a live cell
+        let live = sorted_cells(&p);
+        let neighbours = |c: &Coordinate<i64>| {
+            offsets
+                .iter()
+                .map(|(dx, dy)| Coordinate {
+                    x: c.x + *dx as i64,
+                    y: c.y + *dy as i64,
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut expected = live
+            .iter()
+            .flat_map(|c| neighbours(c).into_iter().chain([*c]))
+            .filter(|c| {
+                let count = neighbours(c).iter().filter(|n| live.contains(n)).count();
+                if live.contains(c) {
+                    survival.contains(&count)
+                } else {
+                    birth.contains(&count)
+                }
+            })
+            .collect::<Vec<_>>();
+        expected.sort_by_key(|c| (c.y, c.x));
+        expected.dedup();
+
+        p = p.step(rule);
+        assert_eq!(sorted_cells(&p), expected, "{}", rule);
+    }
+}
+
+#[test]
+fn test_step_hexagonal() {
+    use crate::grid::Grid;
+
+    let rule = "B2/S34H".parse::<Rule>().unwrap();
+    check_step_masked(&rule, &Grid::Hexagonal.offsets(true), &[2], &[3, 4], 7);
+}
+
+#[test]
+fn test_step_von_neumann() {
+    let von_neumann = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+    let rule = "B13/S024V".parse::<Rule>().unwrap();
+    check_step_masked(&rule, &von_neumann, &[1, 3], &[0, 2, 4], 8);
+    let rule = "B2/S23V".parse::<Rule>().unwrap();
+    check_step_masked(&rule, &von_neumann, &[2], &[2, 3], 9);
+}
+
+#[test]
+fn test_step_by_rows_agrees_with_blocks() {
+    fn check<T>(rule: &Rule, seed: u64)
+    where
+        T: Block,
+        u128: AsPrimitive<T>,
+    {
+        let mut rng = XorShift(seed);
+        let mut cells = Vec::new();
+        // a dense soup, and a sparse scattering of cells to either side of it
+        for y in -40..40 {
+            for x in -70..70 {
+                let one_in = if (-20..20).contains(&x) { 2 } else { 40 };
+                if rng.next().is_multiple_of(one_in) {
+                    cells.push(Coordinate { x, y });
+                }
+            }
+        }
+        let mut p = Playfield::<i64, T>::from_cells(cells);
+        let kernel = Kernel::new(rule);
+        for generation in 0..8 {
+            let next = p.step_with_kernel(&kernel);
+            assert_eq!(
+                sorted_cells(&next),
+                sorted_cells(&p.step_by_blocks(&kernel)),
+                "{} {}",
+                rule,
+                generation
+            );
+            p = next;
+        }
+    }
+
+    for (seed, rule) in ["B3/S23", "B36/S23", "B2/S34H", "B13/S024V", "B2n3/S23-q"]
+        .iter()
+        .enumerate()
+    {
+        let rule = rule.parse::<Rule>().unwrap();
+        check::<u16>(&rule, seed as u64 + 11);
+        check::<u64>(&rule, seed as u64 + 11);
+    }
+}
+
+#[test]
+fn test_step_tracks_changes() {
+    // a block, a blinker and a glider, each in blocks of its own
+    let cells = [
+        (-40, -40),
+        (-39, -40),
+        (-40, -39),
+        (-39, -39),
+        (40, 10),
+        (41, 10),
+        (42, 10),
+        (1, 0),
+        (2, 1),
+        (0, 2),
+        (1, 2),
+        (2, 2),
+    ];
+    let mut p = Playfield::<i64, u16>::from_cells(cells.iter().map(|&(x, y)| Coordinate { x, y }));
+    let kernel = Kernel::new(&Rule::conway());
+    for generation in 0..40 {
+        let next = p.step_with_kernel(&kernel);
+        assert_eq!(
+            sorted_cells(&next),
+            sorted_cells(&p.step_by_blocks(&kernel)),
+            "{}",
+            generation
+        );
+        p = next;
+
+        // only the blinker and the glider change, and the block is never evaluated again
+        let changes = p.changes.as_ref().unwrap();
+        assert!(changes
+            .rows
+            .iter()
+            .all(|(y, runs)| runs.iter().all(|run| !(*y < -15 && run.start < -3))));
+    }
+
+    // an edit forgets the changes, and a different rule ignores them
+    p.set_cell(-38, -40, true);
+    assert!(p.changes.is_none());
+    let next = p.step_with_kernel(&kernel);
+    assert_eq!(
+        sorted_cells(&next),
+        sorted_cells(&p.step_by_blocks(&kernel))
+    );
+    let highlife = Kernel::new(&"B36/S23".parse::<Rule>().unwrap());
+    assert_eq!(
+        sorted_cells(&next.step_with_kernel(&highlife)),
+        sorted_cells(&next.step_by_blocks(&highlife))
+    );
+}
+
+/// the population and bounding box from the cells
+fn measured<T>(p: &Playfield<i64, T>) -> (usize, Option<Bounds<i64>>)
+where
+    T: Block,
+{
+    let cells = p.cells();
+    let bounds = cells.first().map(|first| {
+        cells.iter().fold((*first, *first), |(min, max), cell| {
+            (
+                Coordinate {
+                    x: min.x.min(cell.x),
+                    y: min.y.min(cell.y),
+                },
+                Coordinate {
+                    x: max.x.max(cell.x),
+                    y: max.y.max(cell.y),
+                },
+            )
+        })
+    });
+    (cells.len(), bounds)
+}
+
+#[test]
+fn test_advance_agrees_with_step() {
+    let mut rng = XorShift(14);
+    let mut cells = Vec::new();
+    // all on the torus
+    for y in -15..15 {
+        for x in -15..15 {
+            if rng.next().is_multiple_of(3) {
+                cells.push(Coordinate { x, y });
+            }
+        }
+    }
+
+    let rule = Rule::conway();
+    let torus = "T30,30".parse::<Topology>().unwrap();
+    for topology in [Topology::Plane, torus] {
+        let mut p = Playfield::<i64, u32>::from_cells(cells.iter().copied());
+        let mut q = p.clone();
+        for generation in 0..30 {
+            p = p.step_with_topology(&rule, topology);
+            q.advance_with_topology(&rule, topology);
+            assert_eq!(sorted_cells(&p), sorted_cells(&q), "{}", generation);
+            assert_eq!(
+                (q.population(), q.bounding_box()),
+                (p.population(), p.bounding_box())
+            );
+        }
+    }
+}
+
+#[test]
+fn test_population_and_bounding_box() {
+    let mut p = Playfield::<i64, u16>::new();
+    assert_eq!((p.population(), p.bounding_box()), (0, None));
+
+    p.set_cell(-3, 5, true);
+    p.set_cell(4, -2, true);
+    p.set_cell(0, 0, true);
+    p.set_cell(0, 0, true);
+    assert_eq!(p.population(), 3);
+    assert_eq!(
+        p.bounding_box(),
+        Some((Coordinate { x: -3, y: -2 }, Coordinate { x: 4, y: 5 }))
+    );
+
+    // removing a cell within the box leaves it, and one on its edge shrinks it
+    p.set_cell(0, 0, false);
+    assert_eq!(p.population(), 2);
+    p.set_cell(4, -2, false);
+    assert_eq!(
+        (p.population(), p.bounding_box()),
+        (
+            1,
+            Some((Coordinate { x: -3, y: 5 }, Coordinate { x: -3, y: 5 }))
+        )
+    );
+    p.set_cell(-3, 5, false);
+    assert_eq!((p.population(), p.bounding_box()), (0, None));
+
+    let rows = vec![vec![0x81u8], vec![], vec![0x10]];
+    let p = Playfield::<i64, u16>::from_rows(&rows, Coordinate { x: -1, y: 0 });
+    assert_eq!(p.population(), 3);
+    assert_eq!(
+        p.bounding_box(),
+        Some((Coordinate { x: -8, y: 0 }, Coordinate { x: -1, y: 2 }))
+    );
+}
+
+#[test]
+fn test_step_updates_population_and_bounding_box() {
+    fn check<T>(seed: u64)
+    where
+        T: Block,
+        u128: AsPrimitive<T>,
+    {
+        let mut rng = XorShift(seed);
+        let mut cells = Vec::new();
+        for y in -30..30 {
+            for x in -30..30 {
+                if rng.next().is_multiple_of(3) {
+                    cells.push(Coordinate { x, y });
+                }
+            }
+        }
+        let mut p = Playfield::<i64, T>::from_cells(cells);
+        assert_eq!((p.population(), p.bounding_box()), measured(&p));
+        for rule in ["B3/S23", "B36/S23", "B2/S", "B3/S012345678"] {
+            let rule = rule.parse::<Rule>().unwrap();
+            for generation in 0..10 {
+                p = p.step(&rule);
+                assert_eq!(
+                    (p.population(), p.bounding_box()),
+                    measured(&p),
+                    "{} {}",
+                    rule,
+                    generation
+                );
+            }
+            let reference = p.step_by_blocks(&Kernel::new(&rule));
+            assert_eq!(
+                (reference.population(), reference.bounding_box()),
+                measured(&reference)
+            );
+        }
+
+        // a pattern which dies out
+        let mut p = Playfield::<i64, T>::from_cells([Coordinate { x: 1, y: 1 }]);
+        p = p.step(&Rule::conway());
+        assert_eq!((p.population(), p.bounding_box()), (0, None));
+    }
+
+    check::<u16>(21);
+    check::<u64>(22);
+}
+
+#[test]
+#[allow(clippy::single_range_in_vec_init)]
+fn test_around() {
+    type P = Playfield<i64, u64>;
+    assert_eq!(P::around(&[]), vec![]);
+    assert_eq!(
+        P::around(&[(0, vec![0..1, 5..6]), (1, vec![3..4]), (4, vec![0..2])]),
+        vec![
+            (-1, vec![-1..2, 4..7]),
+            (0, vec![-1..7]),
+            (1, vec![-1..7]),
+            (2, vec![2..5]),
+            (3, vec![-1..3]),
+            (4, vec![-1..3]),
+            (5, vec![-1..3]),
+        ]
+    );
+}
+
+#[test]
+fn test_bands() {
+    let mut p = Playfield::<i64, u64>::new();
+    p.set_threads(Some(4));
+    assert_eq!(p.bands(0), 1);
+    assert_eq!(p.bands(MIN_BAND_ROWS * 2 - 1), 1);
+    assert_eq!(p.bands(MIN_BAND_ROWS * 3), 3);
+    assert_eq!(p.bands(MIN_BAND_ROWS * 100), 4);
+    p.set_threads(Some(1));
+    assert_eq!(p.bands(MIN_BAND_ROWS * 100), 1);
+    p.set_threads(None);
+    assert!(p.bands(MIN_BAND_ROWS * 100) >= 1);
+}
+
+#[test]
+fn test_step_threads_agree() {
+    let mut rng = XorShift(12);
+    let mut cells = Vec::new();
+    // enough block rows for several bands, with gaps which bands may start or end in
+    for y in -150..150 {
+        if (20..30).contains(&y) || (80..83).contains(&y) {
+            continue;
+        }
+        for x in -50..50 {
+            if rng.next().is_multiple_of(3) {
+                cells.push(Coordinate { x, y });
+            }
+        }
+    }
+
+    for rule in ["B3/S23", "B2n3/S23-q"] {
+        let rule = rule.parse::<Rule>().unwrap();
+        let mut single = Playfield::<i64, u64>::from_cells(cells.iter().copied());
+        single.set_threads(Some(1));
+        let mut expected = Vec::new();
+        for _ in 0..4 {
+            single = single.step(&rule);
+            expected.push(sorted_cells(&single));
+        }
+
+        for threads in [2, 3, 7, 64] {
+            let mut p = Playfield::<i64, u64>::from_cells(cells.iter().copied());
+            p.set_threads(Some(threads));
+            for (generation, expected) in expected.iter().enumerate() {
+                p = p.step(&rule);
+                assert_eq!(
+                    sorted_cells(&p),
+                    *expected,
+                    "{} {} {}",
+                    rule,
+                    threads,
+                    generation
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_compaction() {
+    let mut rng = XorShift(13);
+    let mut cells = Vec::new();
+    for y in -40..40 {
+        for x in -200..200 {
+            if rng.next().is_multiple_of(7) {
+                cells.push(Coordinate { x, y });
+            }
+        }
+    }
+
+    let rule = Rule::conway();
+    let mut expected = Playfield::<i64, u16>::from_cells(cells.iter().copied());
+    for policy in [
+        CompactionPolicy::memory(),
+        CompactionPolicy::iteration(),
+        CompactionPolicy {
+            max_zero_run: 3,
+            max_gap: 2,
+            update_threshold: 5,
+        },
+    ] {
+        let mut p = Playfield::<i64, u16>::from_cells(cells.iter().copied());
+        p.set_compaction(Some(policy));
+        let mut q = expected.clone();
+        for generation in 0..20 {
+            p = p.step(&rule);
+            q = q.step(&rule);
+            assert_eq!(
+                sorted_cells(&p),
+                sorted_cells(&q),
+                "{:?} {}",
+                policy,
+                generation
+            );
+            assert_eq!(
+                (p.population(), p.bounding_box()),
+                measured(&p),
+                "{:?} {}",
+                policy,
+                generation
+            );
+        }
+        expected = Playfield::<i64, u16>::from_cells(cells.iter().copied());
+    }
+
+    // compaction keeps zero blocks, so gaps between blocks are filled
+    let blocks = |p: &Playfield<i64, u16>| {
+        p.cc.as_ref().map_or(0, |cc| {
+            cc.rows_enumerator()
+                .map(|(_, row)| row.enumerator().count())
+                .sum::<usize>()
+        })
+    };
+    let mut p = Playfield::<i64, u16>::new();
+    p.set_compaction(Some(CompactionPolicy {
+        update_threshold: 1,
+        ..CompactionPolicy::iteration()
+    }));
+    p.set_cell(0, 0, true);
+    p.set_cell(40, 0, true);
+    assert_eq!(blocks(&p), 6);
+    assert_eq!(p.bounding_box(), measured(&p).1);
+
+    // and a playfield compacted to nothing is empty
+    p.set_cell(0, 0, false);
+    p.set_cell(40, 0, false);
+    assert!(p.cc.is_none());
+    assert_eq!((p.population(), p.bounding_box()), (0, None));
+}
+
+/// time the kernels, stepping block by block and by rows, where the adder by rows is
+/// vectorised for `u64` blocks, with
+/// `cargo test --release bench_step_kernels -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_step_kernels() {
+    use std::time::Instant;
+
+    let mut rng = XorShift(10);
+    let mut cells = Vec::new();
+    for y in 0..512 {
+        for x in 0..512 {
+            if rng.next().is_multiple_of(3) {
+                cells.push(Coordinate { x, y });
+            }
+        }
+    }
+    let soup = Playfield::<i64, u64>::from_cells(cells);
+    let generations = 20;
+
+    let mut results = Vec::new();
+    for (name, kernel, by_rows) in [
+        ("table by blocks", Kernel::table(&Rule::conway()), false),
+        ("adder by blocks", Kernel::new(&Rule::conway()), false),
+        ("table by rows", Kernel::table(&Rule::conway()), true),
+        ("adder by rows", Kernel::new(&Rule::conway()), true),
+    ] {
+        let start = Instant::now();
+        let mut p = soup.clone();
+        for _ in 0..generations {
+            p = if by_rows {
+                p.step_with_kernel(&kernel)
+            } else {
+                p.step_by_blocks(&kernel)
+            };
+        }
+        println!(
+            "{}: {:?} for {} generations",
+            name,
+            start.elapsed(),
+            generations
+        );
+        results.push(sorted_cells(&p));
+    }
+    assert!(results.iter().all(|cells| *cells == results[0]));
+}
+
+/// time stepping a large soup with increasing numbers of threads, with
+/// `cargo test --release bench_step_threads -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_step_threads() {
+    use std::time::Instant;
+
+    let mut rng = XorShift(10);
+    let mut cells = Vec::new();
+    for y in 0..2048 {
+        for x in 0..2048 {
+            if rng.next().is_multiple_of(3) {
+                cells.push(Coordinate { x, y });
+            }
+        }
+    }
+    let soup = Playfield::<i64, u64>::from_cells(cells);
+    let generations = 10;
+
+    let mut results = Vec::new();
+    for threads in [1, 2, 4, 8] {
+        let start = Instant::now();
+        let mut p = soup.clone();
+        p.set_threads(Some(threads));
+        for _ in 0..generations {
+            p = p.step(&Rule::conway());
+        }
+        println!(
+            "{} threads: {:?} for {} generations",
+            threads,
+            start.elapsed(),
+            generations
+        );
+        results.push(sorted_cells(&p));
+    }
+    assert!(results.iter().all(|cells| *cells == results[0]));
+}
+
+/// time stepping a field of still lifes with a few oscillators, with and without the
+/// changes of the last step, and copying each generation or advancing in place, with
+/// `cargo test --release bench_step_active -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_step_active() {
+    use std::time::Instant;
+
+    // a lattice of beehives, which are still, with a hundred blinkers, so that copying
+    // every block outweighs stepping the few which change
+    let mut cells = Vec::new();
+    for j in 0..300 {
+        for i in 0..300 {
+            let (x, y) = (i * 8, j * 8);
+            if (i + j * 300) % 900 == 0 {
+                cells.extend([(x, y + 1), (x + 1, y + 1), (x + 2, y + 1)]);
+            } else {
+                cells.extend([(x + 1, y), (x + 2, y), (x, y + 1), (x + 3, y + 1)]);
+                cells.extend([(x + 1, y + 2), (x + 2, y + 2)]);
+            }
+        }
+    }
+    let soup =
+        Playfield::<i64, u64>::from_cells(cells.into_iter().map(|(x, y)| Coordinate { x, y }));
+    let generations = 100;
+
+    let mut results = Vec::new();
+    for (tracked, in_place) in [(false, false), (true, false), (true, true)] {
+        let start = Instant::now();
+        let mut p = soup.clone();
+        for _ in 0..generations {
+            if !tracked {
+                p.changes = None;
+            }
+            if in_place {
+                p.advance(&Rule::conway());
+            } else {
+                p = p.step(&Rule::conway());
+            }
+        }
+        println!(
+            "tracked {}, in place {}: {:?} for {} generations",
+            tracked,
+            in_place,
+            start.elapsed(),
+            generations
+        );
+        results.push(sorted_cells(&p));
+    }
+    assert_eq!(results[0], results[1]);
+    assert_eq!(results[0], results[2]);
+}
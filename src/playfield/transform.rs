@@ -0,0 +1,277 @@
+use super::{Block, Playfield};
+use crate::cartesian::Coordinate;
+use num::cast::AsPrimitive;
+use num::FromPrimitive;
+use num::Integer;
+use num::One;
+use num::Zero;
+use std::cmp::PartialOrd;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Sub;
+use std::ops::SubAssign;
+
+/// a clockwise rotation by a multiple of 90 degrees
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rotation {
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+/// rows of blocks as `u128`, each with its blocks in order, and the rows in order
+type BlockRows<Idx> = Vec<(Idx, Vec<(Idx, u128)>)>;
+
+/// append `block` at `x` to the end of `row`, combining it with the last block if at `x`
+fn push_or<Idx>(row: &mut Vec<(Idx, u128)>, x: Idx, block: u128)
+where
+    Idx: Copy + PartialEq,
+{
+    match row.last_mut() {
+        Some((last_x, last)) if *last_x == x => *last |= block,
+        _ => row.push((x, block)),
+    }
+}
+
+/// transpose the square bit matrix of `width` rows, each of `width` bits with the most
+/// significant the first column, by swapping ever smaller blocks across the diagonal
+fn transpose_square(rows: &mut [u128], width: usize) {
+    let full = (1u128 << width) - 1;
+    let mut j = width / 2;
+    let mut mask = (1u128 << j) - 1;
+    while j != 0 {
+        let mut k = 0;
+        while k < width {
+            let t = (rows[k] ^ (rows[k + j] >> j)) & mask;
+            rows[k] ^= t;
+            rows[k + j] ^= t << j;
+            k = (k + j + 1) & !j;
+        }
+        j /= 2;
+        mask = (mask ^ (mask << j)) & full;
+    }
+}
+
+/// the blocks of two rows together, in order, combining any at the same place
+fn merge_rows<Idx>(a: Vec<(Idx, u128)>, b: Vec<(Idx, u128)>) -> Vec<(Idx, u128)>
+where
+    Idx: Copy + Ord,
+{
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+    loop {
+        let next = match (a.peek(), b.peek()) {
+            (Some((xa, _)), Some((xb, _))) if xa <= xb => a.next(),
+            (_, Some(_)) => b.next(),
+            (Some(_), None) => a.next(),
+            (None, None) => break,
+        };
+        if let Some((x, block)) = next {
+            push_or(&mut merged, x, block);
+        }
+    }
+    merged
+}
+
+impl<Idx, T> Playfield<Idx, T>
+where
+    T: Block,
+    u128: AsPrimitive<T>,
+    Idx: Copy
+        + Default
+        + One
+        + FromPrimitive
+        + AsPrimitive<usize>
+        + Add<Output = Idx>
+        + Sub<Output = Idx>
+        + PartialOrd
+        + AddAssign
+        + SubAssign
+        + Zero
+        + Ord
+        + Integer
+        + Debug,
+{
+    /// move every cell by `offset`
+    ///
+    /// Blocks move whole by whole blocks, and otherwise each half row is shifted with the
+    /// cells it loses carried into its neighbour, and a shift by an odd number of rows
+    /// moves the half rows between blocks.
+    pub fn translate(&self, offset: Coordinate<Idx>) -> Playfield<Idx, T> {
+        let width = Self::block_width();
+        let (blocks_x, shift) = offset.x.div_mod_floor(&Idx::from_usize(width).unwrap());
+        let (blocks_y, odd) = offset.y.div_mod_floor(&Idx::from_usize(2).unwrap());
+        let (shift, odd) = (shift.as_(), odd != Idx::zero());
+
+        let mut rows = self.block_rows();
+        if shift != 0 {
+            for (_, row) in rows.iter_mut() {
+                let mut shifted = Vec::with_capacity(row.len() + 1);
+                for (x, block) in row.iter() {
+                    push_or(&mut shifted, *x, Self::map_halves(*block, |h| h >> shift));
+                    let carried = Self::map_halves(*block, |h| h << (width - shift));
+                    shifted.push((*x + Idx::one(), carried));
+                }
+                *row = shifted;
+            }
+        }
+        if odd {
+            rows = Self::shift_half_rows(rows);
+        }
+
+        self.with_block_rows(
+            rows.into_iter()
+                .map(|(y, row)| {
+                    let row = row.into_iter().map(|(x, block)| (x + blocks_x, block));
+                    (y + blocks_y, row.collect())
+                })
+                .collect(),
+        )
+    }
+
+    /// mirror left to right, so that the cell at `x` moves to `-1 - x`
+    pub fn flip_horizontal(&self) -> Playfield<Idx, T> {
+        let width = Self::block_width();
+        let rows = self
+            .block_rows()
+            .into_iter()
+            .map(|(y, row)| {
+                let row = row.into_iter().rev().map(|(x, block)| {
+                    let reversed = Self::map_halves(block, |h| h.reverse_bits() >> (128 - width));
+                    (Idx::zero() - Idx::one() - x, reversed)
+                });
+                (y, row.collect())
+            })
+            .collect();
+        self.with_block_rows(rows)
+    }
+
+    /// mirror top to bottom, so that the cell at `y` moves to `-1 - y`
+    pub fn flip_vertical(&self) -> Playfield<Idx, T> {
+        let width = Self::block_width();
+        let mask = (1u128 << width) - 1;
+        let rows = self
+            .block_rows()
+            .into_iter()
+            .rev()
+            .map(|(y, row)| {
+                let row = row
+                    .into_iter()
+                    .map(|(x, block)| (x, (block >> width) | ((block & mask) << width)));
+                (Idx::zero() - Idx::one() - y, row.collect())
+            })
+            .collect();
+        self.with_block_rows(rows)
+    }
+
+    /// swap rows and columns, so that the cell at `x`, `y` moves to `y`, `x`
+    ///
+    /// Each column of blocks is cut into squares of `WIDTH` half rows, whose bit matrices
+    /// are transposed into as many half rows of a single block column.
+    pub fn transpose(&self) -> Playfield<Idx, T> {
+        let width = Self::block_width();
+        let tile_rows = Idx::from_usize(width / 2).unwrap();
+        let mask = (1u128 << width) - 1;
+
+        // the squares by column then row, which after transposing are by row then column
+        let mut tiles: BTreeMap<(Idx, Idx), Vec<u128>> = BTreeMap::new();
+        for (y, row) in self.block_rows() {
+            let (tile_y, offset) = y.div_mod_floor(&tile_rows);
+            let offset = offset.as_() * 2;
+            for (x, block) in row {
+                let tile = tiles.entry((x, tile_y)).or_insert_with(|| vec![0; width]);
+                tile[offset] = block & mask;
+                tile[offset + 1] = block >> width;
+            }
+        }
+
+        let mut rows: BlockRows<Idx> = Vec::new();
+        let mut tiles = tiles.into_iter().peekable();
+        while let Some(((tile_x, _), _)) = tiles.peek() {
+            let tile_x = *tile_x;
+            let mut column = Vec::new();
+            while let Some(((_, tile_y), mut tile)) = tiles.next_if(|((x, _), _)| *x == tile_x) {
+                transpose_square(&mut tile, width);
+                column.push((tile_y, tile));
+            }
+            // the tiles of the block column side by side, in order of the original rows
+            for u in 0..width / 2 {
+                let row = column
+                    .iter()
+                    .map(|(tile_y, tile)| (*tile_y, tile[2 * u] | (tile[2 * u + 1] << width)))
+                    .collect();
+                rows.push((tile_x * tile_rows + Idx::from_usize(u).unwrap(), row));
+            }
+        }
+        self.with_block_rows(rows)
+    }
+
+    /// rotate clockwise about the top left corner of the cell at the origin
+    pub fn rotate(&self, rotation: Rotation) -> Playfield<Idx, T> {
+        match rotation {
+            Rotation::Quarter => self.transpose().flip_horizontal(),
+            Rotation::Half => self.flip_horizontal().flip_vertical(),
+            Rotation::ThreeQuarters => self.transpose().flip_vertical(),
+        }
+    }
+
+    /// apply `f` to each half row of `block`, keeping the cells within the half row
+    fn map_halves<F>(block: u128, f: F) -> u128
+    where
+        F: Fn(u128) -> u128,
+    {
+        let width = Self::block_width();
+        let mask = (1u128 << width) - 1;
+        (f(block & mask) & mask) | ((f(block >> width) & mask) << width)
+    }
+
+    /// move every half row down by one, into the other half of its block or the block below
+    fn shift_half_rows(rows: BlockRows<Idx>) -> BlockRows<Idx> {
+        let width = Self::block_width();
+        let mask = (1u128 << width) - 1;
+        let mut shifted: BlockRows<Idx> = Vec::new();
+        for (y, row) in rows {
+            let lower = row
+                .iter()
+                .map(|(x, block)| (*x, (block & mask) << width))
+                .collect();
+            let upper = row.iter().map(|(x, block)| (*x, block >> width)).collect();
+            match shifted.last_mut() {
+                // the upper halves of the row above
+                Some((last_y, last)) if *last_y == y => {
+                    *last = merge_rows(std::mem::take(last), lower)
+                }
+                _ => shifted.push((y, lower)),
+            }
+            shifted.push((y + Idx::one(), upper));
+        }
+        shifted
+    }
+
+    /// the blocks of each row as `u128`
+    fn block_rows(&self) -> BlockRows<Idx> {
+        self.cc.as_ref().map_or_else(Vec::new, |cc| {
+            cc.rows_enumerator()
+                .map(|(y, row)| (y, row.enumerator().map(|(x, b)| (x, b.as_())).collect()))
+                .collect()
+        })
+    }
+
+    /// a playfield of rows of blocks, skipping any which are empty, with the same settings
+    fn with_block_rows(&self, rows: BlockRows<Idx>) -> Playfield<Idx, T> {
+        let mut playfield = self.empty_like();
+        for (y, row) in rows {
+            for (x, block) in row {
+                if block != 0 {
+                    playfield.set_block(x, y, block.as_());
+                }
+            }
+        }
+        playfield.recount();
+        playfield
+    }
+}
+
+mod tests;
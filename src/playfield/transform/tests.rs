@@ -0,0 +1,190 @@
+#![cfg(test)]
+use super::super::xorshift::XorShift;
+use super::*;
+
+/// random cells, clustered about the origin with a few far from it
+fn random_cells(seed: u64) -> Vec<Coordinate<i64>> {
+    let mut rng = XorShift(seed);
+    let mut cells = Vec::new();
+    for y in -20..20 {
+        for x in -40..40 {
+            if rng.next().is_multiple_of(4) {
+                cells.push(Coordinate { x, y });
+            }
+        }
+    }
+    for _ in 0..10 {
+        let (x, y) = (
+            (rng.next() % 1000) as i64 - 500,
+            (rng.next() % 1000) as i64 - 500,
+        );
+        cells.push(Coordinate { x, y });
+    }
+    cells
+}
+
+fn sorted(mut cells: Vec<Coordinate<i64>>) -> Vec<Coordinate<i64>> {
+    cells.sort_by_key(|c| (c.y, c.x));
+    cells.dedup();
+    cells
+}
+
+/// a transform, for applying to playfields of any block type
+#[derive(Clone, Copy)]
+enum Transform {
+    Translate(Coordinate<i64>),
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    Rotate(Rotation),
+}
+
+fn apply<T>(p: &Playfield<i64, T>, transform: Transform) -> Playfield<i64, T>
+where
+    T: Block,
+    u128: AsPrimitive<T>,
+{
+    match transform {
+        Transform::Translate(offset) => p.translate(offset),
+        Transform::FlipHorizontal => p.flip_horizontal(),
+        Transform::FlipVertical => p.flip_vertical(),
+        Transform::Transpose => p.transpose(),
+        Transform::Rotate(rotation) => p.rotate(rotation),
+    }
+}
+
+/// check that `transform` moves each cell as `f` does, for each block type
+fn check<F>(transform: Transform, f: F)
+where
+    F: Fn(Coordinate<i64>) -> Coordinate<i64>,
+{
+    fn check_block<T, F>(cells: &[Coordinate<i64>], transform: Transform, f: F)
+    where
+        T: Block,
+        u128: AsPrimitive<T>,
+        F: Fn(Coordinate<i64>) -> Coordinate<i64>,
+    {
+        let p = Playfield::<i64, T>::from_cells(cells.iter().copied());
+        let q = apply(&p, transform);
+        assert_eq!(
+            sorted(q.cells()),
+            sorted(cells.iter().map(|c| f(*c)).collect())
+        );
+        assert_eq!(q.population(), p.population());
+    }
+
+    for seed in [1, 2, 3] {
+        let cells = random_cells(seed);
+        check_block::<u16, _>(&cells, transform, &f);
+        check_block::<u32, _>(&cells, transform, &f);
+        check_block::<u64, _>(&cells, transform, &f);
+        check_block::<u128, _>(&cells, transform, &f);
+    }
+}
+
+#[test]
+fn test_transpose_square() {
+    for width in [8, 16, 32, 64] {
+        let mut rng = XorShift(0x2545f4914f6cdd1d);
+        let rows = (0..width)
+            .map(|_| rng.next() as u128 & ((1 << width) - 1))
+            .collect::<Vec<_>>();
+        let mut transposed = rows.clone();
+        transpose_square(&mut transposed, width);
+        for (r, row) in rows.iter().enumerate() {
+            for (c, column) in transposed.iter().enumerate() {
+                let bit = |word: u128, k: usize| (word >> (width - 1 - k)) & 1;
+                assert_eq!(bit(*row, c), bit(*column, r), "{} {} {}", width, r, c);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_translate() {
+    for (dx, dy) in [
+        (0, 0),
+        (3, 0),
+        (0, 1),
+        (-5, -3),
+        (16, 2),
+        (64, -7),
+        (-130, 9),
+    ] {
+        let offset = Coordinate { x: dx, y: dy };
+        check(Transform::Translate(offset), |c| Coordinate {
+            x: c.x + dx,
+            y: c.y + dy,
+        });
+    }
+}
+
+#[test]
+fn test_flips_and_transpose() {
+    check(Transform::FlipHorizontal, |c| Coordinate {
+        x: -1 - c.x,
+        y: c.y,
+    });
+    check(Transform::FlipVertical, |c| Coordinate {
+        x: c.x,
+        y: -1 - c.y,
+    });
+    check(Transform::Transpose, |c| Coordinate { x: c.y, y: c.x });
+}
+
+#[test]
+fn test_rotate() {
+    check(Transform::Rotate(Rotation::Quarter), |c| Coordinate {
+        x: -1 - c.y,
+        y: c.x,
+    });
+    check(Transform::Rotate(Rotation::Half), |c| Coordinate {
+        x: -1 - c.x,
+        y: -1 - c.y,
+    });
+    check(Transform::Rotate(Rotation::ThreeQuarters), |c| Coordinate {
+        x: c.y,
+        y: -1 - c.x,
+    });
+}
+
+#[test]
+fn test_transforms_compose() {
+    let p = Playfield::<i64, u32>::from_cells(random_cells(4));
+    let cells = sorted(p.cells());
+
+    let quarter = |p: &Playfield<i64, u32>| p.rotate(Rotation::Quarter);
+    assert_eq!(
+        sorted(quarter(&quarter(&quarter(&quarter(&p)))).cells()),
+        cells
+    );
+    assert_eq!(
+        sorted(quarter(&p).rotate(Rotation::ThreeQuarters).cells()),
+        cells
+    );
+    assert_eq!(sorted(p.transpose().transpose().cells()), cells);
+    assert_eq!(
+        sorted(
+            p.translate(Coordinate { x: 7, y: -3 })
+                .translate(Coordinate { x: -7, y: 3 })
+                .cells()
+        ),
+        cells
+    );
+
+    // a glider rotated a quarter turn still runs, in the rotated direction
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].map(|(x, y)| Coordinate { x, y });
+    let mut p = Playfield::<i64, u16>::from_cells(glider).rotate(Rotation::Quarter);
+    let start = sorted(p.cells());
+    for _ in 0..4 {
+        p = p.step(&crate::rule::Rule::conway());
+    }
+    let moved = start
+        .iter()
+        .map(|c| Coordinate {
+            x: c.x - 1,
+            y: c.y + 1,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(sorted(p.cells()), moved);
+}
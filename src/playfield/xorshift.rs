@@ -0,0 +1,21 @@
+#![cfg(test)]
+
+/// xorshift pseudo-random numbers, so property tests are repeatable
+pub struct XorShift(pub u64);
+
+impl XorShift {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// mostly zero, so rows have gaps
+    pub fn sparse(&mut self) -> u64 {
+        match self.next() % 3 {
+            0 => self.next(),
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,459 @@
+// TODO remove suppression for dead code warning
+#![allow(dead_code)]
+
+use super::grid::{strip_suffix_ignoring_case, Grid};
+use std::fmt;
+use std::str::FromStr;
+
+/// number of cells in a Moore neighbourhood, including the centre
+pub const N_CELLS: usize = 9;
+
+/// number of distinct configurations of the eight neighbours of a cell
+const N_CONFIGURATIONS: usize = 256;
+
+/// bit of the centre cell in a neighbourhood index
+const CENTRE: usize = 0x10;
+
+/// which neighbours count, as a subset of the Moore neighbourhood
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum NeighbourhoodKind {
+    /// all eight neighbours
+    #[default]
+    Moore,
+    /// the four neighbours which share an edge, selected by the `V` suffix
+    VonNeumann,
+    /// all but the top right and bottom left, emulating the hexagonal grid, selected by
+    /// the `H` suffix
+    Hexagonal,
+}
+
+impl NeighbourhoodKind {
+    /// the neighbours in a configuration which count
+    pub(crate) fn mask(self) -> usize {
+        match self {
+            NeighbourhoodKind::Moore => 0b11111111,
+            NeighbourhoodKind::VonNeumann => 0b01011010,
+            NeighbourhoodKind::Hexagonal => 0b11011011,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            NeighbourhoodKind::Moore => "",
+            NeighbourhoodKind::VonNeumann => "V",
+            NeighbourhoodKind::Hexagonal => Grid::Hexagonal.suffix(),
+        }
+    }
+
+    pub fn grid(self) -> Grid {
+        match self {
+            NeighbourhoodKind::Moore | NeighbourhoodKind::VonNeumann => Grid::Square,
+            NeighbourhoodKind::Hexagonal => Grid::Hexagonal,
+        }
+    }
+
+    /// split the suffix selecting the neighbourhood, in either case, from a rulestring
+    pub fn split_suffix(s: &str) -> (&str, NeighbourhoodKind) {
+        let s = s.trim();
+        for kind in [NeighbourhoodKind::VonNeumann, NeighbourhoodKind::Hexagonal] {
+            if let Some(rest) = strip_suffix_ignoring_case(s, kind.suffix()) {
+                return (rest, kind);
+            }
+        }
+        (s, NeighbourhoodKind::Moore)
+    }
+}
+
+/// representative neighbourhood for each Hensel letter, by neighbour count, in canonical order
+///
+/// Counts of 5 to 7 are the complements of those of 3 to 1, with the same letters.
+const HENSEL: [&[(char, usize)]; 5] = [
+    &[],
+    &[('c', 0b001_000_000), ('e', 0b010_000_000)],
+    &[
+        ('c', 0b001_000_001),
+        ('e', 0b010_001_000),
+        ('a', 0b011_000_000),
+        ('i', 0b010_000_010),
+        ('k', 0b010_000_001),
+        ('n', 0b001_000_100),
+    ],
+    &[
+        ('c', 0b001_000_101),
+        ('e', 0b010_001_010),
+        ('a', 0b011_001_000),
+        ('i', 0b111_000_000),
+        ('k', 0b010_001_100),
+        ('n', 0b011_000_001),
+        ('j', 0b011_100_000),
+        ('q', 0b011_000_100),
+        ('r', 0b011_000_010),
+        ('y', 0b010_000_101),
+    ],
+    &[
+        ('c', 0b101_000_101),
+        ('e', 0b010_101_010),
+        ('a', 0b011_001_001),
+        ('i', 0b011_000_011),
+        ('k', 0b011_100_001),
+        ('n', 0b111_000_001),
+        ('j', 0b011_100_010),
+        ('q', 0b011_001_100),
+        ('r', 0b011_001_010),
+        ('t', 0b111_000_010),
+        ('w', 0b011_100_100),
+        ('y', 0b011_000_101),
+        ('z', 0b011_000_110),
+    ],
+];
+
+/// the configuration of the neighbours in a 3x3 neighbourhood index, that is, without the centre
+fn configuration(index: usize) -> usize {
+    ((index >> 5) << 4) | (index & 0xf)
+}
+
+/// apply a mapping of (row, column) to each cell of a 3x3 neighbourhood index
+fn transformed<F>(index: usize, f: F) -> usize
+where
+    F: Fn(usize, usize) -> (usize, usize),
+{
+    (0..N_CELLS)
+        .filter(|bit| index & (1 << (N_CELLS - 1 - bit)) != 0)
+        .map(|bit| {
+            let (row, col) = f(bit / 3, bit % 3);
+            1 << (N_CELLS - 1 - (row * 3 + col))
+        })
+        .fold(0, |acc, b| acc | b)
+}
+
+/// all rotations and reflections of a 3x3 neighbourhood index
+fn symmetries(index: usize) -> Vec<usize> {
+    let mut images = Vec::new();
+    let mut rotated = index;
+    for _ in 0..4 {
+        images.push(rotated);
+        images.push(transformed(rotated, |row, col| (row, 2 - col)));
+        rotated = transformed(rotated, |row, col| (col, 2 - row));
+    }
+    images
+}
+
+/// the neighbour configurations for the Hensel letter with `n` neighbours, if valid
+fn hensel_configurations(n: usize, letter: char) -> Option<Vec<usize>> {
+    let (complement, letters) = if n < HENSEL.len() {
+        (false, HENSEL[n])
+    } else {
+        (true, *HENSEL.get(N_CELLS - 1 - n)?)
+    };
+
+    letters
+        .iter()
+        .find(|(l, _)| *l == letter)
+        .map(|(_, representative)| {
+            symmetries(*representative)
+                .into_iter()
+                .map(|index| {
+                    let c = configuration(index);
+                    if complement {
+                        !c & (N_CONFIGURATIONS - 1)
+                    } else {
+                        c
+                    }
+                })
+                .collect()
+        })
+}
+
+/// the Hensel letters for `n` neighbours, in canonical order
+fn hensel_letters(n: usize) -> impl Iterator<Item = char> {
+    HENSEL
+        .get(n.min(N_CELLS - 1 - n))
+        .copied()
+        .unwrap_or(&[])
+        .iter()
+        .map(|(letter, _)| *letter)
+}
+
+/// a rule on the Moore neighbourhood, either Life-like, that is outer totalistic,
+/// or isotropic non-totalistic, as given in Hensel notation, or an outer totalistic rule
+/// on another kind of neighbourhood, which is the Moore neighbourhood with some neighbours
+/// masked
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rule {
+    /// `birth[c]` is whether a dead cell with neighbour configuration `c` becomes live
+    birth: [bool; N_CONFIGURATIONS],
+    /// `survival[c]` is whether a live cell with neighbour configuration `c` stays live
+    survival: [bool; N_CONFIGURATIONS],
+    kind: NeighbourhoodKind,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum RuleError {
+    /// not recognisable as either B/S or S/B notation
+    Syntax(String),
+    /// neighbour count not in the range 0 to 8
+    Count(char),
+    /// not a Hensel letter for the preceding neighbour count
+    Letter(char),
+    /// birth on zero neighbours would fill the infinite playfield
+    BirthOnZero,
+    /// number of Generations states out of range
+    States(usize),
+    /// Larger than Life range out of range
+    Range(usize),
+    /// not a recognisable bounded grid suffix
+    Topology(String),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleError::Syntax(s) => write!(f, "invalid rulestring \"{}\"", s),
+            RuleError::Count(c) => write!(f, "invalid neighbour count '{}'", c),
+            RuleError::Letter(c) => write!(f, "invalid Hensel letter '{}'", c),
+            RuleError::BirthOnZero => write!(f, "B0 rules are not supported"),
+            RuleError::States(n) => write!(f, "invalid number of states {}", n),
+            RuleError::Range(r) => write!(f, "invalid range {}", r),
+            RuleError::Topology(s) => write!(f, "invalid topology \"{}\"", s),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+impl Rule {
+    /// create a Life-like rule from the neighbour counts for birth and survival
+    pub fn new(birth: &[usize], survival: &[usize]) -> Rule {
+        Rule::totalistic(birth, survival, NeighbourhoodKind::Moore)
+    }
+
+    /// create a rule on the hexagonal grid from the neighbour counts for birth and survival
+    pub fn hexagonal(birth: &[usize], survival: &[usize]) -> Rule {
+        Rule::totalistic(birth, survival, NeighbourhoodKind::Hexagonal)
+    }
+
+    /// create a rule on the von Neumann neighbourhood from the neighbour counts for birth
+    /// and survival
+    pub fn von_neumann(birth: &[usize], survival: &[usize]) -> Rule {
+        Rule::totalistic(birth, survival, NeighbourhoodKind::VonNeumann)
+    }
+
+    fn totalistic(birth: &[usize], survival: &[usize], kind: NeighbourhoodKind) -> Rule {
+        let mask = kind.mask();
+        let totalistic = |counts: &[usize]| {
+            let mut conditions = [false; N_CONFIGURATIONS];
+            for (c, condition) in conditions.iter_mut().enumerate() {
+                *condition = counts.contains(&((c & mask).count_ones() as usize));
+            }
+            conditions
+        };
+        Rule {
+            birth: totalistic(birth),
+            survival: totalistic(survival),
+            kind,
+        }
+    }
+
+    pub fn kind(&self) -> NeighbourhoodKind {
+        self.kind
+    }
+
+    pub fn grid(&self) -> Grid {
+        self.kind.grid()
+    }
+
+    /// Conway's Game of Life, B3/S23
+    pub fn conway() -> Rule {
+        Rule::new(&[3], &[2, 3])
+    }
+
+    /// whether a cell is born and survives by each count of its neighbours, if the rule
+    /// depends on the count alone
+    pub fn totalistic_counts(&self) -> Option<([bool; N_CELLS], [bool; N_CELLS])> {
+        let mask = self.kind.mask();
+        let counts = |conditions: &[bool; N_CONFIGURATIONS]| {
+            let mut counts = [None; N_CELLS];
+            for (c, condition) in conditions.iter().enumerate() {
+                let count = &mut counts[(c & mask).count_ones() as usize];
+                match count {
+                    Some(by_count) if by_count != condition => return None,
+                    _ => *count = Some(*condition),
+                }
+            }
+            Some(counts.map(|count| count.unwrap_or(false)))
+        };
+        Some((counts(&self.birth)?, counts(&self.survival)?))
+    }
+
+    /// lookup table from 3x3 neighbourhood index to next state of the centre cell
+    ///
+    /// The index has the top left cell as its most significant bit, so the centre is bit 4.
+    pub fn transition_table(&self) -> Vec<bool> {
+        (0..1 << N_CELLS)
+            .map(|index: usize| {
+                let c = configuration(index);
+                if index & CENTRE != 0 {
+                    self.survival[c]
+                } else {
+                    self.birth[c]
+                }
+            })
+            .collect()
+    }
+
+    /// parse the neighbour counts, in any order, each optionally qualified by Hensel letters
+    /// only on the Moore neighbourhood
+    fn parse_conditions(
+        s: &str,
+        kind: NeighbourhoodKind,
+    ) -> Result<[bool; N_CONFIGURATIONS], RuleError> {
+        let mask = kind.mask();
+        let mut conditions = [false; N_CONFIGURATIONS];
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let n = match c.to_digit(10) {
+                Some(n) if n <= mask.count_ones() => n as usize,
+                _ => return Err(RuleError::Count(c)),
+            };
+            let negated = chars.next_if_eq(&'-').is_some();
+            let mut letters = Vec::new();
+            while let Some(letter) = chars.next_if(|l| l.is_ascii_lowercase()) {
+                letters.push(letter);
+            }
+
+            if letters.is_empty() {
+                if negated {
+                    return Err(RuleError::Letter('-'));
+                }
+                for (c, condition) in conditions.iter_mut().enumerate() {
+                    if (c & mask).count_ones() as usize == n {
+                        *condition = true;
+                    }
+                }
+            } else if kind != NeighbourhoodKind::Moore {
+                return Err(RuleError::Letter(letters[0]));
+            } else {
+                let mut selected = [false; N_CONFIGURATIONS];
+                for letter in letters {
+                    for c in hensel_configurations(n, letter).ok_or(RuleError::Letter(letter))? {
+                        selected[c] = true;
+                    }
+                }
+                for (c, condition) in conditions.iter_mut().enumerate() {
+                    if c.count_ones() as usize == n && selected[c] != negated {
+                        *condition = true;
+                    }
+                }
+            }
+        }
+        Ok(conditions)
+    }
+
+    /// format conditions in canonical form, using whichever of the Hensel letters
+    /// or their negation is shorter, or as counts alone other than on the Moore neighbourhood
+    fn format_conditions(conditions: &[bool; N_CONFIGURATIONS], kind: NeighbourhoodKind) -> String {
+        let mut s = String::new();
+        if kind != NeighbourhoodKind::Moore {
+            // a configuration of `n` neighbours, adding one at a time
+            let mask = kind.mask();
+            let mut c = 0;
+            for n in 0..=mask.count_ones() {
+                if conditions[c] {
+                    s.push_str(&n.to_string());
+                }
+                let absent = mask & !c;
+                c |= absent & absent.wrapping_neg();
+            }
+            return s;
+        }
+        for n in 0..N_CELLS {
+            let (present, absent): (Vec<char>, Vec<char>) = hensel_letters(n)
+                .partition(|letter| conditions[hensel_configurations(n, *letter).unwrap()[0]]);
+
+            if absent.is_empty() {
+                // a count of 0 or 8 has only a single configuration, and no letters
+                let c = (1 << n) - 1;
+                if !present.is_empty() || conditions[c] {
+                    s.push_str(&n.to_string());
+                }
+            } else if !present.is_empty() {
+                s.push_str(&n.to_string());
+                if present.len() <= absent.len() {
+                    s.extend(present);
+                } else {
+                    s.push('-');
+                    s.extend(absent);
+                }
+            }
+        }
+        s
+    }
+
+    /// canonical B/S notation without the neighbourhood suffix, for rules which extend the notation
+    pub(crate) fn bs_notation(&self) -> String {
+        format!(
+            "B{}/S{}",
+            Rule::format_conditions(&self.birth, self.kind),
+            Rule::format_conditions(&self.survival, self.kind)
+        )
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::conway()
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    /// parse either `B3/S23` notation, in either order and any case, or `23/3` S/B notation,
+    /// with optional Hensel letters after each count, as in `B2n3/S23-q`, or followed by `H`
+    /// for the hexagonal grid, as in `B2/S34H`, or `V` for the von Neumann neighbourhood
+    fn from_str(s: &str) -> Result<Rule, RuleError> {
+        let syntax = || RuleError::Syntax(s.to_string());
+
+        let (bs, kind) = NeighbourhoodKind::split_suffix(s);
+        // triangular rules have their own type
+        if Grid::split_suffix(bs).1 == Grid::Triangular {
+            return Err(syntax());
+        }
+        let (lhs, rhs) = bs.split_once('/').ok_or_else(syntax)?;
+        let tagged = |part: &str| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| (c.to_ascii_uppercase(), chars.as_str().to_string()))
+        };
+
+        let (birth, survival) = match (tagged(lhs), tagged(rhs)) {
+            (Some(('B', b)), Some(('S', s))) | (Some(('S', s)), Some(('B', b))) => (b, s),
+            (Some(('B', _) | ('S', _)), _) | (_, Some(('B', _) | ('S', _))) => return Err(syntax()),
+            // S/B notation
+            _ => (rhs.to_string(), lhs.to_string()),
+        };
+
+        let rule = Rule {
+            birth: Rule::parse_conditions(&birth, kind)?,
+            survival: Rule::parse_conditions(&survival, kind)?,
+            kind,
+        };
+
+        if rule.birth[0] {
+            Err(RuleError::BirthOnZero)
+        } else {
+            Ok(rule)
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    /// canonical B/S notation, followed by the neighbourhood suffix if any
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.bs_notation(), self.kind.suffix())
+    }
+}
+
+mod tests;
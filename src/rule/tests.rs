@@ -0,0 +1,230 @@
+#![cfg(test)]
+use super::*;
+use crate::grid::Grid;
+
+#[test]
+fn test_parse_bs() {
+    assert_eq!("B3/S23".parse::<Rule>(), Ok(Rule::conway()));
+    assert_eq!("b3/s23".parse::<Rule>(), Ok(Rule::conway()));
+    assert_eq!("S23/B3".parse::<Rule>(), Ok(Rule::conway()));
+    assert_eq!("B36/S23".parse::<Rule>(), Ok(Rule::new(&[3, 6], &[2, 3])));
+    assert_eq!("B2/S".parse::<Rule>(), Ok(Rule::new(&[2], &[])));
+    assert_eq!(
+        "B3678/S34678".parse::<Rule>(),
+        Ok(Rule::new(&[3, 6, 7, 8], &[3, 4, 6, 7, 8]))
+    );
+}
+
+#[test]
+fn test_parse_sb() {
+    assert_eq!("23/3".parse::<Rule>(), Ok(Rule::conway()));
+    assert_eq!("23/36".parse::<Rule>(), Ok(Rule::new(&[3, 6], &[2, 3])));
+    assert_eq!("/2".parse::<Rule>(), Ok(Rule::new(&[2], &[])));
+    assert_eq!(
+        "34678/3678".parse::<Rule>(),
+        Ok(Rule::new(&[3, 6, 7, 8], &[3, 4, 6, 7, 8]))
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        "B3S23".parse::<Rule>(),
+        Err(RuleError::Syntax("B3S23".to_string()))
+    );
+    assert_eq!(
+        "B3/23".parse::<Rule>(),
+        Err(RuleError::Syntax("B3/23".to_string()))
+    );
+    assert_eq!("B39/S23".parse::<Rule>(), Err(RuleError::Count('9')));
+    assert_eq!("B3/S2+".parse::<Rule>(), Err(RuleError::Count('+')));
+    assert_eq!("B03/S23".parse::<Rule>(), Err(RuleError::BirthOnZero));
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(Rule::conway().to_string(), "B3/S23");
+    assert_eq!(
+        "34678/3678".parse::<Rule>().unwrap().to_string(),
+        "B3678/S34678"
+    );
+    assert_eq!("/2".parse::<Rule>().unwrap().to_string(), "B2/S");
+}
+
+#[test]
+fn test_transition_table() {
+    let table = Rule::conway().transition_table();
+
+    assert_eq!(table.len(), 512);
+    assert!(!table[0b000_010_000]);
+    assert!(table[0b000_011_100]);
+    assert!(table[0b100_110_000]);
+    assert!(table[0b101_000_100]);
+    assert!(!table[0b101_010_101]);
+}
+
+#[test]
+fn test_parse_hensel() {
+    assert_eq!("B2ceaikn3/S2".parse::<Rule>(), Ok(Rule::new(&[2, 3], &[2])));
+    assert_eq!("B3/S2-3".parse::<Rule>(), Err(RuleError::Letter('-')));
+    assert_eq!("B2x/S23".parse::<Rule>(), Err(RuleError::Letter('x')));
+    assert_eq!("B1t/S23".parse::<Rule>(), Err(RuleError::Letter('t')));
+    assert_eq!("B3/S8c".parse::<Rule>(), Err(RuleError::Letter('c')));
+
+    let rule = "B2n3/S23-q".parse::<Rule>().unwrap();
+    let table = rule.transition_table();
+
+    // 2n birth, opposite corners, but not 2c
+    assert!(table[0b100_000_001]);
+    assert!(table[0b001_000_100]);
+    assert!(!table[0b101_000_000]);
+
+    // 3q survival excluded in every orientation
+    assert!(!table[0b011_010_100]);
+    assert!(!table[0b100_011_001]);
+    assert!(!table[0b110_010_001]);
+    assert!(table[0b011_011_000]);
+    assert!(table[0b010_010_101]);
+    assert!(table[0b001_110_001]);
+}
+
+#[test]
+fn test_hensel_letters_partition_counts() {
+    for n in 0..N_CELLS {
+        let mut seen = [0; N_CONFIGURATIONS];
+        for letter in hensel_letters(n) {
+            for c in hensel_configurations(n, letter).unwrap() {
+                assert_eq!(c.count_ones() as usize, n);
+                seen[c] = 1;
+            }
+        }
+        let expected = (0..N_CONFIGURATIONS)
+            .filter(|c| c.count_ones() as usize == n)
+            .count();
+        if n == 0 || n == N_CELLS - 1 {
+            assert_eq!(seen.iter().sum::<usize>(), 0);
+        } else {
+            assert_eq!(seen.iter().sum::<usize>(), expected);
+        }
+    }
+}
+
+#[test]
+fn test_display_hensel() {
+    let canonical = |s: &str| s.parse::<Rule>().unwrap().to_string();
+
+    assert_eq!(canonical("B2n3/S23-q"), "B2n3/S23-q");
+    assert_eq!(canonical("B3/S2-an3"), "B3/S2-an3");
+    assert_eq!(canonical("B3/S2ceik3"), "B3/S2-an3");
+    assert_eq!(canonical("B3/S2cek3"), "B3/S2cek3");
+    assert_eq!(canonical("B3aceiknjqry/S2"), "B3/S2");
+    assert_eq!(canonical("B35-c/S1c8"), "B35-c/S1c8");
+    assert_eq!(canonical("B4ceaikn/S4ty"), "B4ceaikn/S4ty");
+}
+
+#[test]
+fn test_parse_hexagonal() {
+    assert_eq!(
+        "B2/S34H".parse::<Rule>(),
+        Ok(Rule::hexagonal(&[2], &[3, 4]))
+    );
+    assert_eq!("34/2h".parse::<Rule>(), Ok(Rule::hexagonal(&[2], &[3, 4])));
+    assert_ne!("B2/S34H".parse::<Rule>(), "B2/S34".parse::<Rule>());
+    assert_eq!("B2/S34H".parse::<Rule>().unwrap().grid(), Grid::Hexagonal);
+
+    assert_eq!(Rule::hexagonal(&[2], &[3, 4]).to_string(), "B2/S34H");
+    assert_eq!(
+        "B2456/S0123456H".parse::<Rule>().unwrap().to_string(),
+        "B2456/S0123456H"
+    );
+
+    assert_eq!("B27/S34H".parse::<Rule>(), Err(RuleError::Count('7')));
+    assert_eq!("B2a/S34H".parse::<Rule>(), Err(RuleError::Letter('a')));
+    assert_eq!(
+        "B2/S34L".parse::<Rule>(),
+        Err(RuleError::Syntax("B2/S34L".to_string()))
+    );
+}
+
+#[test]
+fn test_hexagonal_transition_table() {
+    let table = Rule::hexagonal(&[2], &[]).transition_table();
+
+    // the top right and bottom left cells are not neighbours
+    assert!(!table[0b001_000_100]);
+    assert!(table[0b110_000_000]);
+    assert!(!table[0b011_000_000]);
+    assert!(table[0b111_000_100]);
+    assert!(!table[0b111_000_110]);
+    assert!(table[0b000_100_001]);
+}
+
+#[test]
+fn test_parse_von_neumann() {
+    assert_eq!(
+        "B1/S012V".parse::<Rule>(),
+        Ok(Rule::von_neumann(&[1], &[0, 1, 2]))
+    );
+    assert_eq!(
+        "012/1v".parse::<Rule>(),
+        Ok(Rule::von_neumann(&[1], &[0, 1, 2]))
+    );
+    let rule = "B13/S024V".parse::<Rule>().unwrap();
+    assert_eq!(rule.kind(), NeighbourhoodKind::VonNeumann);
+    assert_eq!(rule.grid(), Grid::Square);
+    assert_eq!(rule.to_string(), "B13/S024V");
+
+    assert_eq!("B15/S0V".parse::<Rule>(), Err(RuleError::Count('5')));
+    assert_eq!("B2c/S0V".parse::<Rule>(), Err(RuleError::Letter('c')));
+    assert_eq!(
+        "B3/S23".parse::<Rule>().unwrap().kind(),
+        NeighbourhoodKind::Moore
+    );
+}
+
+#[test]
+fn test_von_neumann_transition_table() {
+    let table = Rule::von_neumann(&[2], &[]).transition_table();
+
+    // only the cells sharing an edge are neighbours
+    assert!(table[0b010_100_000]);
+    assert!(table[0b000_101_000]);
+    assert!(!table[0b101_000_101]);
+    assert!(!table[0b111_000_000]);
+    assert!(table[0b111_000_010]);
+}
+
+#[test]
+fn test_totalistic_counts() {
+    let (birth, survival) = Rule::conway().totalistic_counts().unwrap();
+    assert_eq!(
+        birth,
+        [false, false, false, true, false, false, false, false, false]
+    );
+    assert_eq!(
+        survival,
+        [false, false, true, true, false, false, false, false, false]
+    );
+
+    let (birth, _) = "B2/S34H"
+        .parse::<Rule>()
+        .unwrap()
+        .totalistic_counts()
+        .unwrap();
+    assert_eq!(
+        birth,
+        [false, false, true, false, false, false, false, false, false]
+    );
+
+    // spelling out every letter is still totalistic
+    assert!("B3/S2-ac3"
+        .parse::<Rule>()
+        .unwrap()
+        .totalistic_counts()
+        .is_none());
+    assert!("B3/S2cekain3"
+        .parse::<Rule>()
+        .unwrap()
+        .totalistic_counts()
+        .is_some());
+}
@@ -0,0 +1,209 @@
+use super::cartesian::Coordinate;
+use super::rule::RuleError;
+use num::FromPrimitive;
+use num::Integer;
+use std::fmt;
+use std::str::FromStr;
+
+/// the surface on which cells live, as given by Golly's rule suffixes such as `:T20,30`
+///
+/// Bounded grids are `width` by `height` cells, with the top left cell at
+/// `-(width / 2)`, `-(height / 2)`, as in Golly.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Topology {
+    /// the infinite plane
+    #[default]
+    Plane,
+    /// a finite plane, beyond whose edges all cells are dead, `:P`
+    BoundedPlane { width: usize, height: usize },
+    /// opposite edges are joined, `:T`
+    Torus { width: usize, height: usize },
+    /// opposite edges are joined, one pair of them with a twist, `:K`
+    KleinBottle {
+        width: usize,
+        height: usize,
+        /// whether the top and bottom edges rather than the left and right are twisted,
+        /// written with `*` after the width rather than the height
+        horizontal_twist: bool,
+    },
+    /// opposite edges are joined, both pairs with a twist, `:C`
+    ///
+    /// Corner cells have only seven neighbours, as the cell diagonally across the corner
+    /// would be the corner cell itself.
+    CrossSurface { width: usize, height: usize },
+}
+
+impl Topology {
+    /// width and height, unless unbounded
+    pub fn size(&self) -> Option<(usize, usize)> {
+        match *self {
+            Topology::Plane => None,
+            Topology::BoundedPlane { width, height }
+            | Topology::Torus { width, height }
+            | Topology::KleinBottle { width, height, .. }
+            | Topology::CrossSurface { width, height } => Some((width, height)),
+        }
+    }
+
+    /// whether the top and bottom, and the left and right edges are joined with a twist,
+    /// for those topologies whose edges are joined
+    fn twists(&self) -> Option<(bool, bool)> {
+        match *self {
+            Topology::Plane | Topology::BoundedPlane { .. } => None,
+            Topology::Torus { .. } => Some((false, false)),
+            Topology::KleinBottle {
+                horizontal_twist, ..
+            } => Some((horizontal_twist, !horizontal_twist)),
+            Topology::CrossSurface { .. } => Some((true, true)),
+        }
+    }
+
+    /// the top left and bottom right cells, unless unbounded
+    pub fn bounds<Idx>(&self) -> Option<(Coordinate<Idx>, Coordinate<Idx>)>
+    where
+        Idx: Copy + Default + Integer + FromPrimitive,
+    {
+        self.size().map(|(width, height)| {
+            let from = |u: usize| Idx::from_usize(u).unwrap();
+            let min = Coordinate {
+                x: Idx::zero() - from(width / 2),
+                y: Idx::zero() - from(height / 2),
+            };
+            let max = Coordinate {
+                x: min.x + from(width) - Idx::one(),
+                y: min.y + from(height) - Idx::one(),
+            };
+            (min, max)
+        })
+    }
+
+    /// whether the cell at `x`, `y` is on the surface
+    pub fn contains<Idx>(&self, x: Idx, y: Idx) -> bool
+    where
+        Idx: Copy + Default + Integer + FromPrimitive,
+    {
+        self.bounds()
+            .is_none_or(|(min, max)| min.x <= x && x <= max.x && min.y <= y && y <= max.y)
+    }
+
+    /// whether the whole neighbourhood of the cell at `x`, `y` is on the surface without
+    /// crossing an edge
+    pub fn is_interior<Idx>(&self, x: Idx, y: Idx) -> bool
+    where
+        Idx: Copy + Default + Integer + FromPrimitive,
+    {
+        self.bounds()
+            .is_none_or(|(min, max)| min.x < x && x < max.x && min.y < y && y < max.y)
+    }
+
+    /// the cell on the surface which is reached at `x`, `y` by crossing edges, if any
+    ///
+    /// Crossing the top or bottom edge with a twist mirrors the column, and crossing the
+    /// left or right edge with a twist mirrors the row.
+    pub fn wrap<Idx>(&self, x: Idx, y: Idx) -> Option<Coordinate<Idx>>
+    where
+        Idx: Copy + Default + Integer + FromPrimitive,
+    {
+        let (min, max) = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return Some(Coordinate { x, y }),
+        };
+        if self.contains(x, y) {
+            return Some(Coordinate { x, y });
+        }
+        let (twisted_rows, twisted_columns) = self.twists()?;
+        let outside_x = x < min.x || max.x < x;
+        let outside_y = y < min.y || max.y < y;
+        if twisted_rows && twisted_columns && outside_x && outside_y {
+            return None;
+        }
+
+        let width = max.x - min.x + Idx::one();
+        let height = max.y - min.y + Idx::one();
+        let (crossings_x, mut dx) = (x - min.x).div_mod_floor(&width);
+        let (crossings_y, mut dy) = (y - min.y).div_mod_floor(&height);
+        if twisted_rows && crossings_y.is_odd() {
+            dx = width - Idx::one() - dx;
+        }
+        if twisted_columns && crossings_x.is_odd() {
+            dy = height - Idx::one() - dy;
+        }
+        Some(Coordinate {
+            x: min.x + dx,
+            y: min.y + dy,
+        })
+    }
+}
+
+/// split a rulestring such as `B3/S23:T20,30` into the rule and its topology
+pub fn split_rule(s: &str) -> Result<(&str, Topology), RuleError> {
+    match s.split_once(':') {
+        Some((rule, suffix)) => Ok((rule, suffix.parse()?)),
+        None => Ok((s, Topology::Plane)),
+    }
+}
+
+impl FromStr for Topology {
+    type Err = RuleError;
+
+    /// parse a Golly suffix without the colon, such as `T20,30`, `K20*,30` or `P20`,
+    /// where a missing height is the same as the width
+    fn from_str(s: &str) -> Result<Topology, RuleError> {
+        let error = || RuleError::Topology(s.to_string());
+
+        let s = s.trim();
+        let mut chars = s.chars();
+        let key = chars.next().ok_or_else(error)?.to_ascii_uppercase();
+        let (width, height) = match chars.as_str().split_once(',') {
+            Some((width, height)) => (width, height),
+            None => (chars.as_str(), chars.as_str()),
+        };
+        let dimension = |d: &str| {
+            let twisted = d.ends_with('*');
+            match d.trim_end_matches('*').parse::<usize>() {
+                Ok(n) if n > 0 => Ok((n, twisted)),
+                _ => Err(error()),
+            }
+        };
+        let ((width, width_twisted), (height, height_twisted)) =
+            (dimension(width)?, dimension(height)?);
+
+        match (key, width_twisted, height_twisted) {
+            ('P', false, false) => Ok(Topology::BoundedPlane { width, height }),
+            ('T', false, false) => Ok(Topology::Torus { width, height }),
+            ('K', true, false) | ('K', false, true) => Ok(Topology::KleinBottle {
+                width,
+                height,
+                horizontal_twist: width_twisted,
+            }),
+            ('C', false, false) => Ok(Topology::CrossSurface { width, height }),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl fmt::Display for Topology {
+    /// canonical Golly suffix, including the colon, and empty for the infinite plane
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Topology::Plane => Ok(()),
+            Topology::BoundedPlane { width, height } => write!(f, ":P{},{}", width, height),
+            Topology::Torus { width, height } => write!(f, ":T{},{}", width, height),
+            Topology::KleinBottle {
+                width,
+                height,
+                horizontal_twist,
+            } => {
+                let (width_twist, height_twist) = if horizontal_twist {
+                    ("*", "")
+                } else {
+                    ("", "*")
+                };
+                write!(f, ":K{}{},{}{}", width, width_twist, height, height_twist)
+            }
+            Topology::CrossSurface { width, height } => write!(f, ":C{},{}", width, height),
+        }
+    }
+}
+
+mod tests;
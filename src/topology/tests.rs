@@ -0,0 +1,188 @@
+#![cfg(test)]
+use super::*;
+
+fn c(x: i32, y: i32) -> Option<Coordinate<i32>> {
+    Some(Coordinate { x, y })
+}
+
+#[test]
+fn test_parse_topology() {
+    assert_eq!(
+        "T20,30".parse::<Topology>(),
+        Ok(Topology::Torus {
+            width: 20,
+            height: 30
+        })
+    );
+    assert_eq!(
+        "p7".parse::<Topology>(),
+        Ok(Topology::BoundedPlane {
+            width: 7,
+            height: 7
+        })
+    );
+    assert_eq!(
+        "K10*,20".parse::<Topology>(),
+        Ok(Topology::KleinBottle {
+            width: 10,
+            height: 20,
+            horizontal_twist: true
+        })
+    );
+    assert_eq!(
+        "K10,20*".parse::<Topology>(),
+        Ok(Topology::KleinBottle {
+            width: 10,
+            height: 20,
+            horizontal_twist: false
+        })
+    );
+    assert_eq!(
+        "C4,6".parse::<Topology>(),
+        Ok(Topology::CrossSurface {
+            width: 4,
+            height: 6
+        })
+    );
+
+    for s in ["", "T", "T0,5", "T5,x", "K5,5", "K5*,5*", "T5*,5", "Q5,5"] {
+        assert_eq!(
+            s.parse::<Topology>(),
+            Err(RuleError::Topology(s.to_string()))
+        );
+    }
+}
+
+#[test]
+fn test_display_topology() {
+    for s in [":T20,30", ":P7,7", ":K10*,20", ":K10,20*", ":C4,6"] {
+        assert_eq!(s[1..].parse::<Topology>().unwrap().to_string(), s);
+    }
+    assert_eq!(Topology::Plane.to_string(), "");
+}
+
+#[test]
+fn test_split_rule() {
+    assert_eq!(split_rule("B3/S23"), Ok(("B3/S23", Topology::Plane)));
+    assert_eq!(
+        split_rule("B3/S23:T8,6"),
+        Ok((
+            "B3/S23",
+            Topology::Torus {
+                width: 8,
+                height: 6
+            }
+        ))
+    );
+    assert_eq!(
+        split_rule("B3/S23:X"),
+        Err(RuleError::Topology("X".to_string()))
+    );
+}
+
+#[test]
+fn test_bounds() {
+    let even = Topology::Torus {
+        width: 4,
+        height: 6,
+    };
+    assert_eq!(
+        even.bounds::<i32>(),
+        Some((c(-2, -3).unwrap(), c(1, 2).unwrap()))
+    );
+    let odd = Topology::BoundedPlane {
+        width: 5,
+        height: 1,
+    };
+    assert_eq!(
+        odd.bounds::<i32>(),
+        Some((c(-2, 0).unwrap(), c(2, 0).unwrap()))
+    );
+    assert_eq!(Topology::Plane.bounds::<i32>(), None);
+
+    assert!(even.contains(1, -3));
+    assert!(!even.contains(2, -3));
+    assert!(even.is_interior(0, 1));
+    assert!(!even.is_interior(0, 2));
+    assert!(Topology::Plane.is_interior(1000, -1000));
+}
+
+#[test]
+fn test_wrap() {
+    // columns -2 to 1 and rows -3 to 2
+    let (width, height) = (4, 6);
+
+    let plane = Topology::BoundedPlane { width, height };
+    assert_eq!(plane.wrap(0, 0), c(0, 0));
+    assert_eq!(plane.wrap(2, 0), None);
+
+    let torus = Topology::Torus { width, height };
+    assert_eq!(torus.wrap(2, 0), c(-2, 0));
+    assert_eq!(torus.wrap(-3, -4), c(1, 2));
+    assert_eq!(torus.wrap(10, 9), c(-2, -3));
+
+    let horizontal = Topology::KleinBottle {
+        width,
+        height,
+        horizontal_twist: true,
+    };
+    assert_eq!(horizontal.wrap(2, 0), c(-2, 0));
+    assert_eq!(horizontal.wrap(-2, 3), c(1, -3));
+    assert_eq!(horizontal.wrap(0, -4), c(-1, 2));
+    assert_eq!(horizontal.wrap(2, 3), c(1, -3));
+
+    let vertical = Topology::KleinBottle {
+        width,
+        height,
+        horizontal_twist: false,
+    };
+    assert_eq!(vertical.wrap(0, 3), c(0, -3));
+    assert_eq!(vertical.wrap(2, -3), c(-2, 2));
+    assert_eq!(vertical.wrap(-3, 0), c(1, -1));
+
+    let cross = Topology::CrossSurface { width, height };
+    assert_eq!(cross.wrap(2, -3), c(-2, 2));
+    assert_eq!(cross.wrap(-2, 3), c(1, -3));
+    assert_eq!(cross.wrap(2, 3), None);
+    assert_eq!(cross.wrap(-3, -4), None);
+
+    assert_eq!(Topology::Plane.wrap(100, -100), c(100, -100));
+}
+
+#[test]
+fn test_wrap_is_symmetric() {
+    // a cell is a neighbour of each of its neighbours
+    for topology in ["T5,4", "P5,4", "K5*,4", "K5,4*", "C5,4", "T3,3", "C4,4"] {
+        let topology = topology.parse::<Topology>().unwrap();
+        let (min, max) = topology.bounds::<i32>().unwrap();
+        let neighbours = |x: i32, y: i32| {
+            let mut images = Vec::new();
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if (dx, dy) != (0, 0) {
+                        images.extend(topology.wrap(x + dx, y + dy));
+                    }
+                }
+            }
+            images
+        };
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                for n in neighbours(x, y) {
+                    let count = |cells: Vec<Coordinate<i32>>, c: Coordinate<i32>| {
+                        cells.iter().filter(|cell| **cell == c).count()
+                    };
+                    assert_eq!(
+                        count(neighbours(x, y), n),
+                        count(neighbours(n.x, n.y), Coordinate { x, y }),
+                        "{} ({}, {}) and {:?}",
+                        topology,
+                        x,
+                        y,
+                        n
+                    );
+                }
+            }
+        }
+    }
+}
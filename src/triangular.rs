@@ -0,0 +1,166 @@
+// TODO remove suppression for dead code warning
+#![allow(dead_code)]
+
+use super::generations::{GenerationsPlayfield, LIVE};
+use super::grid::Grid;
+use super::rule::RuleError;
+use num::cast::AsPrimitive;
+use num::FromPrimitive;
+use num::Integer;
+use num::One;
+use num::Zero;
+use std::cmp::PartialOrd;
+use std::fmt;
+use std::fmt::Debug;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Sub;
+use std::ops::SubAssign;
+use std::str::FromStr;
+
+/// number of neighbours of a triangular cell, sharing either an edge or a vertex
+pub const N_NEIGHBOURS: usize = 12;
+
+/// digits of neighbour counts, where those above 9 continue as in hexadecimal
+const DIGITS: [char; N_NEIGHBOURS + 1] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c',
+];
+
+/// an outer totalistic rule on the triangular grid, as given by the `L` suffix
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TriangularRule {
+    /// `birth[n]` is whether a dead cell with `n` live neighbours becomes live
+    birth: [bool; N_NEIGHBOURS + 1],
+    /// `survival[n]` is whether a live cell with `n` live neighbours stays live
+    survival: [bool; N_NEIGHBOURS + 1],
+}
+
+impl TriangularRule {
+    /// create from the neighbour counts for birth and survival
+    pub fn new(birth: &[usize], survival: &[usize]) -> TriangularRule {
+        let conditions = |counts: &[usize]| std::array::from_fn(|n| counts.contains(&n));
+        TriangularRule {
+            birth: conditions(birth),
+            survival: conditions(survival),
+        }
+    }
+
+    /// whether a cell which is `live` with `count` live neighbours is live next
+    pub fn next_live(&self, live: bool, count: usize) -> bool {
+        if live {
+            self.survival[count]
+        } else {
+            self.birth[count]
+        }
+    }
+
+    fn parse_counts(s: &str) -> Result<[bool; N_NEIGHBOURS + 1], RuleError> {
+        let mut conditions = [false; N_NEIGHBOURS + 1];
+        for c in s.chars() {
+            let n = DIGITS
+                .iter()
+                .position(|digit| *digit == c.to_ascii_lowercase())
+                .ok_or(RuleError::Count(c))?;
+            conditions[n] = true;
+        }
+        Ok(conditions)
+    }
+
+    fn format_counts(conditions: &[bool; N_NEIGHBOURS + 1]) -> String {
+        DIGITS
+            .iter()
+            .zip(conditions.iter())
+            .filter(|(_, condition)| **condition)
+            .map(|(digit, _)| *digit)
+            .collect()
+    }
+}
+
+impl FromStr for TriangularRule {
+    type Err = RuleError;
+
+    /// parse `B4/S456L` or `456/4L` notation, where the counts 10 to 12 are `a` to `c`
+    fn from_str(s: &str) -> Result<TriangularRule, RuleError> {
+        let syntax = || RuleError::Syntax(s.to_string());
+
+        let bs = match Grid::split_suffix(s) {
+            (bs, Grid::Triangular) => bs,
+            _ => return Err(syntax()),
+        };
+        let (lhs, rhs) = bs.split_once('/').ok_or_else(syntax)?;
+        let tag = |part: &str| part.chars().next().map(|c| c.to_ascii_uppercase());
+        let (birth, survival) = match (tag(lhs), tag(rhs)) {
+            (Some('B'), Some('S')) => (&lhs[1..], &rhs[1..]),
+            (Some('S'), Some('B')) => (&rhs[1..], &lhs[1..]),
+            (Some('B' | 'S'), _) | (_, Some('B' | 'S')) => return Err(syntax()),
+            // S/B notation
+            _ => (rhs, lhs),
+        };
+
+        let rule = TriangularRule {
+            birth: TriangularRule::parse_counts(birth)?,
+            survival: TriangularRule::parse_counts(survival)?,
+        };
+        if rule.birth[0] {
+            Err(RuleError::BirthOnZero)
+        } else {
+            Ok(rule)
+        }
+    }
+}
+
+impl fmt::Display for TriangularRule {
+    /// canonical B/S notation with the `L` suffix
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "B{}/S{}{}",
+            TriangularRule::format_counts(&self.birth),
+            TriangularRule::format_counts(&self.survival),
+            Grid::Triangular.suffix()
+        )
+    }
+}
+
+impl<Idx> GenerationsPlayfield<Idx>
+where
+    Idx: Copy
+        + Default
+        + One
+        + FromPrimitive
+        + AsPrimitive<usize>
+        + Add<Output = Idx>
+        + Sub<Output = Idx>
+        + PartialOrd
+        + AddAssign
+        + SubAssign
+        + Zero
+        + Ord
+        + Integer
+        + Debug,
+{
+    /// compute the next generation under `rule` on the triangular grid, where only live
+    /// cells count, and any other cell is dead
+    pub fn step_triangular(&self, rule: &TriangularRule) -> GenerationsPlayfield<Idx> {
+        let mut next = GenerationsPlayfield::<Idx>::new();
+
+        if let Some(cc) = &self.cc {
+            let radius = Grid::Triangular.radius();
+            for nbh in cc.neighbourhood_enumerator_with_radius::<2>() {
+                let live = nbh.items[radius][radius] == Some(&LIVE);
+                let count = nbh
+                    .neighbours(Grid::Triangular)
+                    .into_iter()
+                    .filter(|cell| **cell == Some(&LIVE))
+                    .count();
+                if rule.next_live(live, count) {
+                    next.set_cell(nbh.i_col, nbh.i_row, LIVE);
+                }
+            }
+        }
+
+        next
+    }
+}
+
+mod tests;
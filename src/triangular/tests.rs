@@ -0,0 +1,110 @@
+#![cfg(test)]
+use super::*;
+use crate::cartesian::Coordinate;
+use crate::generations::{State, DEAD};
+
+#[test]
+fn test_parse_triangular_rule() {
+    assert_eq!(
+        "B4/S456L".parse::<TriangularRule>(),
+        Ok(TriangularRule::new(&[4], &[4, 5, 6]))
+    );
+    assert_eq!(
+        "s456/b4l".parse::<TriangularRule>(),
+        Ok(TriangularRule::new(&[4], &[4, 5, 6]))
+    );
+    assert_eq!(
+        "9abc/4L".parse::<TriangularRule>(),
+        Ok(TriangularRule::new(&[4], &[9, 10, 11, 12]))
+    );
+
+    assert_eq!(
+        "B4/S456".parse::<TriangularRule>(),
+        Err(RuleError::Syntax("B4/S456".to_string()))
+    );
+    assert_eq!(
+        "B4/S45dL".parse::<TriangularRule>(),
+        Err(RuleError::Count('d'))
+    );
+    assert_eq!(
+        "B04/S456L".parse::<TriangularRule>(),
+        Err(RuleError::BirthOnZero)
+    );
+}
+
+#[test]
+fn test_display_triangular_rule() {
+    assert_eq!(
+        TriangularRule::new(&[4], &[4, 5, 6]).to_string(),
+        "B4/S456L"
+    );
+    assert_eq!(
+        "cba9/4L".parse::<TriangularRule>().unwrap().to_string(),
+        "B4/S9abcL"
+    );
+}
+
+/// live cells sorted by row then column
+fn live_cells(p: &GenerationsPlayfield<i32>) -> Vec<(i32, i32)> {
+    let (rows, origin) = p.to_rows();
+    let mut cells = Vec::new();
+    for (y, row) in (origin.y..).zip(rows.iter()) {
+        for (x, state) in (origin.x..).zip(row.iter()) {
+            if *state == LIVE {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+/// step by counting the neighbours of every cell near a live cell
+fn naive_step(cells: &[(i32, i32)], rule: &TriangularRule) -> Vec<(i32, i32)> {
+    let neighbours = |x: i32, y: i32| {
+        Grid::Triangular
+            .offsets((x + y) % 2 == 0)
+            .into_iter()
+            .map(move |(dx, dy)| (x + dx as i32, y + dy as i32))
+    };
+    let mut candidates = cells
+        .iter()
+        .flat_map(|(x, y)| neighbours(*x, *y).chain([(*x, *y)]))
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|(x, y)| (*y, *x));
+    candidates.dedup();
+
+    candidates
+        .into_iter()
+        .filter(|(x, y)| {
+            let count = neighbours(*x, *y).filter(|n| cells.contains(n)).count();
+            rule.next_live(cells.contains(&(*x, *y)), count)
+        })
+        .collect()
+}
+
+#[test]
+fn test_step_agrees_with_naive_counting() {
+    let mut seed = 7u32;
+    let mut random = || {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16).is_multiple_of(3)
+    };
+    let rows = (0..10)
+        .map(|_| {
+            (0..10)
+                .map(|_| if random() { LIVE } else { DEAD })
+                .collect::<Vec<State>>()
+        })
+        .collect::<Vec<_>>();
+
+    for rule in ["B4/S456L", "B3/S2345L", "B35/S4567L", "B1/S12L"] {
+        let rule = rule.parse::<TriangularRule>().unwrap();
+        // an odd origin, so that orientation does not follow from the rows
+        let mut p = GenerationsPlayfield::<i32>::from_rows(&rows, Coordinate { x: -3, y: 2 });
+        for generation in 0..5 {
+            let expected = naive_step(&live_cells(&p), &rule);
+            p = p.step_triangular(&rule);
+            assert_eq!(live_cells(&p), expected, "{} {}", rule, generation);
+        }
+    }
+}